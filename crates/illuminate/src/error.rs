@@ -14,14 +14,66 @@ pub enum DeviceError {
     NotMeetRequirement,
     #[error("other reason: {0}")]
     Other(&'static str),
-    #[error(transparent)]
+    #[error("{limit} is {max}, but {value} is requested")]
+    LimitExceeded {
+        limit: &'static str,
+        value: u32,
+        max: u32,
+    },
+    #[error("vertex attribute at location {location} uses {format:?}, which is not a vertex buffer format")]
+    #[cfg(feature = "vulkan")]
+    InvalidVertexAttributeFormat {
+        location: u32,
+        format: ash::vk::Format,
+    },
+    #[error("{samples:?} samples are not supported, supported sample counts: {supported:?}")]
+    #[cfg(feature = "vulkan")]
+    UnsupportedSampleCount {
+        samples: ash::vk::SampleCountFlags,
+        supported: ash::vk::SampleCountFlags,
+    },
+    #[error("{}", describe_vk_result(.0))]
     #[cfg(all(feature = "vulkan"))]
     VulkanError(#[from] ash::vk::Result),
+    #[error("{call} failed: {}", describe_vk_result(.result))]
+    #[cfg(feature = "vulkan")]
+    VulkanCall {
+        call: &'static str,
+        result: ash::vk::Result,
+    },
     #[error(transparent)]
     #[cfg(all(feature = "dx12"))]
     Dx12Error(#[from] windows::core::Error),
 }
 
+/// The spec name of `result`, e.g. `ERROR_DEVICE_LOST`, followed by the description ash has
+/// for core results, so logs are readable without looking up the raw value.
+#[cfg(feature = "vulkan")]
+pub fn describe_vk_result(result: &ash::vk::Result) -> String {
+    let name = format!("{:?}", result);
+    let description = result.to_string();
+    if name.parse::<i32>().is_ok() {
+        format!("unknown VkResult {}", name)
+    } else if description == name {
+        name
+    } else {
+        format!("{} ({})", name, description)
+    }
+}
+
+/// Attaches the name of the failing Vulkan call, e.g. `result.vulkan_call("vkCreateImage")`.
+#[cfg(feature = "vulkan")]
+pub(crate) trait VulkanCallExt<T> {
+    fn vulkan_call(self, call: &'static str) -> Result<T, DeviceError>;
+}
+
+#[cfg(feature = "vulkan")]
+impl<T> VulkanCallExt<T> for Result<T, ash::vk::Result> {
+    fn vulkan_call(self, call: &'static str) -> Result<T, DeviceError> {
+        self.map_err(|result| DeviceError::VulkanCall { call, result })
+    }
+}
+
 #[derive(Clone, Debug, Eq, PartialEq, Error)]
 pub enum SurfaceError {
     #[error("A surface is no longer available")]
@@ -40,9 +92,20 @@ pub enum SurfaceError {
 pub enum ShaderError {
     #[error("compilation failed: {0:?}")]
     Compilation(String),
+    #[error("spirv reflection failed: {0}")]
+    Reflection(String),
+    #[error("invalid spirv: {0}")]
+    InvalidSpirv(&'static str),
+    #[error("entry point {name:?} for stage {stage:?} not found, available: {available:?}")]
+    #[cfg(feature = "vulkan")]
+    EntryPointNotFound {
+        name: String,
+        stage: ash::vk::ShaderStageFlags,
+        available: Vec<String>,
+    },
     #[error(transparent)]
     Device(#[from] DeviceError),
-    #[error(transparent)]
+    #[error("{}", describe_vk_result(.0))]
     #[cfg(all(feature = "vulkan"))]
     VulkanError(#[from] ash::vk::Result),
     #[error(transparent)]
@@ -54,7 +117,7 @@ pub enum ShaderError {
 pub enum InstanceError {
     #[error("Not supported")]
     NotSupport(),
-    #[error(transparent)]
+    #[error("{}", describe_vk_result(.0))]
     #[cfg(all(feature = "vulkan"))]
     VulkanError(#[from] ash::vk::Result),
     #[error(transparent)]
@@ -69,3 +132,35 @@ pub enum PipelineError {
     #[error(transparent)]
     Device(#[from] DeviceError),
 }
+
+#[cfg(all(test, feature = "vulkan"))]
+mod tests {
+    use super::*;
+    use ash::vk;
+
+    #[test]
+    fn known_result_has_name_and_description() {
+        let text = describe_vk_result(&vk::Result::ERROR_DEVICE_LOST);
+        assert!(text.starts_with("ERROR_DEVICE_LOST ("), "{text}");
+        assert!(text.ends_with(')'), "{text}");
+    }
+
+    #[test]
+    fn unknown_result_shows_the_raw_value() {
+        assert_eq!(
+            describe_vk_result(&vk::Result::from_raw(-123_456)),
+            "unknown VkResult -123456"
+        );
+    }
+
+    #[test]
+    fn vulkan_call_error_names_the_call() {
+        let error = DeviceError::VulkanCall {
+            call: "vkCreateBuffer",
+            result: vk::Result::ERROR_OUT_OF_DEVICE_MEMORY,
+        };
+        assert!(error
+            .to_string()
+            .starts_with("vkCreateBuffer failed: ERROR_OUT_OF_DEVICE_MEMORY"));
+    }
+}
@@ -40,6 +40,47 @@ pub struct AdapterRequirements {
     pub sample_rate_shading: bool,
     #[builder(default = true)]
     pub discrete_gpu: bool,
+    #[builder(default = false)]
+    pub multi_draw_indirect: bool,
+    #[builder(default = false)]
+    pub geometry_shader: bool,
+    #[builder(default = false)]
+    pub tessellation_shader: bool,
+    #[builder(default = false)]
+    pub depth_bounds: bool,
+    /// More than one viewport and scissor per pipeline, e.g. for split screen or cube map faces.
+    #[builder(default = false)]
+    pub multi_viewport: bool,
+    /// Different blend states per color attachment of a pipeline.
+    #[builder(default = false)]
+    pub independent_blend: bool,
+    /// `LINE` and `POINT` polygon modes, e.g. for wireframe rendering.
+    #[builder(default = false)]
+    pub fill_mode_non_solid: bool,
+    /// Line widths other than 1.0, see `Device::cmd_set_line_width`.
+    #[builder(default = false)]
+    pub wide_lines: bool,
+    /// A non-zero depth bias clamp, see `Device::cmd_set_depth_bias`.
+    #[builder(default = false)]
+    pub depth_bias_clamp: bool,
+    /// Uses `vkCmdPipelineBarrier2` when supported, otherwise barriers fall back to the legacy path.
+    #[builder(default = true)]
+    pub synchronization2: bool,
+    /// Depth-only and stencil-only attachment layouts (core in Vulkan 1.2), otherwise the combined
+    /// depth/stencil layouts are used.
+    #[builder(default = true)]
+    pub separate_depth_stencil_layouts: bool,
+    /// Pipelines and rendering without render pass objects (core in Vulkan 1.3), see
+    /// `PipelineTarget::DynamicRendering`.
+    #[builder(default = false)]
+    pub dynamic_rendering: bool,
+    /// Descriptor indexing for bindless resources: runtime descriptor arrays, partially bound,
+    /// variable count and update after bind bindings (core in Vulkan 1.2).
+    #[builder(default = false)]
+    pub descriptor_indexing: bool,
+    /// GPU addresses of buffers (core in Vulkan 1.2), see `Buffer::device_address`.
+    #[builder(default = false)]
+    pub buffer_device_address: bool,
     pub adapter_extension_names: Vec<&'static CStr>,
 }
 
@@ -51,6 +92,12 @@ pub struct InstanceDescriptor<'a> {
     pub flags: InstanceFlags,
     #[builder(default = log::LevelFilter::Warn)]
     pub debug_level_filter: LevelFilter,
+    /// Validation message ids (`messageIdNumber`) that will not be logged, e.g. known-benign warnings.
+    #[builder(default)]
+    pub message_id_filter: Vec<i32>,
+    /// Aborts the process in debug builds when the validation layer reports an error.
+    #[builder(default = false)]
+    pub break_on_error: bool,
 }
 
 #[derive(Debug, Default, Copy, Clone)]
@@ -119,4 +166,73 @@ impl Color {
     pub fn new(r: f32, g: f32, b: f32, a: f32) -> Self {
         Self { r, g, b, a }
     }
+
+    /// Creates a linear color from sRGB encoded components, alpha is always linear.
+    pub fn from_srgb(r: f32, g: f32, b: f32, a: f32) -> Self {
+        Self::new(r, g, b, a).to_linear()
+    }
+
+    /// Treats the color as sRGB encoded and converts it to linear space.
+    pub fn to_linear(self) -> Self {
+        Self {
+            r: srgb_to_linear(self.r),
+            g: srgb_to_linear(self.g),
+            b: srgb_to_linear(self.b),
+            a: self.a,
+        }
+    }
+
+    /// Treats the color as linear and converts it to sRGB encoded space.
+    pub fn to_srgb(self) -> Self {
+        Self {
+            r: linear_to_srgb(self.r),
+            g: linear_to_srgb(self.g),
+            b: linear_to_srgb(self.b),
+            a: self.a,
+        }
+    }
+}
+
+// https://en.wikipedia.org/wiki/SRGB#Transformation
+fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(value: f32) -> f32 {
+    if value <= 0.0031308 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn half_srgb_is_about_0_214_linear() {
+        let color = Color::from_srgb(0.5, 0.5, 0.5, 0.5);
+        assert!((color.r - 0.214).abs() < 1e-3);
+        assert!((color.g - 0.214).abs() < 1e-3);
+        assert!((color.b - 0.214).abs() < 1e-3);
+        assert_eq!(color.a, 0.5);
+    }
+
+    #[test]
+    fn srgb_round_trip() {
+        for value in [0.0, 0.002, 0.04, 0.25, 0.5, 0.75, 1.0] {
+            let color = Color::new(value, value, value, 1.0).to_linear().to_srgb();
+            assert!(
+                (color.r - value).abs() < 1e-5,
+                "{} became {}",
+                value,
+                color.r
+            );
+        }
+    }
 }
@@ -1,4 +1,3 @@
-use alloc::ffi::CString;
 use std::collections::HashSet;
 use std::ffi::{c_char, CStr};
 
@@ -6,14 +5,100 @@ use ash::extensions::khr;
 use ash::vk;
 
 use crate::vulkan::debug::DebugUtils;
-use crate::vulkan::instance::InstanceFlags;
-use crate::{AdapterRequirements, QueueFamilyIndices};
+use crate::{AdapterRequirements, QueueFamilyIndices, VulkanCallExt};
 
-use super::{device::Device, instance::Instance, surface::Surface, utils};
+use super::{device::Device, format, instance::Instance, surface::Surface, utils};
 
 pub struct Adapter {
     raw: vk::PhysicalDevice,
     max_msaa_samples: vk::SampleCountFlags,
+    limits: DeviceLimits,
+    info: AdapterInfo,
+}
+
+/// What an application needs to tell adapters apart, e.g. an integrated and a discrete GPU on a laptop.
+#[derive(Clone, Debug)]
+pub struct AdapterInfo {
+    pub name: String,
+    pub device_type: vk::PhysicalDeviceType,
+    pub vendor_id: u32,
+    pub device_id: u32,
+    /// Total size of the device local memory heaps in bytes.
+    pub device_local_memory: vk::DeviceSize,
+}
+
+/// Memory usage of one heap as reported by `VK_EXT_memory_budget`, in bytes.
+///
+/// `usage` covers all processes, `budget` is what this process can allocate before the
+/// driver starts evicting or failing allocations.
+#[derive(Clone, Copy, Debug)]
+pub struct HeapBudget {
+    pub heap_index: u32,
+    pub flags: vk::MemoryHeapFlags,
+    pub size: vk::DeviceSize,
+    pub usage: vk::DeviceSize,
+    pub budget: vk::DeviceSize,
+}
+
+impl HeapBudget {
+    pub fn is_device_local(&self) -> bool {
+        self.flags.contains(vk::MemoryHeapFlags::DEVICE_LOCAL)
+    }
+
+    pub fn available(&self) -> vk::DeviceSize {
+        self.budget.saturating_sub(self.usage)
+    }
+
+    /// Pre-check before a large allocation, to avoid `ERROR_OUT_OF_DEVICE_MEMORY`.
+    pub fn can_allocate(&self, size: vk::DeviceSize) -> bool {
+        size <= self.available()
+    }
+}
+
+/// Picks one of the adapters meeting the requirements by its index.
+pub type AdapterSelector = dyn Fn(&[AdapterInfo]) -> usize;
+
+/// The subset of `vk::PhysicalDeviceLimits` the renderer needs to stay within spec.
+#[derive(Clone, Copy, Debug)]
+pub struct DeviceLimits {
+    pub max_image_dimension_1d: u32,
+    pub max_image_dimension_2d: u32,
+    pub max_image_dimension_3d: u32,
+    pub max_image_dimension_cube: u32,
+    pub max_image_array_layers: u32,
+    pub max_viewports: u32,
+    pub sampled_image_color_sample_counts: vk::SampleCountFlags,
+    pub sampled_image_depth_sample_counts: vk::SampleCountFlags,
+    pub framebuffer_color_sample_counts: vk::SampleCountFlags,
+    pub framebuffer_depth_sample_counts: vk::SampleCountFlags,
+    pub max_push_constants_size: u32,
+    pub min_uniform_buffer_offset_alignment: vk::DeviceSize,
+    pub min_storage_buffer_offset_alignment: vk::DeviceSize,
+    pub max_sampler_anisotropy: f32,
+    /// Nanoseconds per timestamp tick.
+    pub timestamp_period: f32,
+}
+
+impl From<&vk::PhysicalDeviceLimits> for DeviceLimits {
+    fn from(limits: &vk::PhysicalDeviceLimits) -> Self {
+        Self {
+            max_image_dimension_1d: limits.max_image_dimension1_d,
+            max_image_dimension_2d: limits.max_image_dimension2_d,
+            max_image_dimension_3d: limits.max_image_dimension3_d,
+            max_image_dimension_cube: limits.max_image_dimension_cube,
+            max_image_array_layers: limits.max_image_array_layers,
+            max_viewports: limits.max_viewports,
+            sampled_image_color_sample_counts: limits.sampled_image_color_sample_counts,
+            sampled_image_depth_sample_counts: limits.sampled_image_depth_sample_counts,
+            framebuffer_color_sample_counts: limits.framebuffer_color_sample_counts,
+            framebuffer_depth_sample_counts: limits.framebuffer_depth_sample_counts,
+            max_push_constants_size: limits.max_push_constants_size,
+            min_uniform_buffer_offset_alignment: limits.min_uniform_buffer_offset_alignment,
+            min_storage_buffer_offset_alignment: limits.min_storage_buffer_offset_alignment,
+            max_sampler_anisotropy: limits.max_sampler_anisotropy,
+            timestamp_period: limits.timestamp_period,
+        }
+    }
 }
 
 impl Adapter {
@@ -25,20 +110,122 @@ impl Adapter {
         self.max_msaa_samples
     }
 
+    pub fn limits(&self) -> &DeviceLimits {
+        &self.limits
+    }
+
+    pub fn info(&self) -> &AdapterInfo {
+        &self.info
+    }
+
+    /// The default `AdapterSelector`: the first discrete GPU, otherwise the first adapter.
+    pub fn select_discrete_preferred(infos: &[AdapterInfo]) -> usize {
+        infos
+            .iter()
+            .position(|info| info.device_type == vk::PhysicalDeviceType::DISCRETE_GPU)
+            .unwrap_or(0)
+    }
+
+    /// Runs `adapter_selector`, an out of range index falls back to `select_discrete_preferred`.
+    pub fn select(infos: &[AdapterInfo], adapter_selector: &AdapterSelector) -> usize {
+        let index = adapter_selector(infos);
+        if index >= infos.len() {
+            log::warn!(
+                "Adapter selector returned {}, but only {} adapters meet the requirements, fall back to the default.",
+                index,
+                infos.len()
+            );
+            return Self::select_discrete_preferred(infos);
+        }
+        index
+    }
+
+    /// Features supported by `format` with linear tiling, optimal tiling and in buffers.
+    pub fn format_properties(
+        &self,
+        instance: &Instance,
+        format: vk::Format,
+    ) -> vk::FormatProperties {
+        unsafe {
+            instance
+                .raw()
+                .get_physical_device_format_properties(self.raw, format)
+        }
+    }
+
+    /// Vulkan version usable with `instance`, the lower of the instance and the device version.
+    /// A 1.2 device behind a 1.1 instance (e.g. MoltenVK) must not use 1.2 core features.
+    pub fn api_version(&self, instance: &Instance) -> u32 {
+        let properties = unsafe { instance.raw().get_physical_device_properties(self.raw) };
+        properties.api_version.min(instance.api_version())
+    }
+
+    /// Per heap budget, empty if `VK_EXT_memory_budget` is not enabled on `device`.
+    ///
+    /// The values change with every allocation, so query them right before they are needed.
+    pub fn memory_budget(&self, instance: &Instance, device: &Device) -> Vec<HeapBudget> {
+        if !device
+            .enabled_extensions()
+            .contains(&vk::ExtMemoryBudgetFn::name())
+        {
+            return Vec::new();
+        }
+        let mut budget_properties = vk::PhysicalDeviceMemoryBudgetPropertiesEXT::default();
+        let mut memory_properties =
+            vk::PhysicalDeviceMemoryProperties2::builder().push_next(&mut budget_properties);
+        unsafe {
+            instance
+                .raw()
+                .get_physical_device_memory_properties2(self.raw, &mut memory_properties)
+        };
+        let memory_properties = memory_properties.memory_properties;
+        memory_properties.memory_heaps[..memory_properties.memory_heap_count as usize]
+            .iter()
+            .enumerate()
+            .map(|(index, heap)| HeapBudget {
+                heap_index: index as u32,
+                flags: heap.flags,
+                size: heap.size,
+                usage: budget_properties.heap_usage[index],
+                budget: budget_properties.heap_budget[index],
+            })
+            .collect()
+    }
+
     pub fn new(raw: vk::PhysicalDevice, instance: &Instance) -> Self {
         let max_msaa_samples = Self::get_max_msaa_samples(raw, instance);
+        let properties = unsafe { instance.raw().get_physical_device_properties(raw) };
+        let memory_properties =
+            unsafe { instance.raw().get_physical_device_memory_properties(raw) };
+        let device_local_memory = memory_properties.memory_heaps
+            [..memory_properties.memory_heap_count as usize]
+            .iter()
+            .filter(|heap| heap.flags.contains(vk::MemoryHeapFlags::DEVICE_LOCAL))
+            .map(|heap| heap.size)
+            .sum();
+        let info = AdapterInfo {
+            name: utils::vk_to_string(&properties.device_name),
+            device_type: properties.device_type,
+            vendor_id: properties.vendor_id,
+            device_id: properties.device_id,
+            device_local_memory,
+        };
         Self {
             raw,
             max_msaa_samples,
+            limits: DeviceLimits::from(&properties.limits),
+            info,
         }
     }
 
     pub unsafe fn meet_requirements(
         &self,
-        instance: &ash::Instance,
+        instance: &Instance,
         surface: &Surface,
         requirements: &AdapterRequirements,
     ) -> Result<(), crate::DeviceError> {
+        let api_version = self.api_version(instance);
+        let instance = instance.raw();
         let properties = unsafe { instance.get_physical_device_properties(self.raw) };
         if requirements.discrete_gpu
             && properties.device_type != vk::PhysicalDeviceType::DISCRETE_GPU
@@ -52,6 +239,51 @@ impl Adapter {
             log::error!("Device is not support sampler anisotropy!");
             return Err(crate::DeviceError::NotMeetRequirement);
         }
+        if requirements.geometry_shader && features.geometry_shader != vk::TRUE {
+            log::error!("Device is not support geometry shader!");
+            return Err(crate::DeviceError::NotMeetRequirement);
+        }
+        if requirements.tessellation_shader && features.tessellation_shader != vk::TRUE {
+            log::error!("Device is not support tessellation shader!");
+            return Err(crate::DeviceError::NotMeetRequirement);
+        }
+        if requirements.depth_bounds && features.depth_bounds != vk::TRUE {
+            log::error!("Device is not support depth bounds!");
+            return Err(crate::DeviceError::NotMeetRequirement);
+        }
+        if requirements.multi_viewport && features.multi_viewport != vk::TRUE {
+            log::error!("Device is not support multi viewport!");
+            return Err(crate::DeviceError::NotMeetRequirement);
+        }
+        if requirements.independent_blend && features.independent_blend != vk::TRUE {
+            log::error!("Device is not support independent blend!");
+            return Err(crate::DeviceError::NotMeetRequirement);
+        }
+        if requirements.fill_mode_non_solid && features.fill_mode_non_solid != vk::TRUE {
+            log::error!("Device is not support non-solid fill mode!");
+            return Err(crate::DeviceError::NotMeetRequirement);
+        }
+        if requirements.wide_lines && features.wide_lines != vk::TRUE {
+            log::error!("Device is not support wide lines!");
+            return Err(crate::DeviceError::NotMeetRequirement);
+        }
+        if requirements.depth_bias_clamp && features.depth_bias_clamp != vk::TRUE {
+            log::error!("Device is not support depth bias clamp!");
+            return Err(crate::DeviceError::NotMeetRequirement);
+        }
+
+        if requirements.descriptor_indexing
+            && !Self::supports_descriptor_indexing(instance, self.raw, api_version)
+        {
+            log::error!("Device is not support descriptor indexing!");
+            return Err(crate::DeviceError::NotMeetRequirement);
+        }
+        if requirements.buffer_device_address
+            && !Self::supports_buffer_device_address(instance, self.raw, api_version)
+        {
+            log::error!("Device is not support buffer device address!");
+            return Err(crate::DeviceError::NotMeetRequirement);
+        }
 
         let _queue_families =
             unsafe { instance.get_physical_device_queue_family_properties(self.raw) };
@@ -84,6 +316,9 @@ impl Adapter {
         let mut unique_indices = HashSet::new();
         unique_indices.insert(indices.graphics_family.unwrap());
         unique_indices.insert(indices.present_family.unwrap());
+        if let Some(transfer_family) = indices.transfer_family {
+            unique_indices.insert(transfer_family);
+        }
 
         let queue_create_infos = unique_indices
             .iter()
@@ -95,55 +330,260 @@ impl Adapter {
             })
             .collect::<Vec<_>>();
 
+        let supported_features = unsafe { instance_raw.get_physical_device_features(self.raw) };
+        if requirement.multi_draw_indirect && supported_features.multi_draw_indirect != vk::TRUE {
+            log::warn!("Device is not support multi draw indirect, fallback to draw one by one.");
+        }
+
         let physical_device_features = vk::PhysicalDeviceFeatures::builder()
             .sampler_anisotropy(requirement.sampler_anisotropy)
-            .sample_rate_shading(requirement.sample_rate_shading);
-
-        let enable_validation = instance.flags().contains(InstanceFlags::VALIDATION);
-        let mut required_layers = vec![];
-        if enable_validation {
-            required_layers.push("VK_LAYER_KHRONOS_validation");
+            .sample_rate_shading(requirement.sample_rate_shading)
+            .multi_draw_indirect(
+                requirement.multi_draw_indirect
+                    && supported_features.multi_draw_indirect == vk::TRUE,
+            )
+            .geometry_shader(requirement.geometry_shader)
+            .tessellation_shader(requirement.tessellation_shader)
+            .depth_bounds(requirement.depth_bounds)
+            .multi_viewport(requirement.multi_viewport)
+            .independent_blend(requirement.independent_blend)
+            .fill_mode_non_solid(requirement.fill_mode_non_solid)
+            .wide_lines(requirement.wide_lines)
+            .depth_bias_clamp(requirement.depth_bias_clamp)
+            .build();
+
+        let mut synchronization2_features = vk::PhysicalDeviceSynchronization2Features::default();
+        let mut separate_depth_stencil_layouts_features =
+            vk::PhysicalDeviceSeparateDepthStencilLayoutsFeatures::default();
+        let mut dynamic_rendering_features = vk::PhysicalDeviceDynamicRenderingFeatures::default();
+        let mut supported_features2 = vk::PhysicalDeviceFeatures2::builder()
+            .push_next(&mut synchronization2_features)
+            .push_next(&mut separate_depth_stencil_layouts_features)
+            .push_next(&mut dynamic_rendering_features);
+        unsafe { instance_raw.get_physical_device_features2(self.raw, &mut supported_features2) };
+        let api_version = self.api_version(instance);
+        let synchronization2 = requirement.synchronization2
+            && api_version >= vk::API_VERSION_1_3
+            && synchronization2_features.synchronization2 == vk::TRUE;
+        if requirement.synchronization2 && !synchronization2 {
+            log::warn!(
+                "Device is not support synchronization2, fallback to legacy pipeline barrier."
+            );
         }
-        let required_validation_layer_raw_names: Vec<CString> = required_layers
-            .iter()
-            .map(|layer_name| CString::new(*layer_name).unwrap())
-            .collect();
-        let enable_layer_names: Vec<*const c_char> = required_validation_layer_raw_names
+        let separate_depth_stencil_layouts = requirement.separate_depth_stencil_layouts
+            && api_version >= vk::API_VERSION_1_2
+            && separate_depth_stencil_layouts_features.separate_depth_stencil_layouts == vk::TRUE;
+        if requirement.separate_depth_stencil_layouts && !separate_depth_stencil_layouts {
+            log::warn!(
+                "Device is not support separate depth stencil layouts, fallback to combined layouts."
+            );
+        }
+        let dynamic_rendering = requirement.dynamic_rendering
+            && api_version >= vk::API_VERSION_1_3
+            && dynamic_rendering_features.dynamic_rendering == vk::TRUE;
+        if requirement.dynamic_rendering && !dynamic_rendering {
+            log::warn!("Device is not support dynamic rendering, fallback to render passes.");
+        }
+        let mut enabled_dynamic_rendering_features =
+            vk::PhysicalDeviceDynamicRenderingFeatures::builder().dynamic_rendering(true);
+        let mut enabled_separate_depth_stencil_layouts_features =
+            vk::PhysicalDeviceSeparateDepthStencilLayoutsFeatures::builder()
+                .separate_depth_stencil_layouts(true);
+        let mut enabled_synchronization2_features =
+            vk::PhysicalDeviceSynchronization2Features::builder().synchronization2(true);
+        let descriptor_indexing = requirement.descriptor_indexing
+            && Self::supports_descriptor_indexing(instance_raw, self.raw, api_version);
+        let mut enabled_descriptor_indexing_features = Self::descriptor_indexing_features();
+        let mut enabled_buffer_device_address_features =
+            vk::PhysicalDeviceBufferDeviceAddressFeatures::builder().buffer_device_address(true);
+
+        // 设备层已废弃，与实例启用的层保持一致
+        let enable_layer_names: Vec<*const c_char> = instance
+            .layers()
             .iter()
             .map(|layer_name| layer_name.as_ptr())
             .collect();
 
-        let enable_extensions = Self::get_required_device_extensions();
-
         let support_extensions = Self::check_device_extension_support(instance, self.raw);
         if !support_extensions {
             log::error!("device extensions not support");
         }
 
-        let enable_extension_names = enable_extensions
+        let available_extensions = unsafe {
+            instance_raw
+                .enumerate_device_extension_properties(self.raw)
+                .vulkan_call("vkEnumerateDeviceExtensionProperties")?
+        };
+        let available_extension_names: Vec<&CStr> = available_extensions
+            .iter()
+            .map(|extension| unsafe { CStr::from_ptr(extension.extension_name.as_ptr()) })
+            .collect();
+        let requested_extensions = Self::get_required_device_extensions()
+            .into_iter()
+            .chain(Self::get_optional_device_extensions())
+            .collect::<Vec<_>>();
+        let enabled_extensions = utils::select_available_names(
+            "device extensions",
+            &requested_extensions,
+            &available_extension_names,
+        );
+
+        let enable_extension_names = enabled_extensions
             .iter()
             // Safe because `enabled_extensions` entries have static lifetime.
             .map(|&s| s.as_ptr())
             .collect::<Vec<_>>();
-        let device_create_info = vk::DeviceCreateInfo::builder()
+        let mut device_create_info = vk::DeviceCreateInfo::builder()
             .queue_create_infos(&queue_create_infos)
             .enabled_layer_names(&enable_layer_names)
             .enabled_extension_names(&enable_extension_names)
             .enabled_features(&physical_device_features);
+        if synchronization2 {
+            device_create_info =
+                device_create_info.push_next(&mut enabled_synchronization2_features);
+        }
+        if dynamic_rendering {
+            device_create_info =
+                device_create_info.push_next(&mut enabled_dynamic_rendering_features);
+        }
+        if separate_depth_stencil_layouts {
+            device_create_info =
+                device_create_info.push_next(&mut enabled_separate_depth_stencil_layouts_features);
+        }
+        if descriptor_indexing {
+            device_create_info =
+                device_create_info.push_next(&mut enabled_descriptor_indexing_features);
+        }
+        if requirement.buffer_device_address {
+            device_create_info =
+                device_create_info.push_next(&mut enabled_buffer_device_address_features);
+        }
+        let device_fault = enabled_extensions.contains(&vk::ExtDeviceFaultFn::name())
+            && Self::supports_device_fault(instance_raw, self.raw);
+        let mut enabled_fault_features =
+            vk::PhysicalDeviceFaultFeaturesEXT::builder().device_fault(true);
+        if device_fault {
+            device_create_info = device_create_info.push_next(&mut enabled_fault_features);
+        }
 
-        let ash_device: ash::Device =
-            unsafe { instance_raw.create_device(self.raw, &device_create_info, None)? };
+        let ash_device: ash::Device = unsafe {
+            instance_raw
+                .create_device(self.raw, &device_create_info, None)
+                .vulkan_call("vkCreateDevice")?
+        };
 
         log::debug!("Vulkan logical device created.");
 
-        let device = Device::new(ash_device, debug_utils);
+        let device_fault_fn = device_fault.then(|| {
+            vk::ExtDeviceFaultFn::load(|name| unsafe {
+                core::mem::transmute(
+                    instance_raw.get_device_proc_addr(ash_device.handle(), name.as_ptr()),
+                )
+            })
+        });
+        let negative_viewport_height = api_version >= vk::API_VERSION_1_1
+            || enabled_extensions.contains(&vk::KhrMaintenance1Fn::name());
+        if negative_viewport_height {
+            log::info!("Negative viewport height is supported, flipping Y in the viewport.");
+        } else {
+            log::warn!(
+                "Negative viewport height is not supported, flipping Y in the projection matrix."
+            );
+        }
+        let push_descriptor = enabled_extensions
+            .contains(&khr::PushDescriptor::name())
+            .then(|| khr::PushDescriptor::new(instance_raw, &ash_device));
+        // 顶点属性格式只需检查非压缩颜色格式
+        let vertex_buffer_formats = format::uncompressed_color_formats()
+            .filter(|&format| {
+                self.format_properties(instance, format)
+                    .buffer_features
+                    .contains(vk::FormatFeatureFlags::VERTEX_BUFFER)
+            })
+            .collect();
+        let device = Device::new(
+            ash_device,
+            debug_utils,
+            physical_device_features,
+            enabled_extensions,
+            self.limits,
+            synchronization2,
+            separate_depth_stencil_layouts,
+            negative_viewport_height,
+            dynamic_rendering,
+            descriptor_indexing,
+            requirement.buffer_device_address,
+            device_fault_fn,
+            push_descriptor,
+            vertex_buffer_formats,
+        );
         Ok(device)
     }
 
+    /// 无绑定纹理数组需要的 descriptor indexing 特性
+    fn descriptor_indexing_features() -> vk::PhysicalDeviceDescriptorIndexingFeatures {
+        vk::PhysicalDeviceDescriptorIndexingFeatures::builder()
+            .runtime_descriptor_array(true)
+            .descriptor_binding_partially_bound(true)
+            .descriptor_binding_variable_descriptor_count(true)
+            .descriptor_binding_sampled_image_update_after_bind(true)
+            .shader_sampled_image_array_non_uniform_indexing(true)
+            .build()
+    }
+
+    /// Core in Vulkan 1.2, the features struct can't be chained into device creation before.
+    fn supports_descriptor_indexing(
+        instance: &ash::Instance,
+        adapter: vk::PhysicalDevice,
+        api_version: u32,
+    ) -> bool {
+        if api_version < vk::API_VERSION_1_2 {
+            return false;
+        }
+        let mut supported = vk::PhysicalDeviceDescriptorIndexingFeatures::default();
+        let mut features2 = vk::PhysicalDeviceFeatures2::builder().push_next(&mut supported);
+        unsafe { instance.get_physical_device_features2(adapter, &mut features2) };
+        supported.runtime_descriptor_array == vk::TRUE
+            && supported.descriptor_binding_partially_bound == vk::TRUE
+            && supported.descriptor_binding_variable_descriptor_count == vk::TRUE
+            && supported.descriptor_binding_sampled_image_update_after_bind == vk::TRUE
+            && supported.shader_sampled_image_array_non_uniform_indexing == vk::TRUE
+    }
+
+    fn supports_buffer_device_address(
+        instance: &ash::Instance,
+        adapter: vk::PhysicalDevice,
+        api_version: u32,
+    ) -> bool {
+        let mut supported = vk::PhysicalDeviceBufferDeviceAddressFeatures::default();
+        let mut features2 = vk::PhysicalDeviceFeatures2::builder().push_next(&mut supported);
+        unsafe { instance.get_physical_device_features2(adapter, &mut features2) };
+        api_version >= vk::API_VERSION_1_2 && supported.buffer_device_address == vk::TRUE
+    }
+
+    fn supports_device_fault(instance: &ash::Instance, adapter: vk::PhysicalDevice) -> bool {
+        let mut supported = vk::PhysicalDeviceFaultFeaturesEXT::default();
+        let mut features2 = vk::PhysicalDeviceFeatures2::builder().push_next(&mut supported);
+        unsafe { instance.get_physical_device_features2(adapter, &mut features2) };
+        supported.device_fault == vk::TRUE
+    }
+
     fn get_required_device_extensions() -> [&'static CStr; 1] {
         [khr::Swapchain::name()]
     }
 
+    /// Enabled only when available, check `Device::enabled_extensions` before use.
+    fn get_optional_device_extensions() -> Vec<&'static CStr> {
+        vec![
+            vk::ExtMemoryBudgetFn::name(),
+            khr::PushDescriptor::name(),
+            // 1.1 之前负高度视口需要该扩展
+            vk::KhrMaintenance1Fn::name(),
+            #[cfg(feature = "device-fault")]
+            vk::ExtDeviceFaultFn::name(),
+        ]
+    }
+
     fn check_device_extension_support(instance: &Instance, device: vk::PhysicalDevice) -> bool {
         let required_extensions = Self::get_required_device_extensions();
 
@@ -282,3 +722,46 @@ impl Adapter {
         .unwrap_or(vk::SampleCountFlags::TYPE_1)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info(name: &str, device_type: vk::PhysicalDeviceType) -> AdapterInfo {
+        AdapterInfo {
+            name: name.to_owned(),
+            device_type,
+            vendor_id: 0,
+            device_id: 0,
+            device_local_memory: 0,
+        }
+    }
+
+    #[test]
+    fn discrete_adapter_is_preferred() {
+        let infos = [
+            info("integrated", vk::PhysicalDeviceType::INTEGRATED_GPU),
+            info("discrete", vk::PhysicalDeviceType::DISCRETE_GPU),
+        ];
+        assert_eq!(Adapter::select_discrete_preferred(&infos), 1);
+    }
+
+    #[test]
+    fn first_adapter_is_the_fallback() {
+        let infos = [
+            info("cpu", vk::PhysicalDeviceType::CPU),
+            info("integrated", vk::PhysicalDeviceType::INTEGRATED_GPU),
+        ];
+        assert_eq!(Adapter::select_discrete_preferred(&infos), 0);
+    }
+
+    #[test]
+    fn out_of_range_selection_falls_back_to_the_default() {
+        let infos = [
+            info("integrated", vk::PhysicalDeviceType::INTEGRATED_GPU),
+            info("discrete", vk::PhysicalDeviceType::DISCRETE_GPU),
+        ];
+        assert_eq!(Adapter::select(&infos, &|_| 0), 0);
+        assert_eq!(Adapter::select(&infos, &|_| 5), 1);
+    }
+}
@@ -16,6 +16,7 @@ pub enum BufferType {
     Index = 0,
     Vertex = 1,
     Uniform = 2,
+    Indirect = 3,
 }
 
 impl BufferType {
@@ -24,6 +25,7 @@ impl BufferType {
             BufferType::Index => vk::BufferUsageFlags::INDEX_BUFFER,
             BufferType::Vertex => vk::BufferUsageFlags::VERTEX_BUFFER,
             BufferType::Uniform => vk::BufferUsageFlags::UNIFORM_BUFFER,
+            BufferType::Indirect => vk::BufferUsageFlags::INDIRECT_BUFFER,
         }
     }
 }
@@ -36,6 +38,7 @@ pub struct Buffer {
     buffer_size: u64,
     element_size: usize,
     element_count: u32,
+    buffer_usage: vk::BufferUsageFlags,
 }
 
 #[derive(Clone, TypedBuilder)]
@@ -73,6 +76,29 @@ impl Buffer {
         self.raw
     }
 
+    pub fn buffer_usage(&self) -> vk::BufferUsageFlags {
+        self.buffer_usage
+    }
+
+    pub fn size(&self) -> u64 {
+        self.buffer_size
+    }
+
+    /// GPU address of the buffer, e.g. for `buffer_reference` in shaders. Needs the
+    /// `buffer_device_address` requirement and `SHADER_DEVICE_ADDRESS` usage.
+    pub fn device_address(&self) -> Result<vk::DeviceAddress, DeviceError> {
+        if !self
+            .buffer_usage
+            .contains(vk::BufferUsageFlags::SHADER_DEVICE_ADDRESS)
+        {
+            log::error!("Buffer is not created with SHADER_DEVICE_ADDRESS usage!");
+            return Err(DeviceError::Other(
+                "Buffer is not created with SHADER_DEVICE_ADDRESS usage",
+            ));
+        }
+        self.device.get_buffer_device_address(self.raw)
+    }
+
     pub fn new(desc: BufferDescriptor) -> Result<Buffer, DeviceError> {
         let buffer_size = desc.element_count as u64 * desc.element_size as u64;
         let buffer_info = vk::BufferCreateInfo::builder()
@@ -105,6 +131,7 @@ impl Buffer {
             element_size: desc.element_size,
             element_count: desc.element_count,
             buffer_size,
+            buffer_usage: desc.buffer_usage,
         })
     }
 
@@ -146,6 +173,14 @@ impl Buffer {
         Ok(buffer)
     }
 
+    /// Creates a GPU buffer filled with `vk::DrawIndexedIndirectCommand`s for
+    /// `Device::cmd_draw_indexed_indirect`, the stride is `size_of::<vk::DrawIndexedIndirectCommand>()`.
+    pub fn new_indirect_buffer(
+        desc: &StagingBufferDescriptor<vk::DrawIndexedIndirectCommand>,
+    ) -> Result<Buffer, DeviceError> {
+        Self::new_buffer_copy_from_staging_buffer(desc, BufferType::Indirect)
+    }
+
     pub fn new_uniform_buffer<T>(desc: &UniformBufferDescriptor<T>) -> Result<Buffer, DeviceError> {
         let buffer_desc = BufferDescriptor {
             label: Some("Uniform Buffer"),
@@ -160,12 +195,26 @@ impl Buffer {
         Ok(buffer)
     }
 
+    /// The mapped memory of a host visible buffer.
+    pub fn mapped_slice(&self) -> Option<&[u8]> {
+        self.allocation.as_ref().and_then(|a| a.mapped_slice())
+    }
+
     pub fn copy_memory<T>(&mut self, data: &[T]) {
+        self.copy_memory_with_offset(0, data);
+    }
+
+    /// Copies `data` into the mapped memory starting at `offset` bytes, `offset` does not need
+    /// to be aligned for `T`.
+    pub fn copy_memory_with_offset<T>(&mut self, offset: u64, data: &[T]) {
+        let size = std::mem::size_of_val(data);
+        assert!(offset + size as u64 <= self.buffer_size);
         if let Some(allocation) = &self.allocation {
-            let dst = allocation.mapped_ptr().unwrap().cast().as_ptr();
+            let dst = allocation.mapped_ptr().unwrap().cast::<u8>().as_ptr();
             unsafe {
                 use std::ptr::copy_nonoverlapping as memcpy;
-                memcpy(data.as_ptr(), dst, data.len());
+                // 按字节复制，目标地址不需要满足 T 的对齐
+                memcpy(data.as_ptr().cast::<u8>(), dst.add(offset as usize), size);
             }
         }
     }
@@ -181,6 +230,26 @@ impl Buffer {
         })?;
         Ok(())
     }
+
+    /// Copies byte ranges of this buffer into `destination`.
+    pub fn copy_buffer_regions(
+        &self,
+        destination: &Buffer,
+        regions: &[vk::BufferCopy],
+        command_buffer_allocator: &CommandBufferAllocator,
+    ) -> Result<(), DeviceError> {
+        let out_of_bounds = regions.iter().any(|region| {
+            region.src_offset + region.size > self.buffer_size
+                || region.dst_offset + region.size > destination.buffer_size
+        });
+        if out_of_bounds {
+            return Err(DeviceError::Other("Buffer copy region is out of bounds!"));
+        }
+        command_buffer_allocator.create_single_use(|device, command_buffer| {
+            device.cmd_copy_buffer(command_buffer.raw(), self.raw, destination.raw, regions);
+        })?;
+        Ok(())
+    }
 }
 
 impl Drop for Buffer {
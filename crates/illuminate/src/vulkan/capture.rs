@@ -0,0 +1,139 @@
+use std::path::Path;
+use std::rc::Rc;
+
+use ash::vk;
+use gpu_allocator::vulkan::Allocator;
+use gpu_allocator::MemoryLocation;
+use parking_lot::Mutex;
+use typed_builder::TypedBuilder;
+
+use crate::vulkan::buffer::{Buffer, BufferDescriptor};
+use crate::vulkan::command_buffer_allocator::CommandBufferAllocator;
+use crate::vulkan::device::Device;
+use crate::vulkan::format::FormatExt;
+
+#[derive(TypedBuilder)]
+pub struct ScreenshotDescriptor<'a> {
+    pub device: &'a Rc<Device>,
+    pub allocator: Rc<Mutex<Allocator>>,
+    pub command_buffer_allocator: &'a CommandBufferAllocator,
+    /// image must be created with `TRANSFER_SRC` usage
+    pub image: vk::Image,
+    /// layout of the image before and after the readback
+    pub image_layout: vk::ImageLayout,
+    pub extent: vk::Extent2D,
+    pub format: vk::Format,
+    pub path: &'a Path,
+}
+
+/// Reads a color image back to the CPU and writes it to `path` as a RGBA8 PNG.
+pub fn save_screenshot(desc: &ScreenshotDescriptor) -> anyhow::Result<()> {
+    // UNORM 和 SRGB 格式在内存中都是已经编码好的 8 位值，PNG 同样按 sRGB 解释，所以只需要处理通道顺序。
+    let is_bgra = match desc.format.to_unorm() {
+        Some(vk::Format::B8G8R8A8_UNORM) => true,
+        Some(vk::Format::R8G8B8A8_UNORM) => false,
+        _ => anyhow::bail!("Unsupported screenshot format: {:?}", desc.format),
+    };
+
+    let width = desc.extent.width;
+    let height = desc.extent.height;
+    let buffer_desc = BufferDescriptor {
+        label: Some("Screenshot Readback Buffer"),
+        device: desc.device,
+        allocator: desc.allocator.clone(),
+        element_size: 4,
+        element_count: width * height,
+        buffer_usage: vk::BufferUsageFlags::TRANSFER_DST,
+        memory_location: MemoryLocation::GpuToCpu,
+    };
+    let buffer = Buffer::new(buffer_desc)?;
+
+    desc.command_buffer_allocator
+        .create_single_use(|device, command_buffer| {
+            let subresource_range = vk::ImageSubresourceRange::builder()
+                .aspect_mask(vk::ImageAspectFlags::COLOR)
+                .base_mip_level(0)
+                .level_count(1)
+                .base_array_layer(0)
+                .layer_count(1)
+                .build();
+            let to_transfer = vk::ImageMemoryBarrier::builder()
+                .old_layout(desc.image_layout)
+                .new_layout(vk::ImageLayout::TRANSFER_SRC_OPTIMAL)
+                .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                .image(desc.image)
+                .subresource_range(subresource_range)
+                .src_access_mask(vk::AccessFlags::MEMORY_READ)
+                .dst_access_mask(vk::AccessFlags::TRANSFER_READ)
+                .build();
+            device.cmd_pipeline_barrier(
+                command_buffer.raw(),
+                vk::PipelineStageFlags::ALL_COMMANDS,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::DependencyFlags::empty(),
+                &[] as &[vk::MemoryBarrier],
+                &[] as &[vk::BufferMemoryBarrier],
+                &[to_transfer],
+            );
+
+            let region = vk::BufferImageCopy::builder()
+                .buffer_offset(0)
+                .buffer_row_length(0)
+                .buffer_image_height(0)
+                .image_subresource(
+                    vk::ImageSubresourceLayers::builder()
+                        .aspect_mask(vk::ImageAspectFlags::COLOR)
+                        .mip_level(0)
+                        .base_array_layer(0)
+                        .layer_count(1)
+                        .build(),
+                )
+                .image_offset(vk::Offset3D { x: 0, y: 0, z: 0 })
+                .image_extent(vk::Extent3D {
+                    width,
+                    height,
+                    depth: 1,
+                })
+                .build();
+            device.cmd_copy_image_to_buffer(
+                command_buffer.raw(),
+                desc.image,
+                vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                buffer.raw(),
+                &[region],
+            );
+
+            let to_original = vk::ImageMemoryBarrier::builder()
+                .old_layout(vk::ImageLayout::TRANSFER_SRC_OPTIMAL)
+                .new_layout(desc.image_layout)
+                .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                .image(desc.image)
+                .subresource_range(subresource_range)
+                .src_access_mask(vk::AccessFlags::TRANSFER_READ)
+                .dst_access_mask(vk::AccessFlags::MEMORY_READ)
+                .build();
+            device.cmd_pipeline_barrier(
+                command_buffer.raw(),
+                vk::PipelineStageFlags::TRANSFER,
+                vk::PipelineStageFlags::BOTTOM_OF_PIPE,
+                vk::DependencyFlags::empty(),
+                &[] as &[vk::MemoryBarrier],
+                &[] as &[vk::BufferMemoryBarrier],
+                &[to_original],
+            );
+        })?;
+
+    let mapped = buffer
+        .mapped_slice()
+        .ok_or_else(|| anyhow::anyhow!("Screenshot readback buffer is not mapped"))?;
+    let mut pixels = mapped[..(width * height * 4) as usize].to_vec();
+    if is_bgra {
+        pixels.chunks_exact_mut(4).for_each(|p| p.swap(0, 2));
+    }
+
+    image::save_buffer(desc.path, &pixels, width, height, image::ColorType::Rgba8)?;
+    log::debug!("Screenshot saved to '{}'.", desc.path.display());
+    Ok(())
+}
@@ -1,6 +1,7 @@
 use ash::vk;
 use std::ops::Deref;
 
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum CommandBufferState {
     /// ready to begin
     Ready,
@@ -21,6 +22,10 @@ impl CommandBuffer {
         self.raw
     }
 
+    pub fn state(&self) -> CommandBufferState {
+        self.state
+    }
+
     pub fn set_state(&mut self, state: CommandBufferState) {
         self.state = state;
     }
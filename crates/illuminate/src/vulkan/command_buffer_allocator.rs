@@ -1,5 +1,6 @@
 use crate::vulkan::command_buffer::{CommandBuffer, CommandBufferState};
 use crate::vulkan::device::Device;
+use crate::vulkan::fence::Fence;
 use crate::DeviceError;
 use ash::vk;
 use ash::vk::CommandBufferResetFlags;
@@ -98,6 +99,7 @@ impl CommandBufferAllocator {
         &self,
         command_buffer: &mut CommandBuffer,
     ) -> Result<(), DeviceError> {
+        debug_assert_eq!(command_buffer.state(), CommandBufferState::Recording);
         self.device.end_command_buffer(command_buffer.raw())?;
         command_buffer.set_state(CommandBufferState::RecordingEnded);
         Ok(())
@@ -110,9 +112,19 @@ impl CommandBufferAllocator {
     pub fn reset_command_buffer(
         &self,
         command_buffer: &mut CommandBuffer,
+    ) -> Result<(), DeviceError> {
+        self.reset_command_buffer_with_flags(command_buffer, CommandBufferResetFlags::empty())
+    }
+
+    /// `RELEASE_RESOURCES` returns the memory of the command buffer to the pool,
+    /// the empty flags keep it for reusing the buffer in the next frame.
+    pub fn reset_command_buffer_with_flags(
+        &self,
+        command_buffer: &mut CommandBuffer,
+        flags: CommandBufferResetFlags,
     ) -> Result<(), DeviceError> {
         self.device
-            .reset_command_buffer(command_buffer.raw(), CommandBufferResetFlags::empty())?;
+            .reset_command_buffer(command_buffer.raw(), flags)?;
         command_buffer.set_state(CommandBufferState::Ready);
         Ok(())
     }
@@ -126,6 +138,34 @@ impl CommandBufferAllocator {
         self.end_single_use(&mut command_buffer)
     }
 
+    /// Records, submits and waits for a one-time command buffer, e.g. uploads and layout transitions.
+    ///
+    /// 与 `create_single_use` 不同，这里只等待这次提交的 fence，不会等待队列上的其他工作。
+    pub fn run_transient<F>(&self, record: F) -> Result<(), DeviceError>
+    where
+        F: FnOnce(&Rc<Device>, &CommandBuffer),
+    {
+        let mut command_buffer = self.allocate_and_begin_single_use()?;
+        record(&self.device, &command_buffer);
+        let result = self.submit_and_wait(&mut command_buffer);
+        self.free_command_buffer(&mut command_buffer);
+        result
+    }
+
+    fn submit_and_wait(&self, command_buffer: &mut CommandBuffer) -> Result<(), DeviceError> {
+        self.end_command_buffer(command_buffer)?;
+        let fence = Fence::new(&self.device, false)?;
+        let command_buffers = [command_buffer.raw()];
+        let submit_info = vk::SubmitInfo::builder()
+            .command_buffers(&command_buffers)
+            .build();
+        self.device
+            .queue_submit(self.queue, &[submit_info], fence.raw())?;
+        self.update_submitted_command_buffer(command_buffer);
+        fence.wait(u64::MAX)?;
+        Ok(())
+    }
+
     pub fn allocate_and_begin_single_use(&self) -> Result<CommandBuffer, DeviceError> {
         let mut command_buffer = self.allocate_command_buffer(true)?;
         self.begin_command_buffer(&mut command_buffer, true, false, false)?;
@@ -1,6 +1,7 @@
 use crate::Color;
 use ash::vk;
 use ash::vk::ClearDepthStencilValue;
+use winit::dpi::PhysicalSize;
 
 pub fn convert_rect2d(rect: math::Rect2D) -> vk::Rect2D {
     vk::Rect2D::builder()
@@ -15,16 +16,231 @@ pub fn convert_rect2d(rect: math::Rect2D) -> vk::Rect2D {
         .build()
 }
 
-pub fn convert_clear_color(color: Color) -> vk::ClearValue {
-    vk::ClearValue {
-        color: vk::ClearColorValue {
-            float32: [color.r, color.g, color.b, color.a],
-        },
+pub fn convert_physical_size(size: PhysicalSize<u32>) -> vk::Extent2D {
+    vk::Extent2D {
+        width: size.width,
+        height: size.height,
+    }
+}
+
+pub fn convert_extent2d(extent: vk::Extent2D) -> PhysicalSize<u32> {
+    PhysicalSize::new(extent.width, extent.height)
+}
+
+/// Viewport covering the whole extent, flipped on Y so that +Y points up in NDC.
+pub fn flipped_viewport(extent: vk::Extent2D) -> math::Rect2D {
+    math::Rect2D::new(
+        0.0,
+        extent.height as f32,
+        extent.width as f32,
+        -(extent.height as f32),
+    )
+}
+
+/// `flipped_viewport` when negative viewport heights are supported, otherwise an unflipped
+/// viewport, and the projection must be flipped with `flip_projection_y` instead.
+pub fn y_up_viewport(extent: vk::Extent2D, negative_height_supported: bool) -> math::Rect2D {
+    if negative_height_supported {
+        flipped_viewport(extent)
+    } else {
+        math::Rect2D::full(extent.width as f32, extent.height as f32)
+    }
+}
+
+/// Negates the clip space Y, which ends up at the same framebuffer position as a flipped viewport.
+pub fn flip_projection_y(projection: &mut math::Mat4) {
+    let flipped_row = -projection.row(1);
+    projection.set_row(1, &flipped_row);
+}
+
+/// Typed clear color, the variant must match the numeric type of the attachment format.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ClearColor {
+    Float([f32; 4]),
+    Int([i32; 4]),
+    Uint([u32; 4]),
+}
+
+impl ClearColor {
+    pub fn raw(self) -> vk::ClearColorValue {
+        match self {
+            ClearColor::Float(float32) => vk::ClearColorValue { float32 },
+            ClearColor::Int(int32) => vk::ClearColorValue { int32 },
+            ClearColor::Uint(uint32) => vk::ClearColorValue { uint32 },
+        }
     }
 }
 
+pub fn convert_clear_color(color: Color) -> vk::ClearValue {
+    convert_clear_color_value(ClearColor::Float([color.r, color.g, color.b, color.a]))
+}
+
+pub fn convert_clear_color_value(color: ClearColor) -> vk::ClearValue {
+    vk::ClearValue { color: color.raw() }
+}
+
 pub fn convert_clear_depth_stencil(depth: f32, stencil: u32) -> vk::ClearValue {
     vk::ClearValue {
         depth_stencil: ClearDepthStencilValue { depth, stencil },
     }
 }
+
+/// Attachment layout of a depth/stencil format with `aspect_mask`, depth-only or stencil-only
+/// formats get their own layout when `separate_depth_stencil_layouts` is enabled.
+pub fn depth_stencil_attachment_layout(
+    aspect_mask: vk::ImageAspectFlags,
+    separate_depth_stencil_layouts: bool,
+) -> vk::ImageLayout {
+    let layout = if aspect_mask == vk::ImageAspectFlags::DEPTH {
+        vk::ImageLayout::DEPTH_ATTACHMENT_OPTIMAL
+    } else if aspect_mask == vk::ImageAspectFlags::STENCIL {
+        vk::ImageLayout::STENCIL_ATTACHMENT_OPTIMAL
+    } else {
+        vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL
+    };
+    convert_depth_stencil_layout(layout, separate_depth_stencil_layouts)
+}
+
+/// Widens the depth-only and stencil-only layouts to the combined ones when
+/// `separate_depth_stencil_layouts` is not enabled.
+pub fn convert_depth_stencil_layout(
+    layout: vk::ImageLayout,
+    separate_depth_stencil_layouts: bool,
+) -> vk::ImageLayout {
+    if separate_depth_stencil_layouts {
+        return layout;
+    }
+    match layout {
+        vk::ImageLayout::DEPTH_ATTACHMENT_OPTIMAL | vk::ImageLayout::STENCIL_ATTACHMENT_OPTIMAL => {
+            vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL
+        }
+        vk::ImageLayout::DEPTH_READ_ONLY_OPTIMAL | vk::ImageLayout::STENCIL_READ_ONLY_OPTIMAL => {
+            vk::ImageLayout::DEPTH_STENCIL_READ_ONLY_OPTIMAL
+        }
+        layout => layout,
+    }
+}
+
+/// Maps to `vk::PipelineStageFlags`, stages without a legacy bit widen to `ALL_COMMANDS`,
+/// `empty` is used when no stage is set.
+pub fn convert_pipeline_stage_flags2(
+    stages: vk::PipelineStageFlags2,
+    empty: vk::PipelineStageFlags,
+) -> vk::PipelineStageFlags {
+    if stages.as_raw() >> 32 != 0 {
+        return vk::PipelineStageFlags::ALL_COMMANDS;
+    }
+    // 低 32 位与旧版 flag 的取值一致
+    let stages = vk::PipelineStageFlags::from_raw(stages.as_raw() as u32);
+    if stages.is_empty() {
+        empty
+    } else {
+        stages
+    }
+}
+
+/// Maps to `vk::AccessFlags`, accesses without a legacy bit widen to `MEMORY_READ | MEMORY_WRITE`.
+pub fn convert_access_flags2(access: vk::AccessFlags2) -> vk::AccessFlags {
+    let legacy = vk::AccessFlags::from_raw(access.as_raw() as u32);
+    if access.as_raw() >> 32 != 0 {
+        legacy | vk::AccessFlags::MEMORY_READ | vk::AccessFlags::MEMORY_WRITE
+    } else {
+        legacy
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Framebuffer y of a clip space position, per the viewport transform of the spec.
+    fn framebuffer_y(viewport: math::Rect2D, projection: &math::Mat4, point: math::Vec4) -> f32 {
+        let clip = projection * point;
+        let ndc_y = clip.y / clip.w;
+        viewport.y + (ndc_y + 1.0) * 0.5 * viewport.height
+    }
+
+    #[test]
+    fn both_y_flips_give_the_same_framebuffer_y() {
+        let extent = vk::Extent2D {
+            width: 1280,
+            height: 720,
+        };
+        let projection = math::perspective_rh_zo(16.0 / 9.0, 0.8, 0.1, 10.0);
+        let mut flipped_projection = projection;
+        flip_projection_y(&mut flipped_projection);
+
+        let negative_height = y_up_viewport(extent, true);
+        let full = y_up_viewport(extent, false);
+        for point in [
+            math::Vec4::new(0.0, 1.0, -2.0, 1.0),
+            math::Vec4::new(0.5, -0.3, -5.0, 1.0),
+            math::Vec4::new(-1.0, 0.0, -1.0, 1.0),
+        ] {
+            let expected = framebuffer_y(negative_height, &projection, point);
+            let actual = framebuffer_y(full, &flipped_projection, point);
+            assert!((expected - actual).abs() < 1e-3, "{expected} != {actual}");
+        }
+        // y 向上为正，位于视线上方的点落在帧缓冲上半部分
+        let above = framebuffer_y(
+            negative_height,
+            &projection,
+            math::Vec4::new(0.0, 1.0, -2.0, 1.0),
+        );
+        assert!(above < 360.0);
+    }
+
+    #[test]
+    fn clear_color_variants_convert_to_clear_values() {
+        let float = convert_clear_color_value(ClearColor::Float([0.1, 0.2, 0.3, 1.0]));
+        assert_eq!(unsafe { float.color.float32 }, [0.1, 0.2, 0.3, 1.0]);
+        let int = convert_clear_color_value(ClearColor::Int([-1, 0, 1, 2]));
+        assert_eq!(unsafe { int.color.int32 }, [-1, 0, 1, 2]);
+        let uint = convert_clear_color_value(ClearColor::Uint([0, 1, 2, u32::MAX]));
+        assert_eq!(unsafe { uint.color.uint32 }, [0, 1, 2, u32::MAX]);
+
+        let color = convert_clear_color(Color::new(0.5, 0.25, 0.0, 1.0));
+        assert_eq!(unsafe { color.color.float32 }, [0.5, 0.25, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn physical_size_converts_to_a_full_viewport_extent() {
+        let extent = convert_physical_size(PhysicalSize::new(1280, 720));
+        assert_eq!(
+            extent,
+            vk::Extent2D {
+                width: 1280,
+                height: 720,
+            }
+        );
+        assert_eq!(convert_extent2d(extent), PhysicalSize::new(1280, 720));
+        assert_eq!(
+            y_up_viewport(extent, false),
+            math::Rect2D::new(0.0, 0.0, 1280.0, 720.0)
+        );
+        assert_eq!(
+            y_up_viewport(extent, true),
+            math::Rect2D::new(0.0, 720.0, 1280.0, -720.0)
+        );
+    }
+
+    #[test]
+    fn depth_only_aspect_uses_the_depth_layout() {
+        assert_eq!(
+            depth_stencil_attachment_layout(vk::ImageAspectFlags::DEPTH, true),
+            vk::ImageLayout::DEPTH_ATTACHMENT_OPTIMAL
+        );
+        // 不支持分离布局时退回到合并的深度模板布局
+        assert_eq!(
+            depth_stencil_attachment_layout(vk::ImageAspectFlags::DEPTH, false),
+            vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL
+        );
+        assert_eq!(
+            depth_stencil_attachment_layout(
+                vk::ImageAspectFlags::DEPTH | vk::ImageAspectFlags::STENCIL,
+                true
+            ),
+            vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL
+        );
+    }
+}
@@ -1,6 +1,7 @@
 use std::borrow::Cow;
 use std::ffi::CStr;
 use std::os::raw::c_void;
+use std::sync::Arc;
 
 use ash::{extensions::ext, vk};
 
@@ -10,14 +11,35 @@ use crate::vulkan::utils;
 pub struct DebugUtils {
     pub extension: ext::DebugUtils,
     pub messenger: vk::DebugUtilsMessengerEXT,
+    /// User data of the messenger callback, must outlive the messenger.
+    pub filter: Arc<DebugMessageFilter>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct DebugMessageFilter {
+    pub message_id_filter: Vec<i32>,
+    pub break_on_error: bool,
+}
+
+impl DebugMessageFilter {
+    pub fn is_filtered(&self, message_id_number: i32) -> bool {
+        self.message_id_filter.contains(&message_id_number)
+    }
 }
 
 unsafe extern "system" fn vulkan_debug_utils_callback(
     message_severity: vk::DebugUtilsMessageSeverityFlagsEXT,
     message_type: vk::DebugUtilsMessageTypeFlagsEXT,
     p_callback_data: *const vk::DebugUtilsMessengerCallbackDataEXT,
-    _p_user_data: *mut c_void,
+    p_user_data: *mut c_void,
 ) -> vk::Bool32 {
+    let callback_data = *p_callback_data;
+    let message_id_number: i32 = callback_data.message_id_number;
+    let filter = (p_user_data as *const DebugMessageFilter).as_ref();
+    if filter.map_or(false, |filter| filter.is_filtered(message_id_number)) {
+        return vk::FALSE;
+    }
+
     let log_level = match message_severity {
         vk::DebugUtilsMessageSeverityFlagsEXT::VERBOSE => log::Level::Debug,
         vk::DebugUtilsMessageSeverityFlagsEXT::INFO => log::Level::Info,
@@ -32,8 +54,6 @@ unsafe extern "system" fn vulkan_debug_utils_callback(
         vk::DebugUtilsMessageTypeFlagsEXT::VALIDATION => "[Validation]",
         _ => "[Unknown]",
     };
-    let callback_data = *p_callback_data;
-    let message_id_number: i32 = callback_data.message_id_number as i32;
 
     let message_id_name = if callback_data.p_message_id_name.is_null() {
         Cow::from("")
@@ -56,6 +76,15 @@ unsafe extern "system" fn vulkan_debug_utils_callback(
         message
     );
 
+    // 不能在 FFI 回调里 panic，展开穿过 extern "system" 边界是未定义行为
+    if cfg!(debug_assertions)
+        && message_severity == vk::DebugUtilsMessageSeverityFlagsEXT::ERROR
+        && filter.map_or(false, |filter| filter.break_on_error)
+    {
+        log::error!("Aborting on Vulkan validation error.");
+        std::process::abort();
+    }
+
     vk::FALSE
 }
 
@@ -102,10 +131,12 @@ pub fn setup_debug_utils(
     entry: &ash::Entry,
     instance: &ash::Instance,
     min_level: vk::DebugUtilsMessageSeverityFlagsEXT,
+    filter: &Arc<DebugMessageFilter>,
 ) -> Result<(ash::extensions::ext::DebugUtils, vk::DebugUtilsMessengerEXT), crate::InstanceError> {
     let debug_utils_loader = ash::extensions::ext::DebugUtils::new(entry, instance);
 
-    let messenger_ci = populate_debug_messenger_create_info(min_level);
+    let mut messenger_ci = populate_debug_messenger_create_info(min_level);
+    messenger_ci.p_user_data = Arc::as_ptr(filter) as *mut c_void;
 
     let utils_messenger = unsafe {
         debug_utils_loader
@@ -145,3 +176,33 @@ pub fn populate_debug_messenger_create_info(
         .pfn_user_callback(Some(vulkan_debug_utils_callback))
         .build()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filtered_message_id_is_dropped() {
+        let filter = DebugMessageFilter {
+            message_id_filter: vec![0x1234],
+            break_on_error: true,
+        };
+        assert!(filter.is_filtered(0x1234));
+        assert!(!filter.is_filtered(0x4321));
+
+        // 被过滤的错误消息不会触发 break_on_error
+        let callback_data = vk::DebugUtilsMessengerCallbackDataEXT {
+            message_id_number: 0x1234,
+            ..Default::default()
+        };
+        let result = unsafe {
+            vulkan_debug_utils_callback(
+                vk::DebugUtilsMessageSeverityFlagsEXT::ERROR,
+                vk::DebugUtilsMessageTypeFlagsEXT::VALIDATION,
+                &callback_data,
+                &filter as *const DebugMessageFilter as *mut c_void,
+            )
+        };
+        assert_eq!(result, vk::FALSE);
+    }
+}
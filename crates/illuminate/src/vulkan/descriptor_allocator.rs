@@ -0,0 +1,108 @@
+use alloc::rc::Rc;
+
+use ash::vk;
+use typed_builder::TypedBuilder;
+
+use crate::vulkan::descriptor_pool::DescriptorPool;
+use crate::vulkan::device::Device;
+use crate::DeviceError;
+
+#[derive(Clone, TypedBuilder)]
+pub struct DescriptorAllocatorDescriptor<'a> {
+    pub device: &'a Rc<Device>,
+    pub frames_in_flight: usize,
+    /// 每个 pool 中各类描述符的数量
+    pub pool_sizes: &'a [vk::DescriptorPoolSize],
+    /// 每个 pool 最多能分配的 set 数量
+    pub max_sets_per_pool: u32,
+}
+
+struct FramePools {
+    pools: Vec<DescriptorPool>,
+    current: usize,
+}
+
+/// Allocates transient descriptor sets that live for a single frame.
+///
+/// 每个 frame in flight 持有自己的一组 pool，在帧开始时通过 `reset_descriptor_pool` 整体回收，
+/// 当前 pool 用完时再创建新的 pool，避免每帧重新分配导致 pool 耗尽。
+pub struct DescriptorAllocator {
+    device: Rc<Device>,
+    pool_sizes: Vec<vk::DescriptorPoolSize>,
+    max_sets_per_pool: u32,
+    frames: Vec<FramePools>,
+    frame: usize,
+}
+
+impl DescriptorAllocator {
+    pub fn new(desc: &DescriptorAllocatorDescriptor) -> Result<Self, DeviceError> {
+        let mut frames = Vec::with_capacity(desc.frames_in_flight);
+        for _ in 0..desc.frames_in_flight {
+            let pool = DescriptorPool::from_pool_sizes(
+                desc.device,
+                desc.pool_sizes,
+                desc.max_sets_per_pool,
+            )?;
+            frames.push(FramePools {
+                pools: vec![pool],
+                current: 0,
+            });
+        }
+        log::debug!("Descriptor Allocator created.");
+        Ok(Self {
+            device: desc.device.clone(),
+            pool_sizes: desc.pool_sizes.to_vec(),
+            max_sets_per_pool: desc.max_sets_per_pool,
+            frames,
+            frame: 0,
+        })
+    }
+
+    /// Resets every pool of `frame`, the sets allocated in that frame must no longer be in use.
+    pub fn begin_frame(&mut self, frame: usize) -> Result<(), DeviceError> {
+        self.frame = frame;
+        let frame_pools = &mut self.frames[frame];
+        for pool in &frame_pools.pools[..=frame_pools.current] {
+            self.device.reset_descriptor_pool(pool.raw())?;
+        }
+        frame_pools.current = 0;
+        Ok(())
+    }
+
+    pub fn allocate(
+        &mut self,
+        layout: vk::DescriptorSetLayout,
+    ) -> Result<vk::DescriptorSet, DeviceError> {
+        let frame_pools = &mut self.frames[self.frame];
+        loop {
+            let layouts = [layout];
+            let info = vk::DescriptorSetAllocateInfo::builder()
+                .descriptor_pool(frame_pools.pools[frame_pools.current].raw())
+                .set_layouts(&layouts);
+            match self.device.allocate_descriptor_sets(&info) {
+                Ok(sets) => return Ok(sets[0]),
+                Err(DeviceError::VulkanError(
+                    vk::Result::ERROR_OUT_OF_POOL_MEMORY | vk::Result::ERROR_FRAGMENTED_POOL,
+                )) => {
+                    frame_pools.current += 1;
+                    if frame_pools.current == frame_pools.pools.len() {
+                        log::debug!("Descriptor Allocator grows for frame {}.", self.frame);
+                        let pool = DescriptorPool::from_pool_sizes(
+                            &self.device,
+                            &self.pool_sizes,
+                            self.max_sets_per_pool,
+                        )?;
+                        frame_pools.pools.push(pool);
+                    }
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+impl Drop for DescriptorAllocator {
+    fn drop(&mut self) {
+        log::debug!("Descriptor Allocator destroyed.");
+    }
+}
@@ -48,6 +48,56 @@ impl DescriptorPool {
         })
     }
 
+    pub fn from_pool_sizes(
+        device: &Rc<Device>,
+        pool_sizes: &[vk::DescriptorPoolSize],
+        max_sets: u32,
+    ) -> Result<Self, DeviceError> {
+        Self::from_pool_sizes_with_flags(
+            device,
+            pool_sizes,
+            max_sets,
+            vk::DescriptorPoolCreateFlags::empty(),
+        )
+    }
+
+    /// Sets with an `UPDATE_AFTER_BIND_POOL` layout need a pool with the `UPDATE_AFTER_BIND` flag.
+    pub fn from_pool_sizes_with_flags(
+        device: &Rc<Device>,
+        pool_sizes: &[vk::DescriptorPoolSize],
+        max_sets: u32,
+        flags: vk::DescriptorPoolCreateFlags,
+    ) -> Result<Self, DeviceError> {
+        let info = vk::DescriptorPoolCreateInfo::builder()
+            .flags(flags)
+            .pool_sizes(pool_sizes)
+            .max_sets(max_sets);
+        let raw = device.create_descriptor_pool(&info)?;
+        log::debug!("Descriptor Pool created.");
+        Ok(Self {
+            raw,
+            device: device.clone(),
+        })
+    }
+
+    /// Allocates a set whose `VARIABLE_DESCRIPTOR_COUNT` binding holds `descriptor_count` descriptors.
+    pub fn allocate_variable_count_set(
+        &self,
+        layout: vk::DescriptorSetLayout,
+        descriptor_count: u32,
+    ) -> Result<vk::DescriptorSet, DeviceError> {
+        let layouts = [layout];
+        let descriptor_counts = [descriptor_count];
+        let mut variable_count_info =
+            vk::DescriptorSetVariableDescriptorCountAllocateInfo::builder()
+                .descriptor_counts(&descriptor_counts);
+        let info = vk::DescriptorSetAllocateInfo::builder()
+            .descriptor_pool(self.raw)
+            .set_layouts(&layouts)
+            .push_next(&mut variable_count_info);
+        Ok(self.device.allocate_descriptor_sets(&info)?[0])
+    }
+
     pub fn create_texture_descriptor_pool(device: &Rc<Device>) -> Result<Self, DeviceError> {
         let sampler_pool_size = vk::DescriptorPoolSize::builder()
             .descriptor_count(1)
@@ -59,6 +59,7 @@ impl DescriptorSetAllocator {
             descriptor_type: vk::DescriptorType::UNIFORM_BUFFER,
             descriptor_count: 1,
             shader_stage_flags: vk::ShaderStageFlags::VERTEX,
+            binding_flags: vk::DescriptorBindingFlags::empty(),
         };
 
         let image_binding = DescriptorSetLayoutBinding {
@@ -66,6 +67,7 @@ impl DescriptorSetAllocator {
             descriptor_type: vk::DescriptorType::SAMPLED_IMAGE,
             descriptor_count: 1,
             shader_stage_flags: vk::ShaderStageFlags::FRAGMENT,
+            binding_flags: vk::DescriptorBindingFlags::empty(),
         };
 
         let sampler_binding = DescriptorSetLayoutBinding {
@@ -73,11 +75,13 @@ impl DescriptorSetAllocator {
             descriptor_type: vk::DescriptorType::SAMPLER,
             descriptor_count: 1,
             shader_stage_flags: vk::ShaderStageFlags::FRAGMENT,
+            binding_flags: vk::DescriptorBindingFlags::empty(),
         };
 
         let per_frame_layout_desc = DescriptorSetLayoutCreateInfo {
             device,
             bindings: &[ubo_binding, image_binding, sampler_binding],
+            flags: vk::DescriptorSetLayoutCreateFlags::empty(),
         };
 
         let per_frame_layout = DescriptorSetLayout::new(per_frame_layout_desc)?;
@@ -87,10 +91,12 @@ impl DescriptorSetAllocator {
             descriptor_type: vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
             descriptor_count: 1,
             shader_stage_flags: vk::ShaderStageFlags::FRAGMENT,
+            binding_flags: vk::DescriptorBindingFlags::empty(),
         };
         let texture_layout_desc = DescriptorSetLayoutCreateInfo {
             device,
             bindings: &[texture_pool_ubo_binding],
+            flags: vk::DescriptorSetLayoutCreateFlags::empty(),
         };
         let texture_layout = DescriptorSetLayout::new(texture_layout_desc)?;
 
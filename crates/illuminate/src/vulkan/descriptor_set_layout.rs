@@ -10,13 +10,33 @@ use crate::DeviceError;
 pub struct DescriptorSetLayoutCreateInfo<'a> {
     pub device: &'a Rc<Device>,
     pub bindings: &'a [DescriptorSetLayoutBinding],
+    /// `UPDATE_AFTER_BIND_POOL` requires descriptor indexing, `PUSH_DESCRIPTOR_KHR` requires
+    /// `VK_KHR_push_descriptor`, see `Device::cmd_push_descriptor_set`.
+    #[builder(default)]
+    pub flags: vk::DescriptorSetLayoutCreateFlags,
 }
 
 pub struct DescriptorSetLayoutBinding {
     pub binding: u32,
     pub descriptor_type: vk::DescriptorType,
+    /// Upper bound of the binding when `VARIABLE_DESCRIPTOR_COUNT` is set.
     pub descriptor_count: u32,
     pub shader_stage_flags: vk::ShaderStageFlags,
+    /// Non-empty flags require descriptor indexing.
+    pub binding_flags: vk::DescriptorBindingFlags,
+}
+
+impl DescriptorSetLayoutBinding {
+    /// Binding read by a `subpassInput` in the fragment shader, the only stage input attachments are visible to.
+    pub fn input_attachment(binding: u32) -> Self {
+        Self {
+            binding,
+            descriptor_type: vk::DescriptorType::INPUT_ATTACHMENT,
+            descriptor_count: 1,
+            shader_stage_flags: vk::ShaderStageFlags::FRAGMENT,
+            binding_flags: vk::DescriptorBindingFlags::empty(),
+        }
+    }
 }
 
 pub struct DescriptorSetLayout {
@@ -31,6 +51,7 @@ impl DescriptorSetLayout {
 
     pub fn new(desc: DescriptorSetLayoutCreateInfo) -> Result<Self, DeviceError> {
         let device = desc.device;
+        Self::validate_binding_flags(&desc)?;
 
         let bindings = desc
             .bindings
@@ -44,7 +65,19 @@ impl DescriptorSetLayout {
                     .build()
             })
             .collect::<Vec<vk::DescriptorSetLayoutBinding>>();
-        let create_info = vk::DescriptorSetLayoutCreateInfo::builder().bindings(&bindings);
+        let binding_flags = desc
+            .bindings
+            .iter()
+            .map(|binding| binding.binding_flags)
+            .collect::<Vec<_>>();
+        let mut binding_flags_info =
+            vk::DescriptorSetLayoutBindingFlagsCreateInfo::builder().binding_flags(&binding_flags);
+        let mut create_info = vk::DescriptorSetLayoutCreateInfo::builder()
+            .flags(desc.flags)
+            .bindings(&bindings);
+        if binding_flags.iter().any(|flags| !flags.is_empty()) {
+            create_info = create_info.push_next(&mut binding_flags_info);
+        }
         let raw = device.create_descriptor_set_layout(&create_info)?;
         log::debug!("Descriptor Set Layout created.");
 
@@ -53,6 +86,55 @@ impl DescriptorSetLayout {
             device: device.clone(),
         })
     }
+
+    fn validate_binding_flags(desc: &DescriptorSetLayoutCreateInfo) -> Result<(), DeviceError> {
+        let uses_descriptor_indexing = desc
+            .flags
+            .contains(vk::DescriptorSetLayoutCreateFlags::UPDATE_AFTER_BIND_POOL)
+            || desc
+                .bindings
+                .iter()
+                .any(|binding| !binding.binding_flags.is_empty());
+        if uses_descriptor_indexing && !desc.device.is_descriptor_indexing_enabled() {
+            log::error!("Descriptor binding flags require descriptor indexing to be enabled!");
+            return Err(DeviceError::NotMeetRequirement);
+        }
+        if desc
+            .flags
+            .contains(vk::DescriptorSetLayoutCreateFlags::PUSH_DESCRIPTOR_KHR)
+            && !desc.device.is_push_descriptor_enabled()
+        {
+            log::error!(
+                "Push descriptor set layouts require VK_KHR_push_descriptor to be enabled!"
+            );
+            return Err(DeviceError::NotMeetRequirement);
+        }
+
+        // 只有 binding 号最大的 binding 可以是可变数量的
+        let max_binding = desc.bindings.iter().map(|binding| binding.binding).max();
+        let invalid_variable_count = desc.bindings.iter().any(|binding| {
+            binding
+                .binding_flags
+                .contains(vk::DescriptorBindingFlags::VARIABLE_DESCRIPTOR_COUNT)
+                && Some(binding.binding) != max_binding
+        });
+        if invalid_variable_count {
+            return Err(DeviceError::Other(
+                "Only the binding with the largest number can have a variable descriptor count!",
+            ));
+        }
+
+        let invalid_input_attachment = desc.bindings.iter().any(|binding| {
+            binding.descriptor_type == vk::DescriptorType::INPUT_ATTACHMENT
+                && !vk::ShaderStageFlags::FRAGMENT.contains(binding.shader_stage_flags)
+        });
+        if invalid_input_attachment {
+            return Err(DeviceError::Other(
+                "Input attachment bindings can only be used in the fragment stage!",
+            ));
+        }
+        Ok(())
+    }
 }
 
 impl Drop for DescriptorSetLayout {
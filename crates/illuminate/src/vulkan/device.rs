@@ -1,14 +1,96 @@
 use std::ffi::CStr;
 
+use ash::extensions::khr;
 use ash::vk;
+use parking_lot::Mutex;
 
+use crate::vulkan::adapter::DeviceLimits;
+use crate::vulkan::conv;
 use crate::vulkan::debug::DebugUtils;
-use crate::DeviceError;
+use crate::vulkan::format::FormatExt;
+use crate::{DeviceError, VulkanCallExt};
+
+/// Profiling scope for command recording and resource creation, opt-in through the
+/// `profile-commands` feature since these calls are very frequent.
+macro_rules! command_scope {
+    ($name:expr) => {
+        #[cfg(feature = "profile-commands")]
+        profiling::scope!($name);
+    };
+}
 
 pub struct Device {
     /// Loads device local functions.
     raw: ash::Device,
     debug_utils: Option<DebugUtils>,
+    /// Features enabled when the logical device was created.
+    enabled_features: vk::PhysicalDeviceFeatures,
+    /// Extensions enabled when the logical device was created.
+    enabled_extensions: Vec<&'static CStr>,
+    limits: DeviceLimits,
+    /// Whether `VK_KHR_synchronization2` (core in Vulkan 1.3) is enabled.
+    synchronization2: bool,
+    /// Whether depth-only and stencil-only layouts (core in Vulkan 1.2) are enabled.
+    separate_depth_stencil_layouts: bool,
+    /// Whether viewports can have a negative height (core in Vulkan 1.1, `VK_KHR_maintenance1` before).
+    negative_viewport_height: bool,
+    /// Whether `VK_KHR_dynamic_rendering` (core in Vulkan 1.3) is enabled.
+    dynamic_rendering: bool,
+    /// Whether the descriptor indexing features for bindless resources are enabled.
+    descriptor_indexing: bool,
+    /// Whether the buffer_device_address feature (core in Vulkan 1.2) is enabled.
+    buffer_device_address: bool,
+    /// `VK_EXT_device_fault`, loaded only with the `device-fault` feature and when the device supports it.
+    device_fault: Option<vk::ExtDeviceFaultFn>,
+    /// `VK_KHR_push_descriptor`, loaded when the device supports it.
+    push_descriptor: Option<khr::PushDescriptor>,
+    /// Uncompressed color formats with the `VERTEX_BUFFER` buffer feature.
+    vertex_buffer_formats: fxhash::FxHashSet<vk::Format>,
+    /// Usage of every live buffer, used to validate descriptor writes in debug builds.
+    #[cfg(debug_assertions)]
+    buffer_usages: Mutex<fxhash::FxHashMap<vk::Buffer, vk::BufferUsageFlags>>,
+    /// Layout recorded by `set_image_layout` for every image created through this device, a debugging hint.
+    image_layouts: Mutex<fxhash::FxHashMap<vk::Image, vk::ImageLayout>>,
+    /// Extent of every framebuffer created through this device, used to clamp render areas.
+    framebuffer_extents: Mutex<fxhash::FxHashMap<vk::Framebuffer, vk::Extent2D>>,
+    /// Live objects per type, used to report resources outliving the device in debug builds.
+    #[cfg(debug_assertions)]
+    live_resources: Mutex<LiveResources>,
+}
+
+/// Live object count per type, see `Device::live_resources`.
+#[cfg(debug_assertions)]
+#[derive(Default)]
+struct LiveResources(fxhash::FxHashMap<&'static str, usize>);
+
+#[cfg(debug_assertions)]
+impl LiveResources {
+    fn created(&mut self, kind: &'static str, count: usize) {
+        *self.0.entry(kind).or_default() += count;
+    }
+
+    /// Returns `false` if no object of `kind` is live, i.e. it was not created through the device.
+    fn destroyed(&mut self, kind: &'static str) -> bool {
+        match self.0.get_mut(kind) {
+            Some(count) if *count > 0 => {
+                *count -= 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Types with live objects, sorted by name.
+    fn live(&self) -> Vec<(&'static str, usize)> {
+        let mut live: Vec<_> = self
+            .0
+            .iter()
+            .filter(|(_, &count)| count > 0)
+            .map(|(&kind, &count)| (kind, count))
+            .collect();
+        live.sort_unstable();
+        live
+    }
 }
 
 impl Device {
@@ -16,12 +98,269 @@ impl Device {
         &self.raw
     }
 
-    pub fn new(raw: ash::Device, debug_utils: Option<DebugUtils>) -> Self {
-        Self { raw, debug_utils }
+    pub fn enabled_features(&self) -> &vk::PhysicalDeviceFeatures {
+        &self.enabled_features
+    }
+
+    pub fn enabled_extensions(&self) -> &[&'static CStr] {
+        &self.enabled_extensions
+    }
+
+    pub fn limits(&self) -> &DeviceLimits {
+        &self.limits
+    }
+
+    pub fn is_synchronization2_enabled(&self) -> bool {
+        self.synchronization2
+    }
+
+    pub fn is_separate_depth_stencil_layouts_enabled(&self) -> bool {
+        self.separate_depth_stencil_layouts
+    }
+
+    /// Without it, flip Y in the projection matrix instead, see `conv::flip_projection_y`.
+    pub fn is_negative_viewport_height_supported(&self) -> bool {
+        self.negative_viewport_height
+    }
+
+    pub fn is_dynamic_rendering_enabled(&self) -> bool {
+        self.dynamic_rendering
+    }
+
+    pub fn is_descriptor_indexing_enabled(&self) -> bool {
+        self.descriptor_indexing
+    }
+
+    pub fn is_buffer_device_address_enabled(&self) -> bool {
+        self.buffer_device_address
+    }
+
+    /// Whether `format` can be used for vertex attributes.
+    pub fn supports_vertex_buffer_format(&self, format: vk::Format) -> bool {
+        self.vertex_buffer_formats.contains(&format)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        raw: ash::Device,
+        debug_utils: Option<DebugUtils>,
+        enabled_features: vk::PhysicalDeviceFeatures,
+        enabled_extensions: Vec<&'static CStr>,
+        limits: DeviceLimits,
+        synchronization2: bool,
+        separate_depth_stencil_layouts: bool,
+        negative_viewport_height: bool,
+        dynamic_rendering: bool,
+        descriptor_indexing: bool,
+        buffer_device_address: bool,
+        device_fault: Option<vk::ExtDeviceFaultFn>,
+        push_descriptor: Option<khr::PushDescriptor>,
+        vertex_buffer_formats: fxhash::FxHashSet<vk::Format>,
+    ) -> Self {
+        Self {
+            raw,
+            debug_utils,
+            enabled_features,
+            enabled_extensions,
+            limits,
+            synchronization2,
+            separate_depth_stencil_layouts,
+            negative_viewport_height,
+            dynamic_rendering,
+            descriptor_indexing,
+            buffer_device_address,
+            device_fault,
+            push_descriptor,
+            vertex_buffer_formats,
+            #[cfg(debug_assertions)]
+            buffer_usages: Default::default(),
+            image_layouts: Default::default(),
+            framebuffer_extents: Default::default(),
+            #[cfg(debug_assertions)]
+            live_resources: Default::default(),
+        }
+    }
+
+    #[cfg(debug_assertions)]
+    fn track_created(&self, kind: &'static str, count: usize) {
+        self.live_resources.lock().created(kind, count);
+    }
+
+    #[cfg(not(debug_assertions))]
+    fn track_created(&self, _kind: &'static str, _count: usize) {}
+
+    /// Destroying a null handle is a no-op in Vulkan, so it is not counted.
+    #[cfg(debug_assertions)]
+    fn track_destroyed(&self, kind: &'static str, handle: impl vk::Handle) {
+        if handle.as_raw() == 0 {
+            return;
+        }
+        if !self.live_resources.lock().destroyed(kind) {
+            log::warn!("Destroying a {} not created by this device.", kind);
+        }
+    }
+
+    #[cfg(not(debug_assertions))]
+    fn track_destroyed(&self, _kind: &'static str, _handle: impl vk::Handle) {}
+
+    /// Number of live objects per type created through this device, only tracked in debug builds.
+    #[cfg(debug_assertions)]
+    pub fn live_resources(&self) -> Vec<(&'static str, usize)> {
+        self.live_resources.lock().live()
+    }
+
+    pub fn wait_idle(&self) -> Result<(), DeviceError> {
+        unsafe { self.raw.device_wait_idle() }.map_err(|e| self.map_device_lost(e))
+    }
+
+    pub fn is_push_descriptor_enabled(&self) -> bool {
+        self.push_descriptor.is_some()
+    }
+
+    pub fn is_device_fault_enabled(&self) -> bool {
+        self.device_fault.is_some()
+    }
+
+    /// Maps `ERROR_DEVICE_LOST` to `DeviceError::Lost`, logging the fault report first if available.
+    fn map_device_lost(&self, result: vk::Result) -> DeviceError {
+        if result != vk::Result::ERROR_DEVICE_LOST {
+            return result.into();
+        }
+        log::error!("Vulkan device lost!");
+        if let Err(e) = self.log_device_fault() {
+            log::error!("Failed to query device fault info: {}", e);
+        }
+        DeviceError::Lost
     }
 
-    pub fn wait_idle(&self) {
-        unsafe { self.raw.device_wait_idle().unwrap() }
+    /// Logs the `VK_EXT_device_fault` report of a lost device, does nothing if the extension is not loaded.
+    fn log_device_fault(&self) -> Result<(), DeviceError> {
+        let Some(device_fault) = &self.device_fault else {
+            return Ok(());
+        };
+        let device = self.raw.handle();
+        let mut counts = vk::DeviceFaultCountsEXT::default();
+        unsafe {
+            (device_fault.get_device_fault_info_ext)(device, &mut counts, core::ptr::null_mut())
+        }
+        .result()
+        .vulkan_call("vkGetDeviceFaultInfoEXT")?;
+
+        // 厂商二进制数据不解析
+        counts.vendor_binary_size = 0;
+        let mut address_infos =
+            vec![vk::DeviceFaultAddressInfoEXT::default(); counts.address_info_count as usize];
+        let mut vendor_infos =
+            vec![vk::DeviceFaultVendorInfoEXT::default(); counts.vendor_info_count as usize];
+        let mut info = vk::DeviceFaultInfoEXT {
+            p_address_infos: address_infos.as_mut_ptr(),
+            p_vendor_infos: vendor_infos.as_mut_ptr(),
+            ..Default::default()
+        };
+        // 没有请求厂商二进制数据时返回 INCOMPLETE，其余信息仍然有效
+        match unsafe { (device_fault.get_device_fault_info_ext)(device, &mut counts, &mut info) } {
+            vk::Result::SUCCESS | vk::Result::INCOMPLETE => {}
+            result => return Err(result).vulkan_call("vkGetDeviceFaultInfoEXT"),
+        }
+
+        let description = unsafe { CStr::from_ptr(info.description.as_ptr()) };
+        log::error!("Device fault: {}", description.to_string_lossy());
+        for address_info in address_infos
+            .iter()
+            .take(counts.address_info_count as usize)
+        {
+            log::error!(
+                "  {:?} at {:#x} (precision {:#x})",
+                address_info.address_type,
+                address_info.reported_address,
+                address_info.address_precision,
+            );
+        }
+        for vendor_info in vendor_infos.iter().take(counts.vendor_info_count as usize) {
+            let description = unsafe { CStr::from_ptr(vendor_info.description.as_ptr()) };
+            log::error!(
+                "  vendor fault {:#x} (data {:#x}): {}",
+                vendor_info.vendor_fault_code,
+                vendor_info.vendor_fault_data,
+                description.to_string_lossy(),
+            );
+        }
+        Ok(())
+    }
+
+    fn validate_image_create_info(
+        &self,
+        create_info: &vk::ImageCreateInfo,
+    ) -> Result<(), DeviceError> {
+        let extent = create_info.extent;
+        let (limit, max, dimensions) = match create_info.image_type {
+            vk::ImageType::TYPE_1D => (
+                "maxImageDimension1D",
+                self.limits.max_image_dimension_1d,
+                vec![extent.width],
+            ),
+            vk::ImageType::TYPE_3D => (
+                "maxImageDimension3D",
+                self.limits.max_image_dimension_3d,
+                vec![extent.width, extent.height, extent.depth],
+            ),
+            _ if create_info
+                .flags
+                .contains(vk::ImageCreateFlags::CUBE_COMPATIBLE) =>
+            {
+                (
+                    "maxImageDimensionCube",
+                    self.limits.max_image_dimension_cube,
+                    vec![extent.width, extent.height],
+                )
+            }
+            _ => (
+                "maxImageDimension2D",
+                self.limits.max_image_dimension_2d,
+                vec![extent.width, extent.height],
+            ),
+        };
+        if let Some(&value) = dimensions.iter().find(|&&dimension| dimension > max) {
+            log::error!("Image extent {:?} exceeds {} ({}).", extent, limit, max);
+            return Err(DeviceError::LimitExceeded { limit, value, max });
+        }
+        if create_info.array_layers > self.limits.max_image_array_layers {
+            log::error!(
+                "Image has {} array layers, exceeds maxImageArrayLayers ({}).",
+                create_info.array_layers,
+                self.limits.max_image_array_layers
+            );
+            return Err(DeviceError::LimitExceeded {
+                limit: "maxImageArrayLayers",
+                value: create_info.array_layers,
+                max: self.limits.max_image_array_layers,
+            });
+        }
+
+        // 采样图像受 sampledImage*SampleCounts 限制，仅作为附件的图像受 framebuffer*SampleCounts 限制
+        let is_depth_stencil = create_info.format.is_depth_stencil();
+        let sampled = create_info.usage.contains(vk::ImageUsageFlags::SAMPLED);
+        let supported = match (sampled, is_depth_stencil) {
+            (true, true) => self.limits.sampled_image_depth_sample_counts,
+            (true, false) => self.limits.sampled_image_color_sample_counts,
+            (false, true) => self.limits.framebuffer_depth_sample_counts,
+            (false, false) => self.limits.framebuffer_color_sample_counts,
+        };
+        if create_info.samples != vk::SampleCountFlags::TYPE_1
+            && !supported.contains(create_info.samples)
+        {
+            log::error!(
+                "Image with format {:?} does not support {:?} samples, supported: {:?}.",
+                create_info.format,
+                create_info.samples,
+                supported
+            );
+            return Err(DeviceError::UnsupportedSampleCount {
+                samples: create_info.samples,
+                supported,
+            });
+        }
+        Ok(())
     }
 
     pub fn get_image_memory_requirements(&self, image: vk::Image) -> vk::MemoryRequirements {
@@ -32,6 +371,20 @@ impl Device {
         unsafe { self.raw.get_buffer_memory_requirements(buffer) }
     }
 
+    /// The buffer must be created with `SHADER_DEVICE_ADDRESS` usage and bound to memory
+    /// allocated with `DEVICE_ADDRESS`, which the allocator does when the feature is enabled.
+    pub fn get_buffer_device_address(
+        &self,
+        buffer: vk::Buffer,
+    ) -> Result<vk::DeviceAddress, DeviceError> {
+        if !self.buffer_device_address {
+            log::error!("buffer_device_address is not enabled at device creation!");
+            return Err(DeviceError::NotSupport);
+        }
+        let info = vk::BufferDeviceAddressInfo::builder().buffer(buffer);
+        Ok(unsafe { self.raw.get_buffer_device_address(&info) })
+    }
+
     pub unsafe fn bind_buffer_memory(
         &self,
         buffer: vk::Buffer,
@@ -52,27 +405,63 @@ impl Device {
         Ok(())
     }
 
+    /// Checks the extent, array layers and sample count against the device limits, the driver
+    /// may otherwise fail without a useful error.
     pub fn create_image(
         &self,
         create_info: &vk::ImageCreateInfo,
     ) -> Result<vk::Image, DeviceError> {
-        Ok(unsafe { self.raw.create_image(create_info, None)? })
+        command_scope!("create_image");
+        self.validate_image_create_info(create_info)?;
+        let image = unsafe {
+            self.raw
+                .create_image(create_info, None)
+                .vulkan_call("vkCreateImage")?
+        };
+        self.image_layouts
+            .lock()
+            .insert(image, create_info.initial_layout);
+        self.track_created("image", 1);
+        Ok(image)
     }
 
     pub fn destroy_image(&self, image: vk::Image) {
+        self.track_destroyed("image", image);
+        self.image_layouts.lock().remove(&image);
         unsafe {
             self.raw.destroy_image(image, None);
         }
     }
 
+    /// Returns the layout last recorded by `set_image_layout`, `None` if the image is not created
+    /// by this device. Render pass `final_layout`s and raw barriers are not tracked, so treat it as
+    /// a debugging hint only.
+    pub fn image_layout(&self, image: vk::Image) -> Option<vk::ImageLayout> {
+        self.image_layouts.lock().get(&image).copied()
+    }
+
+    pub fn set_image_layout(&self, image: vk::Image, layout: vk::ImageLayout) {
+        if let Some(tracked) = self.image_layouts.lock().get_mut(&image) {
+            *tracked = layout;
+        }
+    }
+
     pub fn create_image_view(
         &self,
         create_info: &vk::ImageViewCreateInfo,
     ) -> Result<vk::ImageView, DeviceError> {
-        Ok(unsafe { self.raw.create_image_view(create_info, None)? })
+        command_scope!("create_image_view");
+        let image_view = unsafe {
+            self.raw
+                .create_image_view(create_info, None)
+                .vulkan_call("vkCreateImageView")?
+        };
+        self.track_created("image_view", 1);
+        Ok(image_view)
     }
 
     pub fn destroy_image_view(&self, image_view: vk::ImageView) {
+        self.track_destroyed("image_view", image_view);
         unsafe {
             self.raw.destroy_image_view(image_view, None);
         }
@@ -82,10 +471,14 @@ impl Device {
         &self,
         create_info: &vk::ShaderModuleCreateInfo,
     ) -> Result<vk::ShaderModule, DeviceError> {
-        Ok(unsafe { self.raw.create_shader_module(create_info, None)? })
+        command_scope!("create_shader_module");
+        let shader_module = unsafe { self.raw.create_shader_module(create_info, None)? };
+        self.track_created("shader_module", 1);
+        Ok(shader_module)
     }
 
     pub fn destroy_shader_module(&self, shader_module: vk::ShaderModule) {
+        self.track_destroyed("shader_module", shader_module);
         unsafe {
             self.raw.destroy_shader_module(shader_module, None);
         }
@@ -99,10 +492,14 @@ impl Device {
         &self,
         create_info: &vk::RenderPassCreateInfo,
     ) -> Result<vk::RenderPass, DeviceError> {
-        Ok(unsafe { self.raw.create_render_pass(create_info, None)? })
+        command_scope!("create_render_pass");
+        let render_pass = unsafe { self.raw.create_render_pass(create_info, None)? };
+        self.track_created("render_pass", 1);
+        Ok(render_pass)
     }
 
     pub fn destroy_render_pass(&self, render_pass: vk::RenderPass) {
+        self.track_destroyed("render_pass", render_pass);
         unsafe { self.raw.destroy_render_pass(render_pass, None) }
     }
 
@@ -110,10 +507,22 @@ impl Device {
         &self,
         create_info: &vk::FramebufferCreateInfo,
     ) -> Result<vk::Framebuffer, DeviceError> {
-        Ok(unsafe { self.raw.create_framebuffer(create_info, None)? })
+        command_scope!("create_framebuffer");
+        let framebuffer = unsafe { self.raw.create_framebuffer(create_info, None)? };
+        self.track_created("framebuffer", 1);
+        self.framebuffer_extents.lock().insert(
+            framebuffer,
+            vk::Extent2D {
+                width: create_info.width,
+                height: create_info.height,
+            },
+        );
+        Ok(framebuffer)
     }
 
     pub fn destroy_framebuffer(&self, framebuffer: vk::Framebuffer) {
+        self.track_destroyed("framebuffer", framebuffer);
+        self.framebuffer_extents.lock().remove(&framebuffer);
         unsafe { self.raw.destroy_framebuffer(framebuffer, None) }
     }
 
@@ -121,10 +530,14 @@ impl Device {
         &self,
         create_info: &vk::SamplerCreateInfo,
     ) -> Result<vk::Sampler, DeviceError> {
-        Ok(unsafe { self.raw.create_sampler(create_info, None)? })
+        command_scope!("create_sampler");
+        let sampler = unsafe { self.raw.create_sampler(create_info, None)? };
+        self.track_created("sampler", 1);
+        Ok(sampler)
     }
 
     pub fn destroy_sampler(&self, sampler: vk::Sampler) {
+        self.track_destroyed("sampler", sampler);
         unsafe { self.raw.destroy_sampler(sampler, None) }
     }
 
@@ -132,10 +545,14 @@ impl Device {
         &self,
         create_info: &vk::PipelineLayoutCreateInfo,
     ) -> Result<vk::PipelineLayout, DeviceError> {
-        Ok(unsafe { self.raw.create_pipeline_layout(create_info, None)? })
+        command_scope!("create_pipeline_layout");
+        let pipeline_layout = unsafe { self.raw.create_pipeline_layout(create_info, None)? };
+        self.track_created("pipeline_layout", 1);
+        Ok(pipeline_layout)
     }
 
     pub fn destroy_pipeline_layout(&self, pipeline_layout: vk::PipelineLayout) {
+        self.track_destroyed("pipeline_layout", pipeline_layout);
         unsafe { self.raw.destroy_pipeline_layout(pipeline_layout, None) }
     }
 
@@ -143,14 +560,19 @@ impl Device {
         &self,
         create_infos: &[vk::GraphicsPipelineCreateInfo],
     ) -> Result<Vec<vk::Pipeline>, DeviceError> {
-        Ok(unsafe {
+        command_scope!("create_graphics_pipelines");
+        let pipelines = unsafe {
             self.raw
                 .create_graphics_pipelines(vk::PipelineCache::default(), create_infos, None)
-                .map_err(|e| e.1)?
-        })
+                .map_err(|e| e.1)
+                .vulkan_call("vkCreateGraphicsPipelines")?
+        };
+        self.track_created("pipeline", pipelines.len());
+        Ok(pipelines)
     }
 
     pub fn destroy_pipeline(&self, pipeline: vk::Pipeline) {
+        self.track_destroyed("pipeline", pipeline);
         unsafe { self.raw.destroy_pipeline(pipeline, None) }
     }
 
@@ -158,10 +580,14 @@ impl Device {
         &self,
         create_info: &vk::CommandPoolCreateInfo,
     ) -> Result<vk::CommandPool, DeviceError> {
-        Ok(unsafe { self.raw.create_command_pool(create_info, None)? })
+        command_scope!("create_command_pool");
+        let command_pool = unsafe { self.raw.create_command_pool(create_info, None)? };
+        self.track_created("command_pool", 1);
+        Ok(command_pool)
     }
 
     pub fn destroy_command_pool(&self, command_pool: vk::CommandPool) {
+        self.track_destroyed("command_pool", command_pool);
         unsafe { self.raw.destroy_command_pool(command_pool, None) }
     }
 
@@ -201,13 +627,11 @@ impl Device {
         submits: &[vk::SubmitInfo],
         fence: vk::Fence,
     ) -> Result<(), DeviceError> {
-        unsafe { self.raw.queue_submit(queue, submits, fence)? };
-        Ok(())
+        unsafe { self.raw.queue_submit(queue, submits, fence) }.map_err(|e| self.map_device_lost(e))
     }
 
     pub fn queue_wait_idle(&self, queue: vk::Queue) -> Result<(), DeviceError> {
-        unsafe { self.raw.queue_wait_idle(queue)? };
-        Ok(())
+        unsafe { self.raw.queue_wait_idle(queue) }.map_err(|e| self.map_device_lost(e))
     }
 
     pub fn free_command_buffers(
@@ -231,10 +655,18 @@ impl Device {
         &self,
         create_info: &vk::BufferCreateInfo,
     ) -> Result<vk::Buffer, DeviceError> {
-        Ok(unsafe { self.raw.create_buffer(create_info, None)? })
+        command_scope!("create_buffer");
+        let buffer = unsafe { self.raw.create_buffer(create_info, None)? };
+        #[cfg(debug_assertions)]
+        self.buffer_usages.lock().insert(buffer, create_info.usage);
+        self.track_created("buffer", 1);
+        Ok(buffer)
     }
 
     pub fn destroy_buffer(&self, buffer: vk::Buffer) {
+        self.track_destroyed("buffer", buffer);
+        #[cfg(debug_assertions)]
+        self.buffer_usages.lock().remove(&buffer);
         unsafe { self.raw.destroy_buffer(buffer, None) }
     }
 
@@ -256,10 +688,15 @@ impl Device {
         &self,
         create_info: &vk::DescriptorSetLayoutCreateInfo,
     ) -> Result<vk::DescriptorSetLayout, DeviceError> {
-        Ok(unsafe { self.raw.create_descriptor_set_layout(create_info, None)? })
+        command_scope!("create_descriptor_set_layout");
+        let descriptor_set_layout =
+            unsafe { self.raw.create_descriptor_set_layout(create_info, None)? };
+        self.track_created("descriptor_set_layout", 1);
+        Ok(descriptor_set_layout)
     }
 
     pub fn destroy_descriptor_set_layout(&self, layout: vk::DescriptorSetLayout) {
+        self.track_destroyed("descriptor_set_layout", layout);
         unsafe { self.raw.destroy_descriptor_set_layout(layout, None) }
     }
 
@@ -267,13 +704,26 @@ impl Device {
         &self,
         create_info: &vk::DescriptorPoolCreateInfo,
     ) -> Result<vk::DescriptorPool, DeviceError> {
-        Ok(unsafe { self.raw.create_descriptor_pool(create_info, None)? })
+        command_scope!("create_descriptor_pool");
+        let descriptor_pool = unsafe { self.raw.create_descriptor_pool(create_info, None)? };
+        self.track_created("descriptor_pool", 1);
+        Ok(descriptor_pool)
     }
 
     pub fn destroy_descriptor_pool(&self, pool: vk::DescriptorPool) {
+        self.track_destroyed("descriptor_pool", pool);
         unsafe { self.raw.destroy_descriptor_pool(pool, None) }
     }
 
+    /// Returns all descriptor sets allocated from the pool back to it.
+    pub fn reset_descriptor_pool(&self, pool: vk::DescriptorPool) -> Result<(), DeviceError> {
+        unsafe {
+            self.raw
+                .reset_descriptor_pool(pool, vk::DescriptorPoolResetFlags::empty())?
+        }
+        Ok(())
+    }
+
     pub fn allocate_descriptor_sets(
         &self,
         create_info: &vk::DescriptorSetAllocateInfo,
@@ -286,12 +736,115 @@ impl Device {
         descriptor_writes: &[vk::WriteDescriptorSet],
         descriptor_copies: &[vk::CopyDescriptorSet],
     ) {
+        #[cfg(debug_assertions)]
+        self.validate_buffer_descriptor_writes(descriptor_writes);
+        #[cfg(debug_assertions)]
+        self.validate_input_attachment_descriptor_writes(descriptor_writes);
         unsafe {
             self.raw
                 .update_descriptor_sets(descriptor_writes, descriptor_copies)
         }
     }
 
+    /// Checks that buffers bound to uniform/storage descriptors were created with the matching usage.
+    #[cfg(debug_assertions)]
+    fn validate_buffer_descriptor_writes(&self, descriptor_writes: &[vk::WriteDescriptorSet]) {
+        let buffer_usages = self.buffer_usages.lock();
+        for write in descriptor_writes {
+            let required_usage = match write.descriptor_type {
+                vk::DescriptorType::UNIFORM_BUFFER | vk::DescriptorType::UNIFORM_BUFFER_DYNAMIC => {
+                    vk::BufferUsageFlags::UNIFORM_BUFFER
+                }
+                vk::DescriptorType::STORAGE_BUFFER | vk::DescriptorType::STORAGE_BUFFER_DYNAMIC => {
+                    vk::BufferUsageFlags::STORAGE_BUFFER
+                }
+                _ => continue,
+            };
+            if write.p_buffer_info.is_null() {
+                continue;
+            }
+            let buffer_infos = unsafe {
+                std::slice::from_raw_parts(write.p_buffer_info, write.descriptor_count as usize)
+            };
+            let is_dynamic = matches!(
+                write.descriptor_type,
+                vk::DescriptorType::UNIFORM_BUFFER_DYNAMIC
+                    | vk::DescriptorType::STORAGE_BUFFER_DYNAMIC
+            );
+            for (i, buffer_info) in buffer_infos.iter().enumerate() {
+                // 动态偏移会加到 offset 上，WHOLE_SIZE 会让非零的动态偏移越界
+                if is_dynamic && buffer_info.range == vk::WHOLE_SIZE {
+                    log::warn!(
+                        "Buffer {:?} is bound to a {:?} descriptor (binding {}, array element {}) with WHOLE_SIZE range, any non-zero dynamic offset will be out of bounds.",
+                        buffer_info.buffer,
+                        write.descriptor_type,
+                        write.dst_binding,
+                        write.dst_array_element + i as u32
+                    );
+                }
+                let usage = match buffer_usages.get(&buffer_info.buffer) {
+                    Some(usage) => *usage,
+                    None => continue,
+                };
+                if !usage.contains(required_usage) {
+                    log::error!(
+                        "Buffer {:?} with usage {:?} is bound to a {:?} descriptor (binding {}, array element {}), which requires {:?} usage!",
+                        buffer_info.buffer,
+                        usage,
+                        write.descriptor_type,
+                        write.dst_binding,
+                        write.dst_array_element + i as u32,
+                        required_usage
+                    );
+                    debug_assert!(
+                        usage.contains(required_usage),
+                        "buffer usage does not match descriptor type"
+                    );
+                }
+            }
+        }
+    }
+
+    /// Input attachments are read in the subpass that renders to the attachment's framebuffer,
+    /// so the image must be in a layout readable by the shader.
+    #[cfg(debug_assertions)]
+    fn validate_input_attachment_descriptor_writes(
+        &self,
+        descriptor_writes: &[vk::WriteDescriptorSet],
+    ) {
+        for write in descriptor_writes {
+            if write.descriptor_type != vk::DescriptorType::INPUT_ATTACHMENT
+                || write.p_image_info.is_null()
+            {
+                continue;
+            }
+            let image_infos = unsafe {
+                std::slice::from_raw_parts(write.p_image_info, write.descriptor_count as usize)
+            };
+            for (i, image_info) in image_infos.iter().enumerate() {
+                let readable = matches!(
+                    image_info.image_layout,
+                    vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL
+                        | vk::ImageLayout::GENERAL
+                        | vk::ImageLayout::DEPTH_STENCIL_READ_ONLY_OPTIMAL
+                        | vk::ImageLayout::DEPTH_READ_ONLY_OPTIMAL
+                        | vk::ImageLayout::STENCIL_READ_ONLY_OPTIMAL
+                        | vk::ImageLayout::READ_ONLY_OPTIMAL
+                );
+                if !readable {
+                    log::error!(
+                        "Image view {:?} is bound to an input attachment descriptor (binding {}, array element {}) with {:?} layout, use SHADER_READ_ONLY_OPTIMAL or GENERAL!",
+                        image_info.image_view,
+                        write.dst_binding,
+                        write.dst_array_element + i as u32,
+                        image_info.image_layout
+                    );
+                    debug_assert!(readable, "input attachment layout is not readable");
+                }
+            }
+        }
+    }
+
     pub fn free_descriptor_sets(
         &self,
         pool: vk::DescriptorPool,
@@ -301,23 +854,63 @@ impl Device {
         Ok(())
     }
 
+    /// The render area is clamped to the framebuffer extent, a stale area after a resize would
+    /// otherwise be a validation error.
     pub fn cmd_begin_render_pass(
         &self,
         command_buffer: vk::CommandBuffer,
         begin_info: &vk::RenderPassBeginInfo,
         contents: vk::SubpassContents,
     ) {
+        command_scope!("cmd_begin_render_pass");
+        let mut begin_info = *begin_info;
+        let extent = self
+            .framebuffer_extents
+            .lock()
+            .get(&begin_info.framebuffer)
+            .copied();
+        if let Some(extent) = extent {
+            let render_area = begin_info.render_area;
+            let clamped = conv::convert_rect2d(
+                math::Rect2D::new(
+                    render_area.offset.x as f32,
+                    render_area.offset.y as f32,
+                    render_area.extent.width as f32,
+                    render_area.extent.height as f32,
+                )
+                .clamp_to_extent(extent.width as f32, extent.height as f32),
+            );
+            if clamped != render_area {
+                #[cfg(debug_assertions)]
+                log::warn!(
+                    "Render area {:?} exceeds the framebuffer extent {:?}, clamped to {:?}.",
+                    render_area,
+                    extent,
+                    clamped
+                );
+                begin_info.render_area = clamped;
+            }
+        }
         unsafe {
             self.raw
-                .cmd_begin_render_pass(command_buffer, begin_info, contents);
+                .cmd_begin_render_pass(command_buffer, &begin_info, contents);
         }
     }
 
     pub fn cmd_end_render_pass(&self, command_buffer: vk::CommandBuffer) {
+        command_scope!("cmd_end_render_pass");
         unsafe { self.raw.cmd_end_render_pass(command_buffer) }
     }
 
+    /// Degenerate viewports (e.g. while the window is being resized) are skipped in debug builds.
+    /// A negative height is allowed, it flips the Y axis.
     pub fn cmd_set_viewport(&self, command_buffer: vk::CommandBuffer, viewport: math::Rect2D) {
+        command_scope!("cmd_set_viewport");
+        #[cfg(debug_assertions)]
+        if viewport.width <= 0.0 || viewport.height == 0.0 {
+            log::warn!("Skip degenerate viewport {:?}.", viewport);
+            return;
+        }
         unsafe {
             let vp = vk::Viewport::builder()
                 .x(viewport.x)
@@ -330,24 +923,157 @@ impl Device {
             self.raw.cmd_set_viewport(command_buffer, 0, &[vp])
         }
     }
+
+    /// Sets viewports starting at `first_viewport`, indices past 0 need the `multi_viewport` feature.
+    pub fn cmd_set_viewports(
+        &self,
+        command_buffer: vk::CommandBuffer,
+        first_viewport: u32,
+        viewports: &[vk::Viewport],
+    ) {
+        command_scope!("cmd_set_viewports");
+        if !self.validate_viewport_range("viewports", first_viewport, viewports.len()) {
+            return;
+        }
+        unsafe {
+            self.raw
+                .cmd_set_viewport(command_buffer, first_viewport, viewports)
+        }
+    }
+
+    /// Without `multi_viewport` only index 0 can be set, and never more than `maxViewports`.
+    fn validate_viewport_range(&self, kind: &str, first: u32, count: usize) -> bool {
+        let end = first as usize + count;
+        if end > 1 && self.enabled_features.multi_viewport == vk::FALSE {
+            log::warn!(
+                "multi_viewport is not enabled, {} {}..{} are ignored.",
+                kind,
+                first,
+                end
+            );
+            return false;
+        }
+        if end > self.limits.max_viewports as usize {
+            log::warn!(
+                "{} {}..{} exceed maxViewports ({}), they are ignored.",
+                kind,
+                first,
+                end,
+                self.limits.max_viewports
+            );
+            return false;
+        }
+        true
+    }
+    /// Skipped in debug builds if any scissor has a zero extent, dropping a single entry would
+    /// shift the indices of the following scissors.
     pub fn cmd_set_scissor(
         &self,
         command_buffer: vk::CommandBuffer,
         first_scissor: u32,
         scissors: &[vk::Rect2D],
     ) {
+        command_scope!("cmd_set_scissor");
+        #[cfg(debug_assertions)]
+        if scissors
+            .iter()
+            .any(|scissor| scissor.extent.width == 0 || scissor.extent.height == 0)
+        {
+            log::warn!("Skip degenerate scissors {:?}.", scissors);
+            return;
+        }
+        if !self.validate_viewport_range("scissors", first_scissor, scissors.len()) {
+            return;
+        }
         unsafe {
             self.raw
                 .cmd_set_scissor(command_buffer, first_scissor, scissors)
         }
     }
 
+    /// Widths other than 1.0 need `AdapterRequirements::wide_lines`, otherwise 1.0 is used.
+    pub fn cmd_set_line_width(&self, command_buffer: vk::CommandBuffer, line_width: f32) {
+        command_scope!("cmd_set_line_width");
+        let line_width = if line_width != 1.0 && self.enabled_features.wide_lines == vk::FALSE {
+            log::warn!(
+                "wide_lines is not enabled, line width {} is ignored.",
+                line_width
+            );
+            1.0
+        } else {
+            line_width
+        };
+        unsafe { self.raw.cmd_set_line_width(command_buffer, line_width) }
+    }
+
+    /// Needs the `depth_bounds` feature and a pipeline with the `DEPTH_BOUNDS` dynamic state.
+    pub fn cmd_set_depth_bounds(
+        &self,
+        command_buffer: vk::CommandBuffer,
+        min_depth_bounds: f32,
+        max_depth_bounds: f32,
+    ) {
+        command_scope!("cmd_set_depth_bounds");
+        if self.enabled_features.depth_bounds == vk::FALSE {
+            log::warn!("depth_bounds is not enabled, depth bounds are ignored.");
+            return;
+        }
+        unsafe {
+            self.raw
+                .cmd_set_depth_bounds(command_buffer, min_depth_bounds, max_depth_bounds)
+        }
+    }
+
+    /// A non-zero clamp needs `AdapterRequirements::depth_bias_clamp`, otherwise the bias is not
+    /// clamped.
+    pub fn cmd_set_depth_bias(
+        &self,
+        command_buffer: vk::CommandBuffer,
+        constant_factor: f32,
+        clamp: f32,
+        slope_factor: f32,
+    ) {
+        command_scope!("cmd_set_depth_bias");
+        let clamp = if clamp != 0.0 && self.enabled_features.depth_bias_clamp == vk::FALSE {
+            log::warn!(
+                "depth_bias_clamp is not enabled, depth bias clamp {} is ignored.",
+                clamp
+            );
+            0.0
+        } else {
+            clamp
+        };
+        unsafe {
+            self.raw
+                .cmd_set_depth_bias(command_buffer, constant_factor, clamp, slope_factor)
+        }
+    }
+
+    pub fn cmd_set_blend_constants(&self, command_buffer: vk::CommandBuffer, constants: [f32; 4]) {
+        command_scope!("cmd_set_blend_constants");
+        unsafe { self.raw.cmd_set_blend_constants(command_buffer, &constants) }
+    }
+
+    pub fn cmd_set_stencil_reference(
+        &self,
+        command_buffer: vk::CommandBuffer,
+        face_mask: vk::StencilFaceFlags,
+        reference: u32,
+    ) {
+        command_scope!("cmd_set_stencil_reference");
+        unsafe {
+            self.raw
+                .cmd_set_stencil_reference(command_buffer, face_mask, reference)
+        }
+    }
+
     pub fn cmd_bind_pipeline(
         &self,
         command_buffer: vk::CommandBuffer,
         pipeline_bind_point: vk::PipelineBindPoint,
         pipeline: vk::Pipeline,
     ) {
+        command_scope!("cmd_bind_pipeline");
         unsafe {
             self.raw
                 .cmd_bind_pipeline(command_buffer, pipeline_bind_point, pipeline);
@@ -362,6 +1088,7 @@ impl Device {
         first_vertex: u32,
         first_instance: u32,
     ) {
+        command_scope!("cmd_draw");
         unsafe {
             self.raw.cmd_draw(
                 command_buffer,
@@ -382,6 +1109,7 @@ impl Device {
         vertex_offset: i32,
         first_instance: u32,
     ) {
+        command_scope!("cmd_draw_indexed");
         unsafe {
             self.raw.cmd_draw_indexed(
                 command_buffer,
@@ -394,6 +1122,42 @@ impl Device {
         }
     }
 
+    /// Records `draw_count` indexed draws whose parameters are read from `buffer`.
+    ///
+    /// Without the `multi_draw_indirect` feature only one draw is allowed per call, so the draws
+    /// are recorded one by one instead.
+    pub fn cmd_draw_indexed_indirect(
+        &self,
+        command_buffer: vk::CommandBuffer,
+        buffer: vk::Buffer,
+        offset: vk::DeviceSize,
+        draw_count: u32,
+        stride: u32,
+    ) {
+        command_scope!("cmd_draw_indexed_indirect");
+        unsafe {
+            if draw_count <= 1 || self.enabled_features.multi_draw_indirect == vk::TRUE {
+                self.raw.cmd_draw_indexed_indirect(
+                    command_buffer,
+                    buffer,
+                    offset,
+                    draw_count,
+                    stride,
+                );
+            } else {
+                for i in 0..draw_count {
+                    self.raw.cmd_draw_indexed_indirect(
+                        command_buffer,
+                        buffer,
+                        offset + i as vk::DeviceSize * stride as vk::DeviceSize,
+                        1,
+                        stride,
+                    );
+                }
+            }
+        }
+    }
+
     pub fn cmd_bind_vertex_buffers(
         &self,
         command_buffer: vk::CommandBuffer,
@@ -401,6 +1165,7 @@ impl Device {
         buffers: &[vk::Buffer],
         offsets: &[vk::DeviceSize],
     ) {
+        command_scope!("cmd_bind_vertex_buffers");
         unsafe {
             self.raw
                 .cmd_bind_vertex_buffers(command_buffer, first_binding, buffers, offsets);
@@ -414,6 +1179,7 @@ impl Device {
         offset: vk::DeviceSize,
         index_type: vk::IndexType,
     ) {
+        command_scope!("cmd_bind_index_buffer");
         unsafe {
             self.raw
                 .cmd_bind_index_buffer(command_buffer, buffer, offset, index_type);
@@ -429,6 +1195,8 @@ impl Device {
         descriptor_sets: &[vk::DescriptorSet],
         dynamic_offsets: &[u32],
     ) {
+        command_scope!("cmd_bind_descriptor_sets");
+        // 动态偏移按描述符集和 binding 的顺序对应每个 *_DYNAMIC 描述符
         unsafe {
             self.raw.cmd_bind_descriptor_sets(
                 command_buffer,
@@ -441,6 +1209,33 @@ impl Device {
         }
     }
 
+    /// Writes descriptors straight into the command buffer instead of binding an allocated set.
+    ///
+    /// Set `set` of `layout` must be created with `PUSH_DESCRIPTOR_KHR`, `dst_set` of `writes` is ignored.
+    pub fn cmd_push_descriptor_set(
+        &self,
+        command_buffer: vk::CommandBuffer,
+        pipeline_bind_point: vk::PipelineBindPoint,
+        layout: vk::PipelineLayout,
+        set: u32,
+        writes: &[vk::WriteDescriptorSet],
+    ) {
+        command_scope!("cmd_push_descriptor_set");
+        let Some(push_descriptor) = &self.push_descriptor else {
+            log::error!("VK_KHR_push_descriptor is not enabled, descriptors are not pushed.");
+            return;
+        };
+        unsafe {
+            push_descriptor.cmd_push_descriptor_set(
+                command_buffer,
+                pipeline_bind_point,
+                layout,
+                set,
+                writes,
+            );
+        }
+    }
+
     pub fn cmd_copy_buffer(
         &self,
         command_buffer: vk::CommandBuffer,
@@ -448,6 +1243,7 @@ impl Device {
         dst_buffer: vk::Buffer,
         regions: &[vk::BufferCopy],
     ) {
+        command_scope!("cmd_copy_buffer");
         unsafe {
             self.raw
                 .cmd_copy_buffer(command_buffer, src_buffer, dst_buffer, regions);
@@ -465,6 +1261,7 @@ impl Device {
         buffer_memory_barriers: &[vk::BufferMemoryBarrier],
         image_memory_barriers: &[vk::ImageMemoryBarrier],
     ) {
+        command_scope!("cmd_pipeline_barrier");
         unsafe {
             self.raw.cmd_pipeline_barrier(
                 command_buffer,
@@ -478,6 +1275,95 @@ impl Device {
         }
     }
 
+    /// Records a barrier with 64-bit stage/access masks. Uses `vkCmdPipelineBarrier2` when
+    /// synchronization2 is enabled, otherwise the barriers are translated to the legacy
+    /// `vkCmdPipelineBarrier`, stage masks are merged and bits without a legacy equivalent widened.
+    pub fn cmd_pipeline_barrier2(
+        &self,
+        command_buffer: vk::CommandBuffer,
+        dependency_flags: vk::DependencyFlags,
+        memory_barriers: &[vk::MemoryBarrier2],
+        buffer_memory_barriers: &[vk::BufferMemoryBarrier2],
+        image_memory_barriers: &[vk::ImageMemoryBarrier2],
+    ) {
+        command_scope!("cmd_pipeline_barrier2");
+        if self.synchronization2 {
+            let dependency_info = vk::DependencyInfo::builder()
+                .dependency_flags(dependency_flags)
+                .memory_barriers(memory_barriers)
+                .buffer_memory_barriers(buffer_memory_barriers)
+                .image_memory_barriers(image_memory_barriers);
+            unsafe {
+                self.raw
+                    .cmd_pipeline_barrier2(command_buffer, &dependency_info);
+            }
+            return;
+        }
+
+        let mut src_stage_mask = vk::PipelineStageFlags2::NONE;
+        let mut dst_stage_mask = vk::PipelineStageFlags2::NONE;
+        let memory_barriers = memory_barriers
+            .iter()
+            .map(|barrier| {
+                src_stage_mask |= barrier.src_stage_mask;
+                dst_stage_mask |= barrier.dst_stage_mask;
+                vk::MemoryBarrier::builder()
+                    .src_access_mask(conv::convert_access_flags2(barrier.src_access_mask))
+                    .dst_access_mask(conv::convert_access_flags2(barrier.dst_access_mask))
+                    .build()
+            })
+            .collect::<Vec<_>>();
+        let buffer_memory_barriers = buffer_memory_barriers
+            .iter()
+            .map(|barrier| {
+                src_stage_mask |= barrier.src_stage_mask;
+                dst_stage_mask |= barrier.dst_stage_mask;
+                vk::BufferMemoryBarrier::builder()
+                    .src_access_mask(conv::convert_access_flags2(barrier.src_access_mask))
+                    .dst_access_mask(conv::convert_access_flags2(barrier.dst_access_mask))
+                    .src_queue_family_index(barrier.src_queue_family_index)
+                    .dst_queue_family_index(barrier.dst_queue_family_index)
+                    .buffer(barrier.buffer)
+                    .offset(barrier.offset)
+                    .size(barrier.size)
+                    .build()
+            })
+            .collect::<Vec<_>>();
+        let image_memory_barriers = image_memory_barriers
+            .iter()
+            .map(|barrier| {
+                src_stage_mask |= barrier.src_stage_mask;
+                dst_stage_mask |= barrier.dst_stage_mask;
+                vk::ImageMemoryBarrier::builder()
+                    .src_access_mask(conv::convert_access_flags2(barrier.src_access_mask))
+                    .dst_access_mask(conv::convert_access_flags2(barrier.dst_access_mask))
+                    .old_layout(barrier.old_layout)
+                    .new_layout(barrier.new_layout)
+                    .src_queue_family_index(barrier.src_queue_family_index)
+                    .dst_queue_family_index(barrier.dst_queue_family_index)
+                    .image(barrier.image)
+                    .subresource_range(barrier.subresource_range)
+                    .build()
+            })
+            .collect::<Vec<_>>();
+
+        self.cmd_pipeline_barrier(
+            command_buffer,
+            conv::convert_pipeline_stage_flags2(
+                src_stage_mask,
+                vk::PipelineStageFlags::TOP_OF_PIPE,
+            ),
+            conv::convert_pipeline_stage_flags2(
+                dst_stage_mask,
+                vk::PipelineStageFlags::BOTTOM_OF_PIPE,
+            ),
+            dependency_flags,
+            &memory_barriers,
+            &buffer_memory_barriers,
+            &image_memory_barriers,
+        );
+    }
+
     pub fn cmd_copy_buffer_to_image(
         &self,
         command_buffer: vk::CommandBuffer,
@@ -486,6 +1372,9 @@ impl Device {
         dst_image_layout: vk::ImageLayout,
         regions: &[vk::BufferImageCopy],
     ) {
+        command_scope!("cmd_copy_buffer_to_image");
+        #[cfg(debug_assertions)]
+        self.validate_transfer_dst_layout(dst_image, dst_image_layout);
         unsafe {
             self.raw.cmd_copy_buffer_to_image(
                 command_buffer,
@@ -497,6 +1386,82 @@ impl Device {
         }
     }
 
+    /// Copies into an image need it in `TRANSFER_DST_OPTIMAL` or `GENERAL` layout.
+    ///
+    /// The tracked layout is not compared, render pass `final_layout`s and raw barriers do not
+    /// update it, so it can be stale.
+    #[cfg(debug_assertions)]
+    fn validate_transfer_dst_layout(&self, image: vk::Image, layout: vk::ImageLayout) {
+        if !matches!(
+            layout,
+            vk::ImageLayout::TRANSFER_DST_OPTIMAL | vk::ImageLayout::GENERAL
+        ) {
+            log::error!(
+                "Copy destination image {:?} uses {:?} layout, use TRANSFER_DST_OPTIMAL or GENERAL!",
+                image,
+                layout
+            );
+        }
+    }
+
+    /// Clears a color image outside of a render pass, the image must be in `GENERAL` or
+    /// `TRANSFER_DST_OPTIMAL` layout and created with `TRANSFER_DST` usage.
+    pub fn cmd_clear_color_image(
+        &self,
+        command_buffer: vk::CommandBuffer,
+        image: vk::Image,
+        image_layout: vk::ImageLayout,
+        color: &vk::ClearColorValue,
+        ranges: &[vk::ImageSubresourceRange],
+    ) {
+        command_scope!("cmd_clear_color_image");
+        unsafe {
+            self.raw
+                .cmd_clear_color_image(command_buffer, image, image_layout, color, ranges);
+        }
+    }
+
+    /// Clears a depth/stencil image outside of a render pass.
+    pub fn cmd_clear_depth_stencil_image(
+        &self,
+        command_buffer: vk::CommandBuffer,
+        image: vk::Image,
+        image_layout: vk::ImageLayout,
+        depth_stencil: &vk::ClearDepthStencilValue,
+        ranges: &[vk::ImageSubresourceRange],
+    ) {
+        command_scope!("cmd_clear_depth_stencil_image");
+        unsafe {
+            self.raw.cmd_clear_depth_stencil_image(
+                command_buffer,
+                image,
+                image_layout,
+                depth_stencil,
+                ranges,
+            );
+        }
+    }
+
+    pub fn cmd_copy_image_to_buffer(
+        &self,
+        command_buffer: vk::CommandBuffer,
+        src_image: vk::Image,
+        src_image_layout: vk::ImageLayout,
+        dst_buffer: vk::Buffer,
+        regions: &[vk::BufferImageCopy],
+    ) {
+        command_scope!("cmd_copy_image_to_buffer");
+        unsafe {
+            self.raw.cmd_copy_image_to_buffer(
+                command_buffer,
+                src_image,
+                src_image_layout,
+                dst_buffer,
+                regions,
+            );
+        }
+    }
+
     /// graphics queue
     pub fn cmd_blit_image(
         &self,
@@ -508,6 +1473,7 @@ impl Device {
         regions: &[vk::ImageBlit],
         filter: vk::Filter,
     ) {
+        command_scope!("cmd_blit_image");
         unsafe {
             self.raw.cmd_blit_image(
                 command_buffer,
@@ -529,6 +1495,7 @@ impl Device {
         offset: u32,
         constants: &[u8],
     ) {
+        command_scope!("cmd_push_constants");
         unsafe {
             self.raw
                 .cmd_push_constants(command_buffer, layout, stage_flags, offset, constants);
@@ -539,10 +1506,14 @@ impl Device {
         &self,
         create_info: &vk::SemaphoreCreateInfo,
     ) -> Result<vk::Semaphore, DeviceError> {
-        Ok(unsafe { self.raw.create_semaphore(create_info, None)? })
+        command_scope!("create_semaphore");
+        let semaphore = unsafe { self.raw.create_semaphore(create_info, None)? };
+        self.track_created("semaphore", 1);
+        Ok(semaphore)
     }
 
     pub fn destroy_semaphore(&self, semaphore: vk::Semaphore) {
+        self.track_destroyed("semaphore", semaphore);
         unsafe { self.raw.destroy_semaphore(semaphore, None) }
     }
 
@@ -550,10 +1521,14 @@ impl Device {
         &self,
         create_info: &vk::FenceCreateInfo,
     ) -> Result<vk::Fence, DeviceError> {
-        Ok(unsafe { self.raw.create_fence(create_info, None)? })
+        command_scope!("create_fence");
+        let fence = unsafe { self.raw.create_fence(create_info, None)? };
+        self.track_created("fence", 1);
+        Ok(fence)
     }
 
     pub fn destroy_fence(&self, fence: vk::Fence) {
+        self.track_destroyed("fence", fence);
         unsafe { self.raw.destroy_fence(fence, None) }
     }
 
@@ -563,9 +1538,28 @@ impl Device {
         wait_all: bool,
         timeout: u64,
     ) -> Result<(), DeviceError> {
-        unsafe { self.raw.wait_for_fences(fences, wait_all, timeout)? };
-        Ok(())
+        unsafe { self.raw.wait_for_fences(fences, wait_all, timeout) }
+            .map_err(|e| self.map_device_lost(e))
     }
+
+    /// Returns `false` on timeout instead of an error, so callers can poll with a finite timeout.
+    pub fn wait_for_fence_with_timeout(
+        &self,
+        fence: vk::Fence,
+        timeout: u64,
+    ) -> Result<bool, DeviceError> {
+        match unsafe { self.raw.wait_for_fences(&[fence], true, timeout) } {
+            Ok(()) => Ok(true),
+            Err(vk::Result::TIMEOUT) => Ok(false),
+            Err(e) => Err(self.map_device_lost(e)),
+        }
+    }
+
+    /// Returns `true` if the fence is signaled.
+    pub fn get_fence_status(&self, fence: vk::Fence) -> Result<bool, DeviceError> {
+        Ok(unsafe { self.raw.get_fence_status(fence)? })
+    }
+
     pub fn reset_fence(&self, fences: &[vk::Fence]) -> Result<(), DeviceError> {
         unsafe { self.raw.reset_fences(fences)? };
         Ok(())
@@ -613,3 +1607,44 @@ impl Device {
         );
     }
 }
+
+impl Drop for Device {
+    fn drop(&mut self) {
+        // 资源持有 Rc<Device>，这里仍存活的对象说明有句柄没有经过 Device 销毁。
+        // 渲染器的销毁顺序没有保证所有资源先于设备销毁，所以只记录错误
+        #[cfg(debug_assertions)]
+        for (kind, count) in self.live_resources() {
+            log::error!("{} {}(s) are not destroyed before the device.", count, kind);
+        }
+        log::debug!("Device destroyed.");
+    }
+}
+
+#[cfg(all(test, debug_assertions))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn live_resources_count_created_and_destroyed_objects() {
+        let mut live_resources = LiveResources::default();
+        live_resources.created("image", 2);
+        live_resources.created("buffer", 1);
+        assert!(live_resources.destroyed("image"));
+        assert_eq!(live_resources.live(), [("buffer", 1), ("image", 1)]);
+
+        assert!(live_resources.destroyed("image"));
+        assert!(live_resources.destroyed("buffer"));
+        assert!(live_resources.live().is_empty());
+    }
+
+    #[test]
+    fn destroying_an_untracked_object_is_reported() {
+        let mut live_resources = LiveResources::default();
+        assert!(!live_resources.destroyed("sampler"));
+        live_resources.created("sampler", 1);
+        assert!(live_resources.destroyed("sampler"));
+        // 计数不会变成负数
+        assert!(!live_resources.destroyed("sampler"));
+        assert!(live_resources.live().is_empty());
+    }
+}
@@ -0,0 +1,189 @@
+use alloc::rc::Rc;
+use std::marker::PhantomData;
+use std::mem::size_of;
+
+use ash::vk;
+use gpu_allocator::vulkan::Allocator;
+use gpu_allocator::MemoryLocation;
+use parking_lot::Mutex;
+use typed_builder::TypedBuilder;
+
+use crate::vulkan::buffer::{Buffer, BufferDescriptor};
+use crate::vulkan::device::Device;
+use crate::DeviceError;
+
+#[derive(Clone, TypedBuilder)]
+pub struct DynamicMeshDescriptor<'a> {
+    pub device: &'a Rc<Device>,
+    pub allocator: Rc<Mutex<Allocator>>,
+    #[builder(default = 1024)]
+    pub vertex_capacity: u32,
+    #[builder(default = 1024)]
+    pub index_capacity: u32,
+}
+
+/// Host visible vertex and index buffers for geometry rebuilt every frame (debug lines, immediate-mode draws).
+///
+/// 容量不足时重新分配到下一个 2 的幂，并拷贝已写入的数据。
+/// 重新分配会销毁旧 buffer，调用者需要保证它不再被 GPU 使用，比如每个 frame in flight 各持有一个 `DynamicMesh`。
+pub struct DynamicMesh<V> {
+    device: Rc<Device>,
+    allocator: Rc<Mutex<Allocator>>,
+    vertex_buffer: Buffer,
+    index_buffer: Buffer,
+    vertex_capacity: u32,
+    index_capacity: u32,
+    vertex_count: u32,
+    index_count: u32,
+    _marker: PhantomData<V>,
+}
+
+impl<V: Copy> DynamicMesh<V> {
+    pub fn vertex_buffer(&self) -> vk::Buffer {
+        self.vertex_buffer.raw()
+    }
+
+    pub fn index_buffer(&self) -> vk::Buffer {
+        self.index_buffer.raw()
+    }
+
+    pub fn vertex_count(&self) -> u32 {
+        self.vertex_count
+    }
+
+    pub fn index_count(&self) -> u32 {
+        self.index_count
+    }
+
+    pub fn vertex_capacity(&self) -> u32 {
+        self.vertex_capacity
+    }
+
+    pub fn index_capacity(&self) -> u32 {
+        self.index_capacity
+    }
+
+    pub fn new(desc: &DynamicMeshDescriptor) -> Result<Self, DeviceError> {
+        let vertex_capacity = desc.vertex_capacity.max(1).next_power_of_two();
+        let index_capacity = desc.index_capacity.max(1).next_power_of_two();
+        let vertex_buffer = Self::create_buffer(
+            desc.device,
+            &desc.allocator,
+            size_of::<V>(),
+            vertex_capacity,
+            vk::BufferUsageFlags::VERTEX_BUFFER,
+        )?;
+        let index_buffer = Self::create_buffer(
+            desc.device,
+            &desc.allocator,
+            size_of::<u32>(),
+            index_capacity,
+            vk::BufferUsageFlags::INDEX_BUFFER,
+        )?;
+        Ok(Self {
+            device: desc.device.clone(),
+            allocator: desc.allocator.clone(),
+            vertex_buffer,
+            index_buffer,
+            vertex_capacity,
+            index_capacity,
+            vertex_count: 0,
+            index_count: 0,
+            _marker: PhantomData,
+        })
+    }
+
+    /// Appends vertices and returns the index of the first one, to be used as base vertex.
+    pub fn push_vertices(&mut self, vertices: &[V]) -> Result<u32, DeviceError> {
+        let first = self.vertex_count;
+        let required = first + vertices.len() as u32;
+        if required > self.vertex_capacity {
+            let capacity = Self::grow_capacity(required);
+            self.vertex_buffer = Self::reallocate(
+                &self.device,
+                &self.allocator,
+                &self.vertex_buffer,
+                size_of::<V>(),
+                first,
+                capacity,
+                vk::BufferUsageFlags::VERTEX_BUFFER,
+            )?;
+            self.vertex_capacity = capacity;
+        }
+        self.vertex_buffer
+            .copy_memory_with_offset((first as usize * size_of::<V>()) as u64, vertices);
+        self.vertex_count = required;
+        Ok(first)
+    }
+
+    /// Appends indices and returns the index of the first one, to be used as first index.
+    pub fn push_indices(&mut self, indices: &[u32]) -> Result<u32, DeviceError> {
+        let first = self.index_count;
+        let required = first + indices.len() as u32;
+        if required > self.index_capacity {
+            let capacity = Self::grow_capacity(required);
+            self.index_buffer = Self::reallocate(
+                &self.device,
+                &self.allocator,
+                &self.index_buffer,
+                size_of::<u32>(),
+                first,
+                capacity,
+                vk::BufferUsageFlags::INDEX_BUFFER,
+            )?;
+            self.index_capacity = capacity;
+        }
+        self.index_buffer
+            .copy_memory_with_offset((first as usize * size_of::<u32>()) as u64, indices);
+        self.index_count = required;
+        Ok(first)
+    }
+
+    /// Clears the written geometry but keeps the buffers.
+    pub fn reset(&mut self) {
+        self.vertex_count = 0;
+        self.index_count = 0;
+    }
+
+    fn grow_capacity(required: u32) -> u32 {
+        let capacity = required.next_power_of_two();
+        debug_assert!(math::is_power_of_2(capacity as u64));
+        capacity
+    }
+
+    fn reallocate(
+        device: &Rc<Device>,
+        allocator: &Rc<Mutex<Allocator>>,
+        old: &Buffer,
+        element_size: usize,
+        element_count: u32,
+        capacity: u32,
+        usage: vk::BufferUsageFlags,
+    ) -> Result<Buffer, DeviceError> {
+        log::debug!("Dynamic mesh buffer grows to {} elements.", capacity);
+        let mut buffer = Self::create_buffer(device, allocator, element_size, capacity, usage)?;
+        if let Some(data) = old.mapped_slice() {
+            buffer.copy_memory(&data[..element_size * element_count as usize]);
+        }
+        Ok(buffer)
+    }
+
+    fn create_buffer(
+        device: &Rc<Device>,
+        allocator: &Rc<Mutex<Allocator>>,
+        element_size: usize,
+        element_count: u32,
+        buffer_usage: vk::BufferUsageFlags,
+    ) -> Result<Buffer, DeviceError> {
+        let desc = BufferDescriptor {
+            label: Some("Dynamic Mesh Buffer"),
+            device,
+            allocator: allocator.clone(),
+            element_size,
+            element_count,
+            buffer_usage,
+            memory_location: MemoryLocation::CpuToGpu,
+        };
+        Buffer::new(desc)
+    }
+}
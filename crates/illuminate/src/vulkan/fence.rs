@@ -0,0 +1,52 @@
+use alloc::rc::Rc;
+
+use ash::vk;
+
+use crate::vulkan::device::Device;
+use crate::DeviceError;
+
+/// A fence owned by the caller, e.g. to track upload or compute work outside the per-frame fences.
+pub struct Fence {
+    raw: vk::Fence,
+    device: Rc<Device>,
+}
+
+impl Fence {
+    pub fn raw(&self) -> vk::Fence {
+        self.raw
+    }
+
+    pub fn new(device: &Rc<Device>, signaled: bool) -> Result<Self, DeviceError> {
+        let flags = if signaled {
+            vk::FenceCreateFlags::SIGNALED
+        } else {
+            vk::FenceCreateFlags::empty()
+        };
+        let create_info = vk::FenceCreateInfo::builder().flags(flags).build();
+        let raw = device.create_fence(&create_info)?;
+        Ok(Self {
+            raw,
+            device: device.clone(),
+        })
+    }
+
+    /// Returns `false` if `timeout` nanoseconds passed before the fence was signaled.
+    pub fn wait(&self, timeout: u64) -> Result<bool, DeviceError> {
+        self.device.wait_for_fence_with_timeout(self.raw, timeout)
+    }
+
+    pub fn is_signaled(&self) -> Result<bool, DeviceError> {
+        self.device.get_fence_status(self.raw)
+    }
+
+    pub fn reset(&self) -> Result<(), DeviceError> {
+        self.device.reset_fence(&[self.raw])
+    }
+}
+
+impl Drop for Fence {
+    fn drop(&mut self) {
+        self.device.destroy_fence(self.raw);
+        log::debug!("Fence destroyed.");
+    }
+}
@@ -0,0 +1,479 @@
+use ash::vk;
+
+/// (UNORM, SRGB) 格式对
+const SRGB_PAIRS: &[(vk::Format, vk::Format)] = &[
+    (vk::Format::R8_UNORM, vk::Format::R8_SRGB),
+    (vk::Format::R8G8_UNORM, vk::Format::R8G8_SRGB),
+    (vk::Format::R8G8B8_UNORM, vk::Format::R8G8B8_SRGB),
+    (vk::Format::B8G8R8_UNORM, vk::Format::B8G8R8_SRGB),
+    (vk::Format::R8G8B8A8_UNORM, vk::Format::R8G8B8A8_SRGB),
+    (vk::Format::B8G8R8A8_UNORM, vk::Format::B8G8R8A8_SRGB),
+    (
+        vk::Format::A8B8G8R8_UNORM_PACK32,
+        vk::Format::A8B8G8R8_SRGB_PACK32,
+    ),
+    (
+        vk::Format::BC1_RGB_UNORM_BLOCK,
+        vk::Format::BC1_RGB_SRGB_BLOCK,
+    ),
+    (
+        vk::Format::BC1_RGBA_UNORM_BLOCK,
+        vk::Format::BC1_RGBA_SRGB_BLOCK,
+    ),
+    (vk::Format::BC2_UNORM_BLOCK, vk::Format::BC2_SRGB_BLOCK),
+    (vk::Format::BC3_UNORM_BLOCK, vk::Format::BC3_SRGB_BLOCK),
+    (vk::Format::BC7_UNORM_BLOCK, vk::Format::BC7_SRGB_BLOCK),
+    (
+        vk::Format::ASTC_4X4_UNORM_BLOCK,
+        vk::Format::ASTC_4X4_SRGB_BLOCK,
+    ),
+    (
+        vk::Format::ASTC_5X4_UNORM_BLOCK,
+        vk::Format::ASTC_5X4_SRGB_BLOCK,
+    ),
+    (
+        vk::Format::ASTC_5X5_UNORM_BLOCK,
+        vk::Format::ASTC_5X5_SRGB_BLOCK,
+    ),
+    (
+        vk::Format::ASTC_6X5_UNORM_BLOCK,
+        vk::Format::ASTC_6X5_SRGB_BLOCK,
+    ),
+    (
+        vk::Format::ASTC_6X6_UNORM_BLOCK,
+        vk::Format::ASTC_6X6_SRGB_BLOCK,
+    ),
+    (
+        vk::Format::ASTC_8X5_UNORM_BLOCK,
+        vk::Format::ASTC_8X5_SRGB_BLOCK,
+    ),
+    (
+        vk::Format::ASTC_8X6_UNORM_BLOCK,
+        vk::Format::ASTC_8X6_SRGB_BLOCK,
+    ),
+    (
+        vk::Format::ASTC_8X8_UNORM_BLOCK,
+        vk::Format::ASTC_8X8_SRGB_BLOCK,
+    ),
+    (
+        vk::Format::ASTC_10X5_UNORM_BLOCK,
+        vk::Format::ASTC_10X5_SRGB_BLOCK,
+    ),
+    (
+        vk::Format::ASTC_10X6_UNORM_BLOCK,
+        vk::Format::ASTC_10X6_SRGB_BLOCK,
+    ),
+    (
+        vk::Format::ASTC_10X8_UNORM_BLOCK,
+        vk::Format::ASTC_10X8_SRGB_BLOCK,
+    ),
+    (
+        vk::Format::ASTC_10X10_UNORM_BLOCK,
+        vk::Format::ASTC_10X10_SRGB_BLOCK,
+    ),
+    (
+        vk::Format::ASTC_12X10_UNORM_BLOCK,
+        vk::Format::ASTC_12X10_SRGB_BLOCK,
+    ),
+    (
+        vk::Format::ASTC_12X12_UNORM_BLOCK,
+        vk::Format::ASTC_12X12_SRGB_BLOCK,
+    ),
+];
+
+/// How the components of a format are interpreted in shaders.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NumericType {
+    /// Unsigned normalized to `[0, 1]`.
+    UNorm,
+    /// Signed normalized to `[-1, 1]`.
+    SNorm,
+    /// Unsigned integer converted to float, e.g. `255` reads as `255.0`.
+    UScaled,
+    SScaled,
+    UInt,
+    SInt,
+    UFloat,
+    SFloat,
+    /// Unsigned normalized with the sRGB transfer function applied to the color components.
+    Srgb,
+}
+
+impl NumericType {
+    /// Read as floats in shaders, the rest need `uint` / `int` types.
+    pub fn is_float(self) -> bool {
+        !matches!(self, Self::UInt | Self::SInt)
+    }
+}
+
+/// Component count, texel size in bytes and numeric type of the uncompressed color formats of
+/// core Vulkan 1.0.
+const FORMAT_COMPONENTS: &[(vk::Format, u8, u8, NumericType)] = &[
+    (vk::Format::R4G4_UNORM_PACK8, 2, 1, NumericType::UNorm),
+    (vk::Format::R4G4B4A4_UNORM_PACK16, 4, 2, NumericType::UNorm),
+    (vk::Format::B4G4R4A4_UNORM_PACK16, 4, 2, NumericType::UNorm),
+    (vk::Format::R5G6B5_UNORM_PACK16, 3, 2, NumericType::UNorm),
+    (vk::Format::B5G6R5_UNORM_PACK16, 3, 2, NumericType::UNorm),
+    (vk::Format::R5G5B5A1_UNORM_PACK16, 4, 2, NumericType::UNorm),
+    (vk::Format::B5G5R5A1_UNORM_PACK16, 4, 2, NumericType::UNorm),
+    (vk::Format::A1R5G5B5_UNORM_PACK16, 4, 2, NumericType::UNorm),
+    (vk::Format::R8_UNORM, 1, 1, NumericType::UNorm),
+    (vk::Format::R8_SNORM, 1, 1, NumericType::SNorm),
+    (vk::Format::R8_USCALED, 1, 1, NumericType::UScaled),
+    (vk::Format::R8_SSCALED, 1, 1, NumericType::SScaled),
+    (vk::Format::R8_UINT, 1, 1, NumericType::UInt),
+    (vk::Format::R8_SINT, 1, 1, NumericType::SInt),
+    (vk::Format::R8_SRGB, 1, 1, NumericType::Srgb),
+    (vk::Format::R8G8_UNORM, 2, 2, NumericType::UNorm),
+    (vk::Format::R8G8_SNORM, 2, 2, NumericType::SNorm),
+    (vk::Format::R8G8_USCALED, 2, 2, NumericType::UScaled),
+    (vk::Format::R8G8_SSCALED, 2, 2, NumericType::SScaled),
+    (vk::Format::R8G8_UINT, 2, 2, NumericType::UInt),
+    (vk::Format::R8G8_SINT, 2, 2, NumericType::SInt),
+    (vk::Format::R8G8_SRGB, 2, 2, NumericType::Srgb),
+    (vk::Format::R8G8B8_UNORM, 3, 3, NumericType::UNorm),
+    (vk::Format::R8G8B8_SNORM, 3, 3, NumericType::SNorm),
+    (vk::Format::R8G8B8_USCALED, 3, 3, NumericType::UScaled),
+    (vk::Format::R8G8B8_SSCALED, 3, 3, NumericType::SScaled),
+    (vk::Format::R8G8B8_UINT, 3, 3, NumericType::UInt),
+    (vk::Format::R8G8B8_SINT, 3, 3, NumericType::SInt),
+    (vk::Format::R8G8B8_SRGB, 3, 3, NumericType::Srgb),
+    (vk::Format::B8G8R8_UNORM, 3, 3, NumericType::UNorm),
+    (vk::Format::B8G8R8_SNORM, 3, 3, NumericType::SNorm),
+    (vk::Format::B8G8R8_USCALED, 3, 3, NumericType::UScaled),
+    (vk::Format::B8G8R8_SSCALED, 3, 3, NumericType::SScaled),
+    (vk::Format::B8G8R8_UINT, 3, 3, NumericType::UInt),
+    (vk::Format::B8G8R8_SINT, 3, 3, NumericType::SInt),
+    (vk::Format::B8G8R8_SRGB, 3, 3, NumericType::Srgb),
+    (vk::Format::R8G8B8A8_UNORM, 4, 4, NumericType::UNorm),
+    (vk::Format::R8G8B8A8_SNORM, 4, 4, NumericType::SNorm),
+    (vk::Format::R8G8B8A8_USCALED, 4, 4, NumericType::UScaled),
+    (vk::Format::R8G8B8A8_SSCALED, 4, 4, NumericType::SScaled),
+    (vk::Format::R8G8B8A8_UINT, 4, 4, NumericType::UInt),
+    (vk::Format::R8G8B8A8_SINT, 4, 4, NumericType::SInt),
+    (vk::Format::R8G8B8A8_SRGB, 4, 4, NumericType::Srgb),
+    (vk::Format::B8G8R8A8_UNORM, 4, 4, NumericType::UNorm),
+    (vk::Format::B8G8R8A8_SNORM, 4, 4, NumericType::SNorm),
+    (vk::Format::B8G8R8A8_USCALED, 4, 4, NumericType::UScaled),
+    (vk::Format::B8G8R8A8_SSCALED, 4, 4, NumericType::SScaled),
+    (vk::Format::B8G8R8A8_UINT, 4, 4, NumericType::UInt),
+    (vk::Format::B8G8R8A8_SINT, 4, 4, NumericType::SInt),
+    (vk::Format::B8G8R8A8_SRGB, 4, 4, NumericType::Srgb),
+    (vk::Format::A8B8G8R8_UNORM_PACK32, 4, 4, NumericType::UNorm),
+    (vk::Format::A8B8G8R8_SNORM_PACK32, 4, 4, NumericType::SNorm),
+    (
+        vk::Format::A8B8G8R8_USCALED_PACK32,
+        4,
+        4,
+        NumericType::UScaled,
+    ),
+    (
+        vk::Format::A8B8G8R8_SSCALED_PACK32,
+        4,
+        4,
+        NumericType::SScaled,
+    ),
+    (vk::Format::A8B8G8R8_UINT_PACK32, 4, 4, NumericType::UInt),
+    (vk::Format::A8B8G8R8_SINT_PACK32, 4, 4, NumericType::SInt),
+    (vk::Format::A8B8G8R8_SRGB_PACK32, 4, 4, NumericType::Srgb),
+    (
+        vk::Format::A2R10G10B10_UNORM_PACK32,
+        4,
+        4,
+        NumericType::UNorm,
+    ),
+    (
+        vk::Format::A2R10G10B10_SNORM_PACK32,
+        4,
+        4,
+        NumericType::SNorm,
+    ),
+    (
+        vk::Format::A2R10G10B10_USCALED_PACK32,
+        4,
+        4,
+        NumericType::UScaled,
+    ),
+    (
+        vk::Format::A2R10G10B10_SSCALED_PACK32,
+        4,
+        4,
+        NumericType::SScaled,
+    ),
+    (vk::Format::A2R10G10B10_UINT_PACK32, 4, 4, NumericType::UInt),
+    (vk::Format::A2R10G10B10_SINT_PACK32, 4, 4, NumericType::SInt),
+    (
+        vk::Format::A2B10G10R10_UNORM_PACK32,
+        4,
+        4,
+        NumericType::UNorm,
+    ),
+    (
+        vk::Format::A2B10G10R10_SNORM_PACK32,
+        4,
+        4,
+        NumericType::SNorm,
+    ),
+    (
+        vk::Format::A2B10G10R10_USCALED_PACK32,
+        4,
+        4,
+        NumericType::UScaled,
+    ),
+    (
+        vk::Format::A2B10G10R10_SSCALED_PACK32,
+        4,
+        4,
+        NumericType::SScaled,
+    ),
+    (vk::Format::A2B10G10R10_UINT_PACK32, 4, 4, NumericType::UInt),
+    (vk::Format::A2B10G10R10_SINT_PACK32, 4, 4, NumericType::SInt),
+    (vk::Format::R16_UNORM, 1, 2, NumericType::UNorm),
+    (vk::Format::R16_SNORM, 1, 2, NumericType::SNorm),
+    (vk::Format::R16_USCALED, 1, 2, NumericType::UScaled),
+    (vk::Format::R16_SSCALED, 1, 2, NumericType::SScaled),
+    (vk::Format::R16_UINT, 1, 2, NumericType::UInt),
+    (vk::Format::R16_SINT, 1, 2, NumericType::SInt),
+    (vk::Format::R16_SFLOAT, 1, 2, NumericType::SFloat),
+    (vk::Format::R16G16_UNORM, 2, 4, NumericType::UNorm),
+    (vk::Format::R16G16_SNORM, 2, 4, NumericType::SNorm),
+    (vk::Format::R16G16_USCALED, 2, 4, NumericType::UScaled),
+    (vk::Format::R16G16_SSCALED, 2, 4, NumericType::SScaled),
+    (vk::Format::R16G16_UINT, 2, 4, NumericType::UInt),
+    (vk::Format::R16G16_SINT, 2, 4, NumericType::SInt),
+    (vk::Format::R16G16_SFLOAT, 2, 4, NumericType::SFloat),
+    (vk::Format::R16G16B16_UNORM, 3, 6, NumericType::UNorm),
+    (vk::Format::R16G16B16_SNORM, 3, 6, NumericType::SNorm),
+    (vk::Format::R16G16B16_USCALED, 3, 6, NumericType::UScaled),
+    (vk::Format::R16G16B16_SSCALED, 3, 6, NumericType::SScaled),
+    (vk::Format::R16G16B16_UINT, 3, 6, NumericType::UInt),
+    (vk::Format::R16G16B16_SINT, 3, 6, NumericType::SInt),
+    (vk::Format::R16G16B16_SFLOAT, 3, 6, NumericType::SFloat),
+    (vk::Format::R16G16B16A16_UNORM, 4, 8, NumericType::UNorm),
+    (vk::Format::R16G16B16A16_SNORM, 4, 8, NumericType::SNorm),
+    (vk::Format::R16G16B16A16_USCALED, 4, 8, NumericType::UScaled),
+    (vk::Format::R16G16B16A16_SSCALED, 4, 8, NumericType::SScaled),
+    (vk::Format::R16G16B16A16_UINT, 4, 8, NumericType::UInt),
+    (vk::Format::R16G16B16A16_SINT, 4, 8, NumericType::SInt),
+    (vk::Format::R16G16B16A16_SFLOAT, 4, 8, NumericType::SFloat),
+    (vk::Format::R32_UINT, 1, 4, NumericType::UInt),
+    (vk::Format::R32_SINT, 1, 4, NumericType::SInt),
+    (vk::Format::R32_SFLOAT, 1, 4, NumericType::SFloat),
+    (vk::Format::R32G32_UINT, 2, 8, NumericType::UInt),
+    (vk::Format::R32G32_SINT, 2, 8, NumericType::SInt),
+    (vk::Format::R32G32_SFLOAT, 2, 8, NumericType::SFloat),
+    (vk::Format::R32G32B32_UINT, 3, 12, NumericType::UInt),
+    (vk::Format::R32G32B32_SINT, 3, 12, NumericType::SInt),
+    (vk::Format::R32G32B32_SFLOAT, 3, 12, NumericType::SFloat),
+    (vk::Format::R32G32B32A32_UINT, 4, 16, NumericType::UInt),
+    (vk::Format::R32G32B32A32_SINT, 4, 16, NumericType::SInt),
+    (vk::Format::R32G32B32A32_SFLOAT, 4, 16, NumericType::SFloat),
+    (vk::Format::R64_UINT, 1, 8, NumericType::UInt),
+    (vk::Format::R64_SINT, 1, 8, NumericType::SInt),
+    (vk::Format::R64_SFLOAT, 1, 8, NumericType::SFloat),
+    (vk::Format::R64G64_UINT, 2, 16, NumericType::UInt),
+    (vk::Format::R64G64_SINT, 2, 16, NumericType::SInt),
+    (vk::Format::R64G64_SFLOAT, 2, 16, NumericType::SFloat),
+    (vk::Format::R64G64B64_UINT, 3, 24, NumericType::UInt),
+    (vk::Format::R64G64B64_SINT, 3, 24, NumericType::SInt),
+    (vk::Format::R64G64B64_SFLOAT, 3, 24, NumericType::SFloat),
+    (vk::Format::R64G64B64A64_UINT, 4, 32, NumericType::UInt),
+    (vk::Format::R64G64B64A64_SINT, 4, 32, NumericType::SInt),
+    (vk::Format::R64G64B64A64_SFLOAT, 4, 32, NumericType::SFloat),
+    (
+        vk::Format::B10G11R11_UFLOAT_PACK32,
+        3,
+        4,
+        NumericType::UFloat,
+    ),
+    (
+        vk::Format::E5B9G9R9_UFLOAT_PACK32,
+        3,
+        4,
+        NumericType::UFloat,
+    ),
+];
+
+/// Formats `FormatExt::component_count` knows about.
+pub(crate) fn uncompressed_color_formats() -> impl Iterator<Item = vk::Format> {
+    FORMAT_COMPONENTS.iter().map(|&(format, ..)| format)
+}
+
+/// Color space and component helpers for `vk::Format`.
+pub trait FormatExt: Copy {
+    fn is_srgb(self) -> bool;
+
+    /// The sRGB sibling of a UNORM format, or itself if it is already sRGB.
+    fn to_srgb(self) -> Option<Self>;
+
+    /// The UNORM sibling of a sRGB format, or itself if it is already UNORM.
+    fn to_unorm(self) -> Option<Self>;
+
+    /// Has a depth and/or stencil component.
+    fn is_depth_stencil(self) -> bool;
+
+    /// `DEPTH` and/or `STENCIL` for depth/stencil formats, `COLOR` otherwise.
+    fn aspect_mask(self) -> vk::ImageAspectFlags;
+
+    /// Number of color components, `None` for depth/stencil, compressed and extension formats.
+    fn component_count(self) -> Option<u8>;
+
+    /// `None` for depth/stencil, compressed and extension formats.
+    fn numeric_type(self) -> Option<NumericType>;
+
+    /// Bytes per texel, `None` for depth/stencil, compressed and extension formats.
+    fn texel_size(self) -> Option<u32>;
+}
+
+impl FormatExt for vk::Format {
+    fn is_srgb(self) -> bool {
+        SRGB_PAIRS.iter().any(|&(_, srgb)| srgb == self)
+    }
+
+    fn to_srgb(self) -> Option<Self> {
+        SRGB_PAIRS
+            .iter()
+            .find(|&&(unorm, srgb)| unorm == self || srgb == self)
+            .map(|&(_, srgb)| srgb)
+    }
+
+    fn to_unorm(self) -> Option<Self> {
+        SRGB_PAIRS
+            .iter()
+            .find(|&&(unorm, srgb)| unorm == self || srgb == self)
+            .map(|&(unorm, _)| unorm)
+    }
+
+    fn is_depth_stencil(self) -> bool {
+        matches!(
+            self,
+            vk::Format::D16_UNORM
+                | vk::Format::X8_D24_UNORM_PACK32
+                | vk::Format::D32_SFLOAT
+                | vk::Format::S8_UINT
+                | vk::Format::D16_UNORM_S8_UINT
+                | vk::Format::D24_UNORM_S8_UINT
+                | vk::Format::D32_SFLOAT_S8_UINT
+        )
+    }
+
+    fn aspect_mask(self) -> vk::ImageAspectFlags {
+        match self {
+            vk::Format::D16_UNORM | vk::Format::X8_D24_UNORM_PACK32 | vk::Format::D32_SFLOAT => {
+                vk::ImageAspectFlags::DEPTH
+            }
+            vk::Format::S8_UINT => vk::ImageAspectFlags::STENCIL,
+            vk::Format::D16_UNORM_S8_UINT
+            | vk::Format::D24_UNORM_S8_UINT
+            | vk::Format::D32_SFLOAT_S8_UINT => {
+                vk::ImageAspectFlags::DEPTH | vk::ImageAspectFlags::STENCIL
+            }
+            _ => vk::ImageAspectFlags::COLOR,
+        }
+    }
+
+    fn component_count(self) -> Option<u8> {
+        FORMAT_COMPONENTS
+            .iter()
+            .find(|&&(format, ..)| format == self)
+            .map(|&(_, count, ..)| count)
+    }
+
+    fn numeric_type(self) -> Option<NumericType> {
+        FORMAT_COMPONENTS
+            .iter()
+            .find(|&&(format, ..)| format == self)
+            .map(|&(.., numeric_type)| numeric_type)
+    }
+
+    fn texel_size(self) -> Option<u32> {
+        FORMAT_COMPONENTS
+            .iter()
+            .find(|&&(format, ..)| format == self)
+            .map(|&(_, _, size, _)| size as u32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn component_count_of_color_formats() {
+        assert_eq!(vk::Format::R8_UNORM.component_count(), Some(1));
+        assert_eq!(vk::Format::R16G16_SFLOAT.component_count(), Some(2));
+        assert_eq!(
+            vk::Format::B10G11R11_UFLOAT_PACK32.component_count(),
+            Some(3)
+        );
+        assert_eq!(vk::Format::B8G8R8A8_SRGB.component_count(), Some(4));
+        assert_eq!(vk::Format::D24_UNORM_S8_UINT.component_count(), None);
+        assert_eq!(vk::Format::BC7_UNORM_BLOCK.component_count(), None);
+    }
+
+    #[test]
+    fn numeric_type_of_color_formats() {
+        assert_eq!(
+            vk::Format::R8G8B8A8_UNORM.numeric_type(),
+            Some(NumericType::UNorm)
+        );
+        assert_eq!(
+            vk::Format::R8G8B8A8_SRGB.numeric_type(),
+            Some(NumericType::Srgb)
+        );
+        assert_eq!(
+            vk::Format::R16_SSCALED.numeric_type(),
+            Some(NumericType::SScaled)
+        );
+        assert_eq!(
+            vk::Format::R32G32_UINT.numeric_type(),
+            Some(NumericType::UInt)
+        );
+        assert_eq!(
+            vk::Format::E5B9G9R9_UFLOAT_PACK32.numeric_type(),
+            Some(NumericType::UFloat)
+        );
+        assert_eq!(vk::Format::D32_SFLOAT.numeric_type(), None);
+    }
+
+    #[test]
+    fn texel_size_of_color_formats() {
+        assert_eq!(vk::Format::R4G4_UNORM_PACK8.texel_size(), Some(1));
+        assert_eq!(vk::Format::R5G6B5_UNORM_PACK16.texel_size(), Some(2));
+        assert_eq!(vk::Format::R8G8B8_UNORM.texel_size(), Some(3));
+        assert_eq!(vk::Format::R8G8B8A8_SRGB.texel_size(), Some(4));
+        assert_eq!(vk::Format::A2B10G10R10_UNORM_PACK32.texel_size(), Some(4));
+        assert_eq!(vk::Format::R16G16B16A16_SFLOAT.texel_size(), Some(8));
+        assert_eq!(vk::Format::R64G64B64A64_SFLOAT.texel_size(), Some(32));
+    }
+
+    #[test]
+    fn texel_size_is_unknown_for_depth_and_compressed_formats() {
+        assert_eq!(vk::Format::D32_SFLOAT.texel_size(), None);
+        assert_eq!(vk::Format::BC7_UNORM_BLOCK.texel_size(), None);
+    }
+
+    #[test]
+    fn srgb_and_unorm_round_trip() {
+        let unorm = vk::Format::R8G8B8A8_UNORM;
+        let srgb = vk::Format::R8G8B8A8_SRGB;
+        assert_eq!(unorm.to_srgb(), Some(srgb));
+        assert_eq!(srgb.to_unorm(), Some(unorm));
+        assert_eq!(unorm.to_srgb().and_then(FormatExt::to_unorm), Some(unorm));
+        assert_eq!(srgb.to_srgb(), Some(srgb));
+        assert_eq!(vk::Format::R32_SFLOAT.to_srgb(), None);
+    }
+
+    #[test]
+    fn component_count_and_numeric_type_of_listed_formats() {
+        let cases = [
+            (vk::Format::R32G32B32_SFLOAT, 3, NumericType::SFloat),
+            (vk::Format::R8G8B8A8_UINT, 4, NumericType::UInt),
+            (vk::Format::R16_SNORM, 1, NumericType::SNorm),
+        ];
+        for (format, component_count, numeric_type) in cases {
+            assert_eq!(format.component_count(), Some(component_count));
+            assert_eq!(format.numeric_type(), Some(numeric_type));
+        }
+    }
+}
@@ -0,0 +1,88 @@
+use std::collections::hash_map::Entry;
+use std::rc::Rc;
+
+use ash::vk;
+use parking_lot::Mutex;
+use typed_builder::TypedBuilder;
+
+use crate::vulkan::device::Device;
+use crate::DeviceError;
+
+#[derive(Clone, TypedBuilder, Hash, PartialEq, Eq)]
+pub struct FramebufferDescriptor {
+    pub render_pass: vk::RenderPass,
+    pub texture_views: Vec<vk::ImageView>,
+    pub swapchain_extent: vk::Extent2D,
+}
+
+/// Caches framebuffers by (render pass, attachment image views, extent).
+pub struct FramebufferCache {
+    device: Rc<Device>,
+    map: Mutex<fxhash::FxHashMap<FramebufferDescriptor, vk::Framebuffer>>,
+}
+
+impl FramebufferCache {
+    pub fn new(device: &Rc<Device>) -> Self {
+        Self {
+            device: device.clone(),
+            map: Default::default(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.lock().is_empty()
+    }
+
+    /// Returns the cached framebuffer for `desc`, creating it on first use.
+    pub fn get_or_create(
+        &self,
+        desc: FramebufferDescriptor,
+    ) -> Result<vk::Framebuffer, DeviceError> {
+        Ok(match self.map.lock().entry(desc) {
+            Entry::Occupied(e) => *e.get(),
+            Entry::Vacant(e) => {
+                let desc = e.key();
+                let create_info = vk::FramebufferCreateInfo::builder()
+                    .render_pass(desc.render_pass)
+                    .attachments(&desc.texture_views)
+                    .width(desc.swapchain_extent.width)
+                    .height(desc.swapchain_extent.height)
+                    .layers(1)
+                    .build();
+                *e.insert(self.device.create_framebuffer(&create_info)?)
+            }
+        })
+    }
+
+    /// Destroys the framebuffers that reference any of `image_views`, call it before the views are destroyed.
+    pub fn invalidate_image_views(&self, image_views: &[vk::ImageView]) {
+        self.map.lock().retain(|desc, framebuffer| {
+            let stale = desc
+                .texture_views
+                .iter()
+                .any(|view| image_views.contains(view));
+            if stale {
+                self.device.destroy_framebuffer(*framebuffer);
+            }
+            !stale
+        });
+    }
+
+    pub fn clear(&self) {
+        self.map
+            .lock()
+            .drain()
+            .for_each(|(_, framebuffer)| self.device.destroy_framebuffer(framebuffer));
+    }
+}
+
+impl Drop for FramebufferCache {
+    fn drop(&mut self) {
+        self.clear();
+        log::debug!("Framebuffer Cache destroyed.");
+    }
+}
@@ -2,6 +2,7 @@ use crate::vulkan::adapter::Adapter;
 use crate::vulkan::command_buffer_allocator::CommandBufferAllocator;
 use crate::vulkan::device::Device;
 use crate::vulkan::instance::Instance;
+use crate::vulkan::render_pass::DepthAttachmentType;
 use crate::DeviceError;
 use ash::vk;
 use gpu_allocator::vulkan::{Allocation, AllocationCreateDesc, Allocator};
@@ -19,6 +20,7 @@ pub struct Image {
     width: u32,
     height: u32,
     mip_levels: u32,
+    array_layers: u32,
 }
 
 #[derive(TypedBuilder)]
@@ -29,6 +31,9 @@ pub struct ImageDescriptor<'a> {
     pub dimension: [u32; 2],
     pub mip_levels: u32,
     pub array_layers: u32,
+    /// `CUBE_COMPATIBLE` is required for cube views, which also need 6 array layers.
+    #[builder(default)]
+    pub flags: vk::ImageCreateFlags,
     pub samples: vk::SampleCountFlags,
     pub tiling: vk::ImageTiling,
     pub usage: vk::ImageUsageFlags,
@@ -56,9 +61,49 @@ pub struct DepthImageDescriptor<'a> {
     pub allocator: Rc<Mutex<Allocator>>,
     pub width: u32,
     pub height: u32,
+    pub attachment_type: DepthAttachmentType,
+    /// Tried before the candidate formats of `attachment_type`.
+    pub preferred_format: Option<vk::Format>,
     pub command_buffer_allocator: &'a CommandBufferAllocator,
 }
 
+/// A copy between a buffer and one mip level of an image, the buffer data is tightly packed
+/// and array layers follow each other.
+#[derive(Clone, Copy, Debug, TypedBuilder)]
+pub struct BufferImageCopy {
+    #[builder(default = 0)]
+    pub buffer_offset: vk::DeviceSize,
+    #[builder(default = vk::ImageAspectFlags::COLOR)]
+    pub aspect_mask: vk::ImageAspectFlags,
+    #[builder(default = 0)]
+    pub mip_level: u32,
+    #[builder(default = 0)]
+    pub base_array_layer: u32,
+    #[builder(default = 1)]
+    pub layer_count: u32,
+    pub extent: vk::Extent3D,
+}
+
+impl BufferImageCopy {
+    pub fn raw(&self) -> vk::BufferImageCopy {
+        let subresource = vk::ImageSubresourceLayers::builder()
+            .aspect_mask(self.aspect_mask)
+            .mip_level(self.mip_level)
+            .base_array_layer(self.base_array_layer)
+            .layer_count(self.layer_count)
+            .build();
+        // row length 和 image height 为 0 表示紧密排列
+        vk::BufferImageCopy::builder()
+            .buffer_offset(self.buffer_offset)
+            .buffer_row_length(0)
+            .buffer_image_height(0)
+            .image_subresource(subresource)
+            .image_offset(vk::Offset3D { x: 0, y: 0, z: 0 })
+            .image_extent(self.extent)
+            .build()
+    }
+}
+
 impl Image {
     pub fn raw(&self) -> vk::Image {
         self.raw
@@ -80,6 +125,10 @@ impl Image {
         self.format
     }
 
+    pub fn array_layers(&self) -> u32 {
+        self.array_layers
+    }
+
     pub fn get_max_mip_levels(&self) -> u32 {
         Self::max_mip_levels(self.width, self.height)
     }
@@ -89,7 +138,14 @@ impl Image {
     }
 
     pub fn new(desc: &ImageDescriptor) -> Result<Self, DeviceError> {
+        if desc.flags.contains(vk::ImageCreateFlags::CUBE_COMPATIBLE)
+            && (desc.array_layers < 6 || desc.dimension[0] != desc.dimension[1])
+        {
+            log::error!("cube compatible image must be square with at least 6 array layers.");
+            return Err(DeviceError::Other("invalid cube compatible image"));
+        }
         let create_info = vk::ImageCreateInfo::builder()
+            .flags(desc.flags)
             .image_type(desc.image_type)
             .extent(vk::Extent3D {
                 width: desc.dimension[0],
@@ -146,7 +202,8 @@ impl Image {
             format: desc.format,
             width: desc.dimension[0],
             height: desc.dimension[1],
-            mip_levels: desc.mip_levels
+            mip_levels: desc.mip_levels,
+            array_layers: desc.array_layers,
         })
     }
 
@@ -162,6 +219,7 @@ impl Image {
             dimension: [desc.width, desc.height],
             mip_levels: desc.mip_levels,
             array_layers: 1,
+            flags: vk::ImageCreateFlags::empty(),
             samples: desc.samples,
             tiling: vk::ImageTiling::OPTIMAL,
             usage,
@@ -172,7 +230,19 @@ impl Image {
     }
 
     pub fn new_depth_image(desc: &DepthImageDescriptor) -> Result<Self, DeviceError> {
-        let depth_format = Image::get_depth_format(desc.instance.raw(), desc.adapter.raw())?;
+        let samples = desc.adapter.max_msaa_samples();
+        let candidates = desc
+            .preferred_format
+            .iter()
+            .chain(desc.attachment_type.candidate_formats())
+            .cloned()
+            .collect::<Vec<_>>();
+        let depth_format = Image::get_depth_format_for_samples(
+            desc.instance.raw(),
+            desc.adapter.raw(),
+            &candidates,
+            samples,
+        )?;
 
         let depth_image_desc = ImageDescriptor {
             device: desc.device,
@@ -181,7 +251,8 @@ impl Image {
             dimension: [desc.width, desc.height],
             mip_levels: 1,
             array_layers: 1,
-            samples: desc.adapter.max_msaa_samples(),
+            flags: vk::ImageCreateFlags::empty(),
+            samples,
             tiling: vk::ImageTiling::OPTIMAL,
             usage: vk::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT,
             sharing_mode: vk::SharingMode::EXCLUSIVE,
@@ -227,21 +298,87 @@ impl Image {
         instance: &ash::Instance,
         adapter: vk::PhysicalDevice,
     ) -> Result<vk::Format, DeviceError> {
-        let formats = &[
-            vk::Format::D32_SFLOAT,
-            vk::Format::D32_SFLOAT_S8_UINT,
-            vk::Format::D24_UNORM_S8_UINT,
-        ];
-
         Image::get_supported_format(
             instance,
             adapter,
-            formats,
+            DepthAttachmentType::Depth.candidate_formats(),
             vk::ImageTiling::OPTIMAL,
             vk::FormatFeatureFlags::DEPTH_STENCIL_ATTACHMENT,
         )
     }
 
+    /// Picks the first candidate usable as a depth attachment with `samples` samples,
+    /// falls back to the first one usable as a depth attachment at all.
+    pub fn get_depth_format_for_samples(
+        instance: &ash::Instance,
+        adapter: vk::PhysicalDevice,
+        candidates: &[vk::Format],
+        samples: vk::SampleCountFlags,
+    ) -> Result<vk::Format, DeviceError> {
+        let limits = unsafe { instance.get_physical_device_properties(adapter) }.limits;
+        let supports_samples = |format: vk::Format| {
+            if !limits.framebuffer_depth_sample_counts.contains(samples) {
+                return false;
+            }
+            unsafe {
+                instance.get_physical_device_image_format_properties(
+                    adapter,
+                    format,
+                    vk::ImageType::TYPE_2D,
+                    vk::ImageTiling::OPTIMAL,
+                    vk::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT,
+                    vk::ImageCreateFlags::empty(),
+                )
+            }
+            .map_or(false, |properties| {
+                properties.sample_counts.contains(samples)
+            })
+        };
+
+        let supported = candidates
+            .iter()
+            .cloned()
+            .filter(|format| {
+                Image::get_supported_format(
+                    instance,
+                    adapter,
+                    &[*format],
+                    vk::ImageTiling::OPTIMAL,
+                    vk::FormatFeatureFlags::DEPTH_STENCIL_ATTACHMENT,
+                )
+                .is_ok()
+            })
+            .collect::<Vec<_>>();
+        match supported.iter().find(|format| supports_samples(**format)) {
+            Some(format) => Ok(*format),
+            None => {
+                let format = supported
+                    .first()
+                    .cloned()
+                    .ok_or(DeviceError::Other("Failed to find supported format!"))?;
+                log::warn!(
+                    "No depth format supports {:?} samples, fallback to {:?}.",
+                    samples,
+                    format
+                );
+                Ok(format)
+            }
+        }
+    }
+
+    /// Aspects contained in a depth and/or stencil format.
+    pub fn depth_stencil_aspect_mask(format: vk::Format) -> vk::ImageAspectFlags {
+        match format {
+            vk::Format::S8_UINT => vk::ImageAspectFlags::STENCIL,
+            vk::Format::D16_UNORM_S8_UINT
+            | vk::Format::D24_UNORM_S8_UINT
+            | vk::Format::D32_SFLOAT_S8_UINT => {
+                vk::ImageAspectFlags::DEPTH | vk::ImageAspectFlags::STENCIL
+            }
+            _ => vk::ImageAspectFlags::DEPTH,
+        }
+    }
+
     /// 屏障主要用于同步目的，因此必须指定哪些类型的涉及资源的操作必须发生在屏障之前，哪些涉及资源的操作必须等待屏障。
     pub fn transit_layout(
         &mut self,
@@ -251,14 +388,22 @@ impl Image {
         command_buffer_allocator: &CommandBufferAllocator,
         mip_levels: u32,
     ) -> Result<(), DeviceError> {
+        // 记录的布局只在经过 transit_layout 等路径时更新，render pass 的 final_layout 和手写的屏障
+        // 不会更新它，所以以调用方传入的旧布局为准，记录的布局仅用于调试检查
+        #[cfg(debug_assertions)]
+        if let Some(tracked) = self.device.image_layout(self.raw) {
+            if tracked != old_layout {
+                log::warn!(
+                    "Image {:?} old layout mismatch: given {:?}, tracked {:?}.",
+                    self.raw,
+                    old_layout,
+                    tracked
+                );
+            }
+        }
         command_buffer_allocator.create_single_use(|device, command_buffer| {
             let aspect_mask = if new_layout == vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL {
-                match format {
-                    vk::Format::D32_SFLOAT_S8_UINT | vk::Format::D24_UNORM_S8_UINT => {
-                        vk::ImageAspectFlags::DEPTH | vk::ImageAspectFlags::STENCIL
-                    }
-                    _ => vk::ImageAspectFlags::DEPTH,
-                }
+                Self::depth_stencil_aspect_mask(format)
             } else {
                 vk::ImageAspectFlags::COLOR
             };
@@ -300,7 +445,8 @@ impl Image {
                 .base_mip_level(0)
                 .level_count(mip_levels)
                 .base_array_layer(0)
-                .layer_count(1)
+                // 布局按整张图像追踪，所以转换覆盖全部层
+                .layer_count(self.array_layers)
                 .build();
             let barrier = vk::ImageMemoryBarrier::builder()
                 .old_layout(old_layout)
@@ -323,6 +469,7 @@ impl Image {
                 &[barrier],
             );
         })?;
+        self.device.set_image_layout(self.raw, new_layout);
 
         Ok(())
     }
@@ -334,26 +481,18 @@ impl Image {
         height: u32,
         command_buffer_allocator: &CommandBufferAllocator,
     ) -> Result<(), DeviceError> {
+        let array_layers = self.array_layers;
         command_buffer_allocator.create_single_use(|device, command_buffer| {
-            let subresource = vk::ImageSubresourceLayers::builder()
-                .aspect_mask(vk::ImageAspectFlags::COLOR)
-                .mip_level(0)
-                .base_array_layer(0)
-                .layer_count(1)
-                .build();
-
-            let region = vk::BufferImageCopy::builder()
-                .buffer_offset(0)
-                .buffer_row_length(0)
-                .buffer_image_height(0)
-                .image_subresource(subresource)
-                .image_offset(vk::Offset3D { x: 0, y: 0, z: 0 })
-                .image_extent(vk::Extent3D {
+            // 多层图像（例如立方体贴图）要求 buffer 中各层数据依次紧密排列
+            let region = BufferImageCopy::builder()
+                .layer_count(array_layers)
+                .extent(vk::Extent3D {
                     width,
                     height,
                     depth: 1,
                 })
-                .build();
+                .build()
+                .raw();
 
             device.cmd_copy_buffer_to_image(
                 command_buffer.raw(),
@@ -1,4 +1,5 @@
 use crate::vulkan::device::Device;
+use crate::vulkan::image::Image;
 use crate::Label;
 use ash::vk;
 use std::rc::Rc;
@@ -10,9 +11,15 @@ pub struct ImageViewDescriptor<'a> {
     pub format: vk::Format,
     pub dimension: vk::ImageViewType,
     pub aspect_mask: vk::ImageAspectFlags,
+    #[builder(default)]
+    pub base_mip_level: u32,
     pub mip_levels: u32,
+    #[builder(default)]
+    pub base_array_layer: u32,
+    /// CUBE 需要 6 层，CUBE_ARRAY 需要 6 的倍数。
+    #[builder(default = 1)]
+    pub array_layers: u32,
     // pub usage: vk::ImageUsageFlags,
-    // pub range: vk::ImageSubresourceRange,
 }
 
 pub struct ImageView {
@@ -37,7 +44,10 @@ impl ImageView {
             format,
             dimension: vk::ImageViewType::TYPE_2D,
             aspect_mask: vk::ImageAspectFlags::COLOR,
+            base_mip_level: 0,
             mip_levels,
+            base_array_layer: 0,
+            array_layers: 1,
         };
         Self::new(device, image, &desc)
     }
@@ -52,22 +62,47 @@ impl ImageView {
             label,
             format,
             dimension: vk::ImageViewType::TYPE_2D,
-            aspect_mask: vk::ImageAspectFlags::DEPTH,
+            aspect_mask: Image::depth_stencil_aspect_mask(format),
+            base_mip_level: 0,
             mip_levels: 1,
+            base_array_layer: 0,
+            array_layers: 1,
         };
         Self::new(device, image, &desc)
     }
 
-    fn new(
+    /// The image must be created with `CUBE_COMPATIBLE` and 6 array layers.
+    pub fn new_cube_image_view(
+        label: Label,
+        device: &Rc<Device>,
+        image: vk::Image,
+        format: vk::Format,
+        mip_levels: u32,
+    ) -> Result<ImageView, crate::DeviceError> {
+        let desc = ImageViewDescriptor {
+            label,
+            format,
+            dimension: vk::ImageViewType::CUBE,
+            aspect_mask: vk::ImageAspectFlags::COLOR,
+            base_mip_level: 0,
+            mip_levels,
+            base_array_layer: 0,
+            array_layers: 6,
+        };
+        Self::new(device, image, &desc)
+    }
+
+    pub fn new(
         device: &Rc<Device>,
         image: vk::Image,
         desc: &ImageViewDescriptor,
     ) -> Result<ImageView, crate::DeviceError> {
+        Self::validate_layer_count(desc.dimension, desc.array_layers)?;
         let range = vk::ImageSubresourceRange::builder()
             .aspect_mask(desc.aspect_mask)
-            .base_array_layer(0)
-            .layer_count(1)
-            .base_mip_level(0)
+            .base_array_layer(desc.base_array_layer)
+            .layer_count(desc.array_layers)
+            .base_mip_level(desc.base_mip_level)
             .level_count(desc.mip_levels)
             .build();
         let info = vk::ImageViewCreateInfo::builder()
@@ -98,6 +133,29 @@ impl ImageView {
             device: device.clone(),
         })
     }
+
+    fn validate_layer_count(
+        dimension: vk::ImageViewType,
+        array_layers: u32,
+    ) -> Result<(), crate::DeviceError> {
+        let valid = match dimension {
+            vk::ImageViewType::TYPE_1D
+            | vk::ImageViewType::TYPE_2D
+            | vk::ImageViewType::TYPE_3D => array_layers == 1,
+            vk::ImageViewType::CUBE => array_layers == 6,
+            vk::ImageViewType::CUBE_ARRAY => array_layers > 0 && array_layers % 6 == 0,
+            _ => array_layers > 0,
+        };
+        if !valid {
+            log::error!(
+                "{} array layers is invalid for image view type {:?}.",
+                array_layers,
+                dimension
+            );
+            return Err(crate::DeviceError::Other("invalid image view layer count"));
+        }
+        Ok(())
+    }
 }
 
 impl Drop for ImageView {
@@ -17,8 +17,10 @@ use crate::vulkan::instance::Instance;
 use crate::vulkan::texture::VulkanTexture;
 
 pub struct ImguiRenderer {
-    _device: Rc<Device>,
+    device: Rc<Device>,
     renderer: Renderer,
+    /// The renderer only borrows it for uploads, it is destroyed with this renderer.
+    command_pool: vk::CommandPool,
     texture_id_set: HashSet<TextureId>,
     descriptor_set_allocator: Rc<DescriptorSetAllocator>,
 }
@@ -42,14 +44,8 @@ impl ImguiRenderer {
     }
 
     pub fn new(desc: &mut ImguiRendererDescriptor) -> anyhow::Result<Self> {
-        let device_properties = unsafe {
-            desc.instance
-                .raw()
-                .get_physical_device_properties(desc.adapter.raw())
-        };
-
         desc.context.fonts().tex_desired_width =
-            device_properties.limits.max_image_dimension2_d as i32;
+            desc.adapter.limits().max_image_dimension_2d as i32;
 
         let options = Some(Options {
             in_flight_frames: MAX_FRAMES_IN_FLIGHT,
@@ -62,7 +58,7 @@ impl ImguiRenderer {
             device: desc.device.raw().clone(),
             physical_device: desc.adapter.raw(),
             debug_settings: Default::default(),
-            buffer_device_address: false,
+            buffer_device_address: desc.device.is_buffer_device_address_enabled(),
         })?;
 
         let renderer = Renderer::with_gpu_allocator(
@@ -79,7 +75,8 @@ impl ImguiRenderer {
             renderer,
             descriptor_set_allocator: desc.descriptor_set_allocator.clone(),
             texture_id_set: HashSet::new(),
-            _device: desc.device.clone(),
+            device: desc.device.clone(),
+            command_pool: desc.command_pool,
         })
     }
 
@@ -96,3 +93,10 @@ impl ImguiRenderer {
         Ok(texture_id)
     }
 }
+
+impl Drop for ImguiRenderer {
+    fn drop(&mut self) {
+        self.device.destroy_command_pool(self.command_pool);
+        log::debug!("Imgui Renderer destroyed.");
+    }
+}
@@ -1,13 +1,14 @@
 use alloc::ffi::CString;
 use std::ffi::{c_void, CStr};
+use std::sync::Arc;
 
 use ash::{extensions::*, vk};
 use log::LevelFilter;
 
-use crate::vulkan::{debug, platforms};
+use crate::vulkan::{debug, platforms, utils};
 use crate::{InstanceDescriptor, InstanceError};
 
-use super::debug::DebugUtils;
+use super::debug::{DebugMessageFilter, DebugUtils};
 use super::{adapter::Adapter, surface::Surface};
 
 bitflags::bitflags! {
@@ -23,8 +24,13 @@ pub struct Instance {
     /// Loads the Vulkan library. Needs to outlive Instance and Device.
     entry: ash::Entry,
     debug_utils: Option<DebugUtils>,
+    /// Extensions actually enabled, requested ones the driver does not support are dropped.
     extensions: Vec<&'static CStr>,
+    /// Layers actually enabled.
+    layers: Vec<CString>,
     flags: InstanceFlags,
+    /// Version requested in `VkApplicationInfo`, device features newer than it can't be used.
+    api_version: u32,
 }
 
 impl Instance {
@@ -33,14 +39,18 @@ impl Instance {
         entry: ash::Entry,
         debug_utils: Option<DebugUtils>,
         extensions: Vec<&'static CStr>,
+        layers: Vec<CString>,
         flags: InstanceFlags,
+        api_version: u32,
     ) -> Self {
         Self {
             raw,
             entry,
             debug_utils,
             extensions,
+            layers,
             flags,
+            api_version,
         }
     }
 
@@ -48,6 +58,10 @@ impl Instance {
         &self.raw
     }
 
+    pub fn api_version(&self) -> u32 {
+        self.api_version
+    }
+
     pub fn flags(&self) -> InstanceFlags {
         self.flags
     }
@@ -56,6 +70,14 @@ impl Instance {
         &self.debug_utils
     }
 
+    pub fn extensions(&self) -> &[&'static CStr] {
+        &self.extensions
+    }
+
+    pub fn layers(&self) -> &[CString] {
+        &self.layers
+    }
+
     pub unsafe fn init(desc: &InstanceDescriptor) -> Result<Self, InstanceError> {
         #[cfg(not(target_os = "macos"))]
         let vulkan_api_version = vk::API_VERSION_1_3;
@@ -95,20 +117,44 @@ impl Instance {
             .map(|layer_name| CString::new(*layer_name).unwrap())
             .collect();
 
-        let enable_layer_names: Vec<*const i8> = required_layer_raw_names
+        let available_layers = entry
+            .enumerate_instance_layer_properties()
+            .map_err(InstanceError::VulkanError)?;
+        let available_layer_names: Vec<&CStr> = available_layers
+            .iter()
+            .map(|layer| CStr::from_ptr(layer.layer_name.as_ptr()))
+            .collect();
+        let required_layer_names: Vec<&CStr> = required_layer_raw_names
+            .iter()
+            .map(CString::as_c_str)
+            .collect();
+        let enabled_layers: Vec<CString> = utils::select_available_names(
+            "instance layers",
+            &required_layer_names,
+            &available_layer_names,
+        )
+        .into_iter()
+        .map(CStr::to_owned)
+        .collect();
+        let enable_layer_names: Vec<*const i8> = enabled_layers
             .iter()
             .map(|layer_name| layer_name.as_ptr())
             .collect();
 
-        let extension_cstr_names = platforms::required_extension_names(enable_debug);
-        log::debug!("Required extension:");
-        let extension_names: Vec<*const i8> = extension_cstr_names
+        let available_extensions = entry
+            .enumerate_instance_extension_properties(None)
+            .map_err(InstanceError::VulkanError)?;
+        let available_extension_names: Vec<&CStr> = available_extensions
             .iter()
-            .map(|x| {
-                log::debug!("  * {}", x.to_str().unwrap());
-                x.as_ptr()
-            })
+            .map(|extension| CStr::from_ptr(extension.extension_name.as_ptr()))
             .collect();
+        let extension_cstr_names = utils::select_available_names(
+            "instance extensions",
+            &platforms::required_extension_names(enable_debug),
+            &available_extension_names,
+        );
+        let extension_names: Vec<*const i8> =
+            extension_cstr_names.iter().map(|x| x.as_ptr()).collect();
 
         let create_info = vk::InstanceCreateInfo::builder()
             .application_info(&app_info)
@@ -132,11 +178,16 @@ impl Instance {
                     }
                     _ => vk::DebugUtilsMessageSeverityFlagsEXT::ERROR,
                 };
+                let filter = Arc::new(DebugMessageFilter {
+                    message_id_filter: desc.message_id_filter.clone(),
+                    break_on_error: desc.break_on_error,
+                });
                 let (extension, messenger) =
-                    debug::setup_debug_utils(&entry, &instance, vk_msg_max_level)?;
+                    debug::setup_debug_utils(&entry, &instance, vk_msg_max_level, &filter)?;
                 Some(DebugUtils {
                     extension,
                     messenger,
+                    filter,
                 })
             } else {
                 None
@@ -150,7 +201,9 @@ impl Instance {
             entry,
             debug_utils,
             extensions: extension_cstr_names,
+            layers: enabled_layers,
             flags,
+            api_version: vulkan_api_version,
         })
     }
 
@@ -0,0 +1,194 @@
+use alloc::rc::Rc;
+
+use ash::vk;
+use typed_builder::TypedBuilder;
+
+use crate::vulkan::buffer::Buffer;
+use crate::vulkan::descriptor_pool::DescriptorPool;
+use crate::vulkan::descriptor_set_layout::{
+    DescriptorSetLayout, DescriptorSetLayoutBinding, DescriptorSetLayoutCreateInfo,
+};
+use crate::vulkan::device::Device;
+use crate::vulkan::shader::VERTEX_FRAGMENT;
+use crate::vulkan::texture::VulkanTexture;
+use crate::{DeviceError, Label};
+
+/// Set number of material resources, see `DescriptorSetAllocator`.
+pub const MATERIAL_DESCRIPTOR_SET: u32 = 2;
+
+#[derive(TypedBuilder)]
+pub struct MaterialDescriptor<'a> {
+    #[builder(default)]
+    pub label: Label<'a>,
+    pub device: &'a Rc<Device>,
+    /// Created by `Material::create_layout` with the same number of textures and uniform buffer.
+    pub layout: &'a DescriptorSetLayout,
+    #[builder(default)]
+    pub textures: &'a [&'a VulkanTexture],
+    #[builder(default)]
+    pub uniform_buffer: Option<&'a Buffer>,
+    #[builder(default = MATERIAL_DESCRIPTOR_SET)]
+    pub set_index: u32,
+}
+
+/// 材质持有绑定了纹理和 uniform buffer 的描述符集，绘制前绑定到管线布局的材质描述符集编号上。
+///
+/// The uniform buffer, if any, is at binding 0, followed by one combined image sampler per texture.
+pub struct Material {
+    device: Rc<Device>,
+    descriptor_set: vk::DescriptorSet,
+    set_index: u32,
+    // 描述符集随 pool 一起释放
+    _descriptor_pool: DescriptorPool,
+}
+
+impl Material {
+    pub fn descriptor_set(&self) -> vk::DescriptorSet {
+        self.descriptor_set
+    }
+
+    pub fn set_index(&self) -> u32 {
+        self.set_index
+    }
+
+    /// Layout of materials with `texture_count` textures, shared by all of them and their pipelines.
+    pub fn create_layout(
+        device: &Rc<Device>,
+        texture_count: u32,
+        uniform_buffer: bool,
+    ) -> Result<DescriptorSetLayout, DeviceError> {
+        let mut bindings = Vec::new();
+        if uniform_buffer {
+            bindings.push(DescriptorSetLayoutBinding {
+                binding: 0,
+                descriptor_type: vk::DescriptorType::UNIFORM_BUFFER,
+                descriptor_count: 1,
+                shader_stage_flags: VERTEX_FRAGMENT,
+                binding_flags: vk::DescriptorBindingFlags::empty(),
+            });
+        }
+        let first_texture_binding = bindings.len() as u32;
+        bindings.extend((0..texture_count).map(|index| DescriptorSetLayoutBinding {
+            binding: first_texture_binding + index,
+            descriptor_type: vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
+            descriptor_count: 1,
+            shader_stage_flags: vk::ShaderStageFlags::FRAGMENT,
+            binding_flags: vk::DescriptorBindingFlags::empty(),
+        }));
+        DescriptorSetLayout::new(
+            DescriptorSetLayoutCreateInfo::builder()
+                .device(device)
+                .bindings(&bindings)
+                .build(),
+        )
+    }
+
+    pub fn new(desc: MaterialDescriptor) -> Result<Self, DeviceError> {
+        let device = desc.device;
+        let mut pool_sizes = Vec::new();
+        if desc.uniform_buffer.is_some() {
+            pool_sizes.push(
+                vk::DescriptorPoolSize::builder()
+                    .ty(vk::DescriptorType::UNIFORM_BUFFER)
+                    .descriptor_count(1)
+                    .build(),
+            );
+        }
+        if !desc.textures.is_empty() {
+            pool_sizes.push(
+                vk::DescriptorPoolSize::builder()
+                    .ty(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+                    .descriptor_count(desc.textures.len() as u32)
+                    .build(),
+            );
+        }
+        if pool_sizes.is_empty() {
+            log::error!("Material needs at least a texture or a uniform buffer!");
+            return Err(DeviceError::Other("Material has no resources"));
+        }
+        let descriptor_pool = DescriptorPool::from_pool_sizes(device, &pool_sizes, 1)?;
+        let layouts = [desc.layout.raw()];
+        let allocate_info = vk::DescriptorSetAllocateInfo::builder()
+            .descriptor_pool(descriptor_pool.raw())
+            .set_layouts(&layouts);
+        let descriptor_set = device.allocate_descriptor_sets(&allocate_info)?[0];
+        if let Some(label) = desc.label {
+            unsafe {
+                device.set_object_name(vk::ObjectType::DESCRIPTOR_SET, descriptor_set, label)
+            };
+        }
+
+        // 描述符信息需要在 update_descriptor_sets 调用期间保持有效
+        let buffer_infos = desc
+            .uniform_buffer
+            .map(|buffer| {
+                vk::DescriptorBufferInfo::builder()
+                    .buffer(buffer.raw())
+                    .offset(0)
+                    .range(vk::WHOLE_SIZE)
+                    .build()
+            })
+            .into_iter()
+            .collect::<Vec<_>>();
+        let image_infos = desc
+            .textures
+            .iter()
+            .map(|texture| {
+                vk::DescriptorImageInfo::builder()
+                    .image_layout(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)
+                    .image_view(texture.raw_image_view())
+                    .sampler(texture.raw_sampler())
+                    .build()
+            })
+            .collect::<Vec<_>>();
+        let mut writes = Vec::with_capacity(buffer_infos.len() + image_infos.len());
+        if !buffer_infos.is_empty() {
+            writes.push(
+                vk::WriteDescriptorSet::builder()
+                    .dst_set(descriptor_set)
+                    .dst_binding(0)
+                    .dst_array_element(0)
+                    .descriptor_type(vk::DescriptorType::UNIFORM_BUFFER)
+                    .buffer_info(&buffer_infos)
+                    .build(),
+            );
+        }
+        let first_texture_binding = buffer_infos.len() as u32;
+        writes.extend(image_infos.iter().enumerate().map(|(index, image_info)| {
+            vk::WriteDescriptorSet::builder()
+                .dst_set(descriptor_set)
+                .dst_binding(first_texture_binding + index as u32)
+                .dst_array_element(0)
+                .descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+                .image_info(std::slice::from_ref(image_info))
+                .build()
+        }));
+        device.update_descriptor_sets(&writes, &[]);
+
+        log::debug!("Material created.");
+        Ok(Self {
+            device: device.clone(),
+            descriptor_set,
+            set_index: desc.set_index,
+            _descriptor_pool: descriptor_pool,
+        })
+    }
+
+    /// `pipeline_layout` must have a compatible layout at `set_index`.
+    pub fn bind(&self, command_buffer: vk::CommandBuffer, pipeline_layout: vk::PipelineLayout) {
+        self.device.cmd_bind_descriptor_sets(
+            command_buffer,
+            vk::PipelineBindPoint::GRAPHICS,
+            pipeline_layout,
+            self.set_index,
+            &[self.descriptor_set],
+            &[],
+        );
+    }
+}
+
+impl Drop for Material {
+    fn drop(&mut self) {
+        log::debug!("Material destroyed.");
+    }
+}
@@ -1,27 +1,37 @@
 pub mod adapter;
 pub mod buffer;
+pub mod capture;
 pub mod command_buffer;
 pub mod command_buffer_allocator;
 pub mod conv;
 pub mod debug;
+pub mod descriptor_allocator;
 pub mod descriptor_pool;
 pub mod descriptor_set_allocator;
 pub mod descriptor_set_layout;
 pub mod device;
+pub mod dynamic_mesh;
+pub mod fence;
+pub mod format;
+pub mod framebuffer;
 pub mod image;
 pub mod image_view;
 pub mod imgui;
 pub mod instance;
+pub mod material;
 pub mod model;
+pub mod passes;
 pub mod pipeline;
 pub mod pipeline_layout;
 pub mod platforms;
 pub mod render_pass;
 pub mod renderer;
 pub mod sampler;
+pub mod semaphore;
 pub mod shader;
 pub mod surface;
 pub mod swapchain;
 pub mod texture;
 pub mod uniform_buffer;
+pub mod upload;
 pub mod utils;
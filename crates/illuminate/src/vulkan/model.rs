@@ -1,6 +1,7 @@
 use std::collections::HashMap;
 use std::fs::File;
 use std::io::BufReader;
+use std::path::Path;
 use std::rc::Rc;
 
 use ash::vk;
@@ -22,6 +23,14 @@ pub struct Model {
     texture: VulkanTexture,
 }
 
+/// Triangulated geometry of a single glTF primitive, ready to be uploaded as vertex/index buffers.
+#[derive(Clone, Debug, Default)]
+pub struct Mesh {
+    pub vertices: Vec<Vertex3D>,
+    pub indices: Vec<u32>,
+    pub material_index: Option<usize>,
+}
+
 #[derive(Clone, TypedBuilder)]
 pub struct ModelDescriptor<'a> {
     pub file_name: &'a str,
@@ -118,6 +127,65 @@ impl Model {
             texture,
         })
     }
+
+    /// Loads every triangle primitive of a glTF file, one [`Mesh`] per primitive.
+    pub fn load_gltf<P: AsRef<Path>>(path: P) -> anyhow::Result<Vec<Mesh>> {
+        let (document, buffers, _) = gltf::import(path.as_ref())?;
+
+        let mut meshes = Vec::new();
+        for mesh in document.meshes() {
+            for primitive in mesh.primitives() {
+                if primitive.mode() != gltf::mesh::Mode::Triangles {
+                    log::warn!(
+                        "skip glTF primitive with unsupported topology {:?}",
+                        primitive.mode()
+                    );
+                    continue;
+                }
+                let reader = primitive.reader(|buffer| Some(&buffers[buffer.index()]));
+
+                let positions = match reader.read_positions() {
+                    Some(positions) => positions,
+                    None => continue,
+                };
+                let mut colors = reader.read_colors(0).map(|c| c.into_rgb_f32());
+                let mut tex_coords = reader.read_tex_coords(0).map(|t| t.into_f32());
+
+                let vertices = positions
+                    .map(|position| {
+                        let color = colors
+                            .as_mut()
+                            .and_then(|c| c.next())
+                            .unwrap_or([1.0, 1.0, 1.0]);
+                        let tex_coord = tex_coords
+                            .as_mut()
+                            .and_then(|t| t.next())
+                            .unwrap_or([0.0, 0.0]);
+                        // glTF 和 Vulkan 一样以左上角为纹理坐标原点，不需要翻转 v
+                        Vertex3D {
+                            position: vec3(position[0], position[1], position[2]),
+                            color: vec3(color[0], color[1], color[2]),
+                            tex_coord: vec2(tex_coord[0], tex_coord[1]),
+                        }
+                    })
+                    .collect::<Vec<_>>();
+
+                let indices = match reader.read_indices() {
+                    Some(indices) => indices.into_u32().collect(),
+                    None => (0..vertices.len() as u32).collect(),
+                };
+
+                meshes.push(Mesh {
+                    vertices,
+                    indices,
+                    material_index: primitive.material().index(),
+                });
+            }
+        }
+
+        log::debug!("glTF model loaded, {} meshes.", meshes.len());
+        Ok(meshes)
+    }
 }
 
 impl Drop for Model {
@@ -125,3 +193,21 @@ impl Drop for Model {
         log::debug!("model destroyed.");
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn load_gltf_reads_an_embedded_cube() {
+        let mut path = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("../../resources/models/cube.gltf");
+        let meshes = Model::load_gltf(path).unwrap();
+        assert_eq!(meshes.len(), 1);
+        // 每个面 4 个顶点、2 个三角形
+        assert_eq!(meshes[0].vertices.len(), 24);
+        assert_eq!(meshes[0].indices.len(), 36);
+        assert!(meshes[0].indices.iter().all(|&index| index < 24));
+        assert_eq!(meshes[0].material_index, None);
+    }
+}
@@ -0,0 +1,252 @@
+use alloc::rc::Rc;
+
+use ash::vk;
+use gpu_allocator::vulkan::Allocator;
+use gpu_allocator::MemoryLocation;
+use math::{Vec2, Vec3, Vec4, Vertex3D};
+use parking_lot::Mutex;
+use typed_builder::TypedBuilder;
+
+use crate::vulkan::buffer::{Buffer, BufferDescriptor};
+use crate::vulkan::device::Device;
+use crate::vulkan::pipeline::{ColorBlendState, DepthState, Pipeline, PrimitiveState};
+use crate::vulkan::shader::{Shader, ShaderDescriptor};
+
+pub const DEBUG_GRID_VERTEX_SHADER: &str = "debug_grid.vert";
+pub const DEBUG_GRID_FRAGMENT_SHADER: &str = "debug_grid.frag";
+
+const GRID_COLOR: Vec3 = Vec3::new(0.5, 0.5, 0.5);
+const AXIS_COLORS: [Vec3; 3] = [
+    Vec3::new(1.0, 0.0, 0.0),
+    Vec3::new(0.0, 1.0, 0.0),
+    Vec3::new(0.0, 0.0, 1.0),
+];
+
+#[derive(TypedBuilder)]
+pub struct DebugGridPassDescriptor<'a> {
+    pub device: &'a Rc<Device>,
+    pub allocator: Rc<Mutex<Allocator>>,
+    /// Must have a depth attachment, the grid is depth tested but does not write depth.
+    pub render_pass: vk::RenderPass,
+    #[builder(default = vk::SampleCountFlags::TYPE_1)]
+    pub msaa_samples: vk::SampleCountFlags,
+    /// The projection is reversed, see `DepthState::reverse_z`.
+    #[builder(default = false)]
+    pub reverse_z: bool,
+    /// Distance between grid lines in world units.
+    #[builder(default = 1.0)]
+    pub spacing: f32,
+    /// Grid lines on each side of an axis, the grid covers `half_line_count * spacing` around the origin.
+    #[builder(default = 50)]
+    pub half_line_count: u32,
+}
+
+/// 调试用的地面网格和坐标轴，位于 z = 0 平面（Z 轴朝上），X/Y/Z 轴分别为红/绿/蓝。
+///
+/// Drawn with `LINE_LIST` after the opaque geometry, lines fade out with their distance to the camera.
+pub struct DebugGridPass {
+    device: Rc<Device>,
+    pipeline: Pipeline,
+    vertex_buffer: Buffer,
+    vertex_count: u32,
+    spacing: f32,
+    half_line_count: u32,
+    enabled: bool,
+}
+
+impl DebugGridPass {
+    pub fn pipeline(&self) -> &Pipeline {
+        &self.pipeline
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    pub fn spacing(&self) -> f32 {
+        self.spacing
+    }
+
+    /// The vertices are rewritten in place, so the grid must no longer be in use by the GPU.
+    pub fn set_spacing(&mut self, spacing: f32) {
+        self.spacing = spacing;
+        self.vertex_buffer
+            .copy_memory(&Self::grid_vertices(spacing, self.half_line_count));
+    }
+
+    /// Grid lines parallel to X and Y, skipping the ones on the axes, followed by the three axis lines.
+    /// The X and Y axes cross the whole grid, the Z axis only points up.
+    ///
+    /// Returns `8 * half_line_count + 6` vertices, two per line.
+    pub fn grid_vertices(spacing: f32, half_line_count: u32) -> Vec<Vertex3D> {
+        let vertex = |position: Vec3, color: Vec3| Vertex3D::new(position, color, Vec2::zeros());
+        let half_extent = half_line_count as f32 * spacing;
+        let mut vertices = Vec::with_capacity(8 * half_line_count as usize + 6);
+        let half_line_count = half_line_count as i32;
+        for i in (-half_line_count..=half_line_count).filter(|&i| i != 0) {
+            let offset = i as f32 * spacing;
+            vertices.push(vertex(Vec3::new(-half_extent, offset, 0.0), GRID_COLOR));
+            vertices.push(vertex(Vec3::new(half_extent, offset, 0.0), GRID_COLOR));
+            vertices.push(vertex(Vec3::new(offset, -half_extent, 0.0), GRID_COLOR));
+            vertices.push(vertex(Vec3::new(offset, half_extent, 0.0), GRID_COLOR));
+        }
+        for (axis, color) in AXIS_COLORS.into_iter().enumerate() {
+            let mut start = Vec3::zeros();
+            let mut end = Vec3::zeros();
+            // 网格线跳过了坐标轴，X/Y 轴需要覆盖负半轴
+            if axis != 2 {
+                start[axis] = -half_extent;
+            }
+            end[axis] = half_extent;
+            vertices.push(vertex(start, color));
+            vertices.push(vertex(end, color));
+        }
+        vertices
+    }
+
+    pub fn new(desc: DebugGridPassDescriptor) -> anyhow::Result<Self> {
+        let device = desc.device;
+        let vertex_shader = Shader::new_vert(&ShaderDescriptor {
+            label: Some("Debug Grid Vert"),
+            device,
+            spv_bytes: &Shader::load_pre_compiled_spv_bytes_from_name(DEBUG_GRID_VERTEX_SHADER),
+            entry_name: "main",
+            specialization: None,
+        })?;
+        let fragment_shader = Shader::new_frag(&ShaderDescriptor {
+            label: Some("Debug Grid Frag"),
+            device,
+            spv_bytes: &Shader::load_pre_compiled_spv_bytes_from_name(DEBUG_GRID_FRAGMENT_SHADER),
+            entry_name: "main",
+            specialization: None,
+        })?;
+        let primitive_state = PrimitiveState::builder()
+            .topology(vk::PrimitiveTopology::LINE_LIST)
+            .cull_mode(vk::CullModeFlags::NONE)
+            .build();
+        // 淡出的线条是半透明的，不写深度，避免挡住之后绘制的物体
+        let compare_op = if desc.reverse_z {
+            vk::CompareOp::GREATER_OR_EQUAL
+        } else {
+            vk::CompareOp::LESS_OR_EQUAL
+        };
+        let depth_state = DepthState::builder()
+            .write_enable(false)
+            .compare_op(compare_op)
+            .build();
+        let pipeline = Pipeline::new(
+            device,
+            desc.render_pass,
+            desc.msaa_samples.into(),
+            &[],
+            &[vertex_shader, fragment_shader],
+            primitive_state,
+            depth_state,
+            &[ColorBlendState::alpha_blend()],
+        )?;
+
+        let vertices = Self::grid_vertices(desc.spacing, desc.half_line_count);
+        let mut vertex_buffer = Buffer::new(
+            BufferDescriptor::builder()
+                .label(Some("Debug Grid Vertex Buffer"))
+                .device(device)
+                .allocator(desc.allocator.clone())
+                .element_size(std::mem::size_of::<Vertex3D>())
+                .element_count(vertices.len() as u32)
+                .buffer_usage(vk::BufferUsageFlags::VERTEX_BUFFER)
+                .memory_location(MemoryLocation::CpuToGpu)
+                .build(),
+        )?;
+        vertex_buffer.copy_memory(&vertices);
+
+        log::debug!("Debug Grid Pass created.");
+        Ok(Self {
+            device: device.clone(),
+            pipeline,
+            vertex_buffer,
+            vertex_count: vertices.len() as u32,
+            spacing: desc.spacing,
+            half_line_count: desc.half_line_count,
+            enabled: true,
+        })
+    }
+
+    /// Draws nothing while disabled, the viewport and scissor must be set by the caller.
+    pub fn draw(
+        &self,
+        command_buffer: vk::CommandBuffer,
+        view_projection: &math::Mat4,
+        camera_position: Vec3,
+    ) {
+        if !self.enabled {
+            return;
+        }
+        self.device.cmd_bind_pipeline(
+            command_buffer,
+            vk::PipelineBindPoint::GRAPHICS,
+            self.pipeline.raw(),
+        );
+        let pipeline_layout = self.pipeline.raw_pipeline_layout();
+        let (_, view_projection_bytes, _) = unsafe { view_projection.as_slice().align_to::<u8>() };
+        self.device.cmd_push_constants(
+            command_buffer,
+            pipeline_layout,
+            vk::ShaderStageFlags::VERTEX,
+            0,
+            view_projection_bytes,
+        );
+        // 网格边缘处完全淡出
+        let fade_distance = self.half_line_count as f32 * self.spacing;
+        let camera_fade = Vec4::new(
+            camera_position.x,
+            camera_position.y,
+            camera_position.z,
+            fade_distance,
+        );
+        let (_, camera_fade_bytes, _) = unsafe { camera_fade.as_slice().align_to::<u8>() };
+        self.device.cmd_push_constants(
+            command_buffer,
+            pipeline_layout,
+            vk::ShaderStageFlags::VERTEX,
+            view_projection_bytes.len() as u32,
+            camera_fade_bytes,
+        );
+        self.device
+            .cmd_bind_vertex_buffers(command_buffer, 0, &[self.vertex_buffer.raw()], &[0]);
+        self.device
+            .cmd_draw(command_buffer, self.vertex_count, 1, 0, 0);
+    }
+}
+
+impl Drop for DebugGridPass {
+    fn drop(&mut self) {
+        log::debug!("Debug Grid Pass destroyed.");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grid_vertex_count() {
+        assert_eq!(DebugGridPass::grid_vertices(1.0, 0).len(), 6);
+        assert_eq!(DebugGridPass::grid_vertices(1.0, 50).len(), 8 * 50 + 6);
+    }
+
+    #[test]
+    fn horizontal_axes_cover_the_negative_side() {
+        let vertices = DebugGridPass::grid_vertices(0.5, 4);
+        let axes = &vertices[vertices.len() - 6..];
+        assert_eq!(axes[0].position, Vec3::new(-2.0, 0.0, 0.0));
+        assert_eq!(axes[1].position, Vec3::new(2.0, 0.0, 0.0));
+        assert_eq!(axes[2].position, Vec3::new(0.0, -2.0, 0.0));
+        assert_eq!(axes[3].position, Vec3::new(0.0, 2.0, 0.0));
+        assert_eq!(axes[4].position, Vec3::zeros());
+        assert_eq!(axes[5].position, Vec3::new(0.0, 0.0, 2.0));
+    }
+}
@@ -0,0 +1,99 @@
+use alloc::rc::Rc;
+
+use ash::vk;
+use typed_builder::TypedBuilder;
+
+use crate::vulkan::device::Device;
+use crate::vulkan::pipeline::{ColorBlendState, DepthState, Pipeline, PrimitiveState};
+use crate::vulkan::shader::{Shader, ShaderDescriptor};
+
+#[derive(TypedBuilder)]
+pub struct DepthPrepassDescriptor<'a> {
+    pub device: &'a Rc<Device>,
+    /// The render pass of the main pass, the prepass is drawn in the same subpass before it.
+    pub render_pass: vk::RenderPass,
+    #[builder(default = vk::SampleCountFlags::TYPE_1)]
+    pub msaa_samples: vk::SampleCountFlags,
+    /// Must be the same as the main pipeline, so descriptor sets can be bound the same way.
+    pub descriptor_set_layouts: &'a [vk::DescriptorSetLayout],
+    /// Name of the main pass vertex shader. The `EQUAL` compare of the main pass needs both
+    /// pipelines to compute the same depth, so the shader must declare `invariant gl_Position`.
+    pub vertex_shader: &'a str,
+    #[builder(default = false)]
+    pub reverse_z: bool,
+}
+
+/// 深度预渲染：先只写深度，主通道再以 EQUAL 比较绘制，每个像素只执行一次开销大的片元着色器。
+///
+/// The pipeline has no fragment stage and an empty color write mask, the main pipeline drawn
+/// afterwards must use `main_pass_depth_state`.
+pub struct DepthPrepass {
+    device: Rc<Device>,
+    pipeline: Pipeline,
+}
+
+impl DepthPrepass {
+    pub fn pipeline(&self) -> &Pipeline {
+        &self.pipeline
+    }
+
+    /// Only fragments that won the prepass are shaded, depth is already written.
+    pub fn main_pass_depth_state() -> DepthState {
+        DepthState::builder()
+            .write_enable(false)
+            .compare_op(vk::CompareOp::EQUAL)
+            .build()
+    }
+
+    pub fn new(desc: DepthPrepassDescriptor) -> anyhow::Result<Self> {
+        let device = desc.device;
+        let vertex_shader = Shader::new_vert(&ShaderDescriptor {
+            label: Some("Depth Prepass Vert"),
+            device,
+            spv_bytes: &Shader::load_pre_compiled_spv_bytes_from_name(desc.vertex_shader),
+            entry_name: "main",
+            specialization: None,
+        })?;
+        let depth_state = if desc.reverse_z {
+            DepthState::reverse_z()
+        } else {
+            DepthState::default()
+        };
+        let blend_state = ColorBlendState {
+            color_write_mask: vk::ColorComponentFlags::empty(),
+            ..ColorBlendState::opaque()
+        };
+        let pipeline = Pipeline::new(
+            device,
+            desc.render_pass,
+            desc.msaa_samples.into(),
+            desc.descriptor_set_layouts,
+            &[vertex_shader],
+            PrimitiveState::default(),
+            depth_state,
+            &[blend_state],
+        )?;
+
+        log::debug!("Depth Prepass created.");
+        Ok(Self {
+            device: device.clone(),
+            pipeline,
+        })
+    }
+
+    /// Binds the depth-only pipeline. Its layout only has the vertex push constant range, so
+    /// descriptor sets and push constants must be set again after binding the main pipeline.
+    pub fn bind(&self, command_buffer: vk::CommandBuffer) {
+        self.device.cmd_bind_pipeline(
+            command_buffer,
+            vk::PipelineBindPoint::GRAPHICS,
+            self.pipeline.raw(),
+        );
+    }
+}
+
+impl Drop for DepthPrepass {
+    fn drop(&mut self) {
+        log::debug!("Depth Prepass destroyed.");
+    }
+}
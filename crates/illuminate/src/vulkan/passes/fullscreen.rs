@@ -0,0 +1,84 @@
+use alloc::rc::Rc;
+
+use ash::vk;
+use typed_builder::TypedBuilder;
+
+use crate::vulkan::device::Device;
+use crate::vulkan::pipeline::{ColorBlendState, DepthState, Pipeline, PrimitiveState};
+use crate::vulkan::shader::{Shader, ShaderDescriptor};
+
+/// Precompiled name of the vertex shader, it outputs the uv at `location = 0`.
+pub const FULLSCREEN_VERTEX_SHADER: &str = "fullscreen.vert";
+
+#[derive(TypedBuilder)]
+pub struct FullscreenPassDescriptor<'a> {
+    pub device: &'a Rc<Device>,
+    pub render_pass: vk::RenderPass,
+    #[builder(default = vk::SampleCountFlags::TYPE_1)]
+    pub msaa_samples: vk::SampleCountFlags,
+    #[builder(default)]
+    pub descriptor_set_layouts: &'a [vk::DescriptorSetLayout],
+    /// Reads the uv from `location = 0`.
+    pub fragment_shader: Shader,
+    /// Replaces `FULLSCREEN_VERTEX_SHADER`, it must also generate the triangle from `gl_VertexIndex`.
+    #[builder(default)]
+    pub vertex_shader: Option<Shader>,
+    #[builder(default)]
+    pub depth_state: DepthState,
+    #[builder(default)]
+    pub color_blend_state: ColorBlendState,
+}
+
+/// 后处理使用的全屏三角形，顶点在着色器中由 `gl_VertexIndex` 生成，不需要顶点缓冲区。
+pub struct FullscreenPass {
+    device: Rc<Device>,
+    pipeline: Pipeline,
+}
+
+impl FullscreenPass {
+    pub fn pipeline(&self) -> &Pipeline {
+        &self.pipeline
+    }
+
+    pub fn new(desc: FullscreenPassDescriptor) -> anyhow::Result<Self> {
+        let vertex_shader = match desc.vertex_shader {
+            Some(vertex_shader) => vertex_shader,
+            None => Shader::new_vert(&ShaderDescriptor {
+                label: Some("Fullscreen Vert"),
+                device: desc.device,
+                spv_bytes: &Shader::load_pre_compiled_spv_bytes_from_name(FULLSCREEN_VERTEX_SHADER),
+                entry_name: "main",
+                specialization: None,
+            })?,
+        };
+        // 三角形的一部分在视口外，不做剔除，也就不用关心视口翻转后的绕序
+        let primitive_state = PrimitiveState::builder()
+            .cull_mode(vk::CullModeFlags::NONE)
+            .vertex_buffer(false)
+            .build();
+        let pipeline = Pipeline::new(
+            desc.device,
+            desc.render_pass,
+            desc.msaa_samples.into(),
+            desc.descriptor_set_layouts,
+            &[vertex_shader, desc.fragment_shader],
+            primitive_state,
+            desc.depth_state,
+            &[desc.color_blend_state],
+        )?;
+        Ok(Self {
+            device: desc.device.clone(),
+            pipeline,
+        })
+    }
+
+    /// Binds the pipeline and draws the triangle, descriptor sets must be bound by the caller.
+    pub fn draw(&self, command_buffer: vk::CommandBuffer) {
+        self.device.cmd_bind_pipeline(
+            command_buffer,
+            vk::PipelineBindPoint::GRAPHICS,
+            self.pipeline.raw(),
+        );
+        self.device.cmd_draw(command_buffer, 3, 1, 0, 0);
+    }
+}
@@ -0,0 +1,5 @@
+pub mod debug_grid;
+pub mod depth_prepass;
+pub mod fullscreen;
+pub mod outline;
+pub mod skybox;
@@ -0,0 +1,224 @@
+use alloc::rc::Rc;
+
+use ash::vk;
+use typed_builder::TypedBuilder;
+
+use crate::vulkan::device::Device;
+use crate::vulkan::pipeline::{
+    ColorBlendState, DepthState, Pipeline, PrimitiveState, StencilState,
+};
+use crate::vulkan::shader::{Shader, ShaderDescriptor};
+use crate::Color;
+
+pub const OUTLINE_VERTEX_SHADER: &str = "outline.vert";
+pub const OUTLINE_FRAGMENT_SHADER: &str = "outline.frag";
+
+#[derive(TypedBuilder)]
+pub struct OutlinePassDescriptor<'a> {
+    pub device: &'a Rc<Device>,
+    /// Its depth/stencil attachment must have a stencil component cleared to 0.
+    pub render_pass: vk::RenderPass,
+    #[builder(default = vk::SampleCountFlags::TYPE_1)]
+    pub msaa_samples: vk::SampleCountFlags,
+    /// Stencil value marking the outlined object, must differ from the clear value.
+    #[builder(default = 1)]
+    pub stencil_reference: u32,
+}
+
+/// The color and width of an outline.
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct OutlineStyle {
+    pub color: Color,
+    /// Scale of the silhouette in model space, e.g. 1.05 for a thin outline.
+    pub scale: f32,
+}
+
+impl Default for OutlineStyle {
+    fn default() -> Self {
+        Self {
+            color: Color::new(1.0, 0.6, 0.0, 1.0),
+            scale: 1.05,
+        }
+    }
+}
+
+/// 模板描边：先把物体写入模板缓冲区，再绘制放大的轮廓，只保留模板值不等于参考值的部分。
+///
+/// Draw the object with its own pipeline first, then bind `mask_pipeline` and draw it again,
+/// then bind `outline_pipeline` and draw it a third time. Vertex and index buffers are bound
+/// by the caller, only the position at `location = 0` is read.
+pub struct OutlinePass {
+    device: Rc<Device>,
+    mask_pipeline: Pipeline,
+    outline_pipeline: Pipeline,
+    stencil_reference: u32,
+}
+
+impl OutlinePass {
+    pub fn mask_pipeline(&self) -> &Pipeline {
+        &self.mask_pipeline
+    }
+
+    pub fn outline_pipeline(&self) -> &Pipeline {
+        &self.outline_pipeline
+    }
+
+    pub fn stencil_reference(&self) -> u32 {
+        self.stencil_reference
+    }
+
+    pub fn new(desc: OutlinePassDescriptor) -> anyhow::Result<Self> {
+        let device = desc.device;
+        let load_shaders = |label: &str| -> anyhow::Result<[Shader; 2]> {
+            let vertex_shader = Shader::new_vert(&ShaderDescriptor {
+                label: Some(format!("{} Vert", label).as_str()),
+                device,
+                spv_bytes: &Shader::load_pre_compiled_spv_bytes_from_name(OUTLINE_VERTEX_SHADER),
+                entry_name: "main",
+                specialization: None,
+            })?;
+            let fragment_shader = Shader::new_frag(&ShaderDescriptor {
+                label: Some(format!("{} Frag", label).as_str()),
+                device,
+                spv_bytes: &Shader::load_pre_compiled_spv_bytes_from_name(OUTLINE_FRAGMENT_SHADER),
+                entry_name: "main",
+                specialization: None,
+            })?;
+            Ok([vertex_shader, fragment_shader])
+        };
+        // 放大后的轮廓不再保证绕序一致，不做剔除
+        let primitive_state = PrimitiveState::builder()
+            .cull_mode(vk::CullModeFlags::NONE)
+            .build();
+
+        // 只写模板，不写颜色和深度，被遮挡的部分也写入，轮廓因此不会画进物体内部
+        let mask_depth_state = DepthState::builder()
+            .test_enable(false)
+            .write_enable(false)
+            .stencil(StencilState {
+                dynamic_reference: true,
+                ..StencilState::write(desc.stencil_reference)
+            })
+            .build();
+        let mask_blend_state = ColorBlendState {
+            color_write_mask: vk::ColorComponentFlags::empty(),
+            ..ColorBlendState::opaque()
+        };
+        let mask_pipeline = Pipeline::new(
+            device,
+            desc.render_pass,
+            desc.msaa_samples.into(),
+            &[],
+            &load_shaders("Outline Mask")?,
+            primitive_state,
+            mask_depth_state,
+            &[mask_blend_state],
+        )?;
+
+        // 轮廓不做深度测试，被遮挡时也能看到
+        let outline_depth_state = DepthState::builder()
+            .test_enable(false)
+            .write_enable(false)
+            .stencil(StencilState {
+                dynamic_reference: true,
+                ..StencilState::not_equal(desc.stencil_reference)
+            })
+            .build();
+        let outline_pipeline = Pipeline::new(
+            device,
+            desc.render_pass,
+            desc.msaa_samples.into(),
+            &[],
+            &load_shaders("Outline")?,
+            primitive_state,
+            outline_depth_state,
+            &[ColorBlendState::default()],
+        )?;
+
+        log::debug!("Outline Pass created.");
+        Ok(Self {
+            device: device.clone(),
+            mask_pipeline,
+            outline_pipeline,
+            stencil_reference: desc.stencil_reference,
+        })
+    }
+
+    /// Binds `mask_pipeline`, the next draws write the stencil reference.
+    pub fn bind_mask(&self, command_buffer: vk::CommandBuffer, model_view_projection: &math::Mat4) {
+        let style = OutlineStyle {
+            scale: 1.0,
+            ..Default::default()
+        };
+        self.bind(
+            command_buffer,
+            &self.mask_pipeline,
+            model_view_projection,
+            &style,
+        );
+    }
+
+    /// Binds `outline_pipeline`, the next draws are scaled and only kept outside the mask.
+    pub fn bind_outline(
+        &self,
+        command_buffer: vk::CommandBuffer,
+        model_view_projection: &math::Mat4,
+        style: &OutlineStyle,
+    ) {
+        self.bind(
+            command_buffer,
+            &self.outline_pipeline,
+            model_view_projection,
+            style,
+        );
+    }
+
+    fn bind(
+        &self,
+        command_buffer: vk::CommandBuffer,
+        pipeline: &Pipeline,
+        model_view_projection: &math::Mat4,
+        style: &OutlineStyle,
+    ) {
+        self.device.cmd_bind_pipeline(
+            command_buffer,
+            vk::PipelineBindPoint::GRAPHICS,
+            pipeline.raw(),
+        );
+        self.device.cmd_set_stencil_reference(
+            command_buffer,
+            vk::StencilFaceFlags::FRONT_AND_BACK,
+            self.stencil_reference,
+        );
+        let pipeline_layout = pipeline.raw_pipeline_layout();
+        let (_, mvp_bytes, _) = unsafe { model_view_projection.as_slice().align_to::<u8>() };
+        self.device.cmd_push_constants(
+            command_buffer,
+            pipeline_layout,
+            vk::ShaderStageFlags::VERTEX,
+            0,
+            mvp_bytes,
+        );
+        // color 和 scale 紧跟在 64 字节的矩阵之后
+        let style_bytes = unsafe {
+            std::slice::from_raw_parts(
+                style as *const OutlineStyle as *const u8,
+                std::mem::size_of::<OutlineStyle>(),
+            )
+        };
+        self.device.cmd_push_constants(
+            command_buffer,
+            pipeline_layout,
+            vk::ShaderStageFlags::VERTEX,
+            mvp_bytes.len() as u32,
+            style_bytes,
+        );
+    }
+}
+
+impl Drop for OutlinePass {
+    fn drop(&mut self) {
+        log::debug!("Outline Pass destroyed.");
+    }
+}
@@ -0,0 +1,195 @@
+use alloc::rc::Rc;
+
+use ash::vk;
+use typed_builder::TypedBuilder;
+
+use crate::vulkan::descriptor_pool::DescriptorPool;
+use crate::vulkan::descriptor_set_layout::{
+    DescriptorSetLayout, DescriptorSetLayoutBinding, DescriptorSetLayoutCreateInfo,
+};
+use crate::vulkan::device::Device;
+use crate::vulkan::passes::fullscreen::{FullscreenPass, FullscreenPassDescriptor};
+use crate::vulkan::pipeline::{DepthState, Pipeline};
+use crate::vulkan::shader::{Shader, ShaderDescriptor, SpecializationInfo};
+
+pub const SKYBOX_VERTEX_SHADER: &str = "skybox.vert";
+pub const SKYBOX_FRAGMENT_SHADER: &str = "skybox.frag";
+
+#[derive(TypedBuilder)]
+pub struct SkyboxPassDescriptor<'a> {
+    pub device: &'a Rc<Device>,
+    /// Must have a depth attachment cleared to 1.0, or 0.0 with `reverse_z`.
+    pub render_pass: vk::RenderPass,
+    #[builder(default = vk::SampleCountFlags::TYPE_1)]
+    pub msaa_samples: vk::SampleCountFlags,
+    /// The projection is reversed, see `DepthState::reverse_z`. The far plane is then at depth 0.0.
+    #[builder(default = false)]
+    pub reverse_z: bool,
+}
+
+/// 用立方体贴图绘制天空盒，放在不透明几何体之后绘制。
+///
+/// 全屏三角形位于远平面上，深度测试为 LESS_OR_EQUAL（reverse-Z 时为 GREATER_OR_EQUAL）且不写深度，因此只覆盖没有几何体的像素。
+pub struct SkyboxPass {
+    device: Rc<Device>,
+    fullscreen_pass: FullscreenPass,
+    descriptor_set: vk::DescriptorSet,
+    // 描述符集随 pool 一起释放
+    _descriptor_pool: DescriptorPool,
+    _descriptor_set_layout: DescriptorSetLayout,
+    has_cubemap: bool,
+}
+
+impl SkyboxPass {
+    pub fn pipeline(&self) -> &Pipeline {
+        self.fullscreen_pass.pipeline()
+    }
+
+    pub fn new(desc: SkyboxPassDescriptor) -> anyhow::Result<Self> {
+        let device = desc.device;
+        let image_binding = DescriptorSetLayoutBinding {
+            binding: 0,
+            descriptor_type: vk::DescriptorType::SAMPLED_IMAGE,
+            descriptor_count: 1,
+            shader_stage_flags: vk::ShaderStageFlags::FRAGMENT,
+            binding_flags: vk::DescriptorBindingFlags::empty(),
+        };
+        let sampler_binding = DescriptorSetLayoutBinding {
+            binding: 1,
+            descriptor_type: vk::DescriptorType::SAMPLER,
+            descriptor_count: 1,
+            shader_stage_flags: vk::ShaderStageFlags::FRAGMENT,
+            binding_flags: vk::DescriptorBindingFlags::empty(),
+        };
+        let descriptor_set_layout = DescriptorSetLayout::new(
+            DescriptorSetLayoutCreateInfo::builder()
+                .device(device)
+                .bindings(&[image_binding, sampler_binding])
+                .build(),
+        )?;
+
+        let pool_sizes = [
+            vk::DescriptorPoolSize::builder()
+                .ty(vk::DescriptorType::SAMPLED_IMAGE)
+                .descriptor_count(1)
+                .build(),
+            vk::DescriptorPoolSize::builder()
+                .ty(vk::DescriptorType::SAMPLER)
+                .descriptor_count(1)
+                .build(),
+        ];
+        let descriptor_pool = DescriptorPool::from_pool_sizes(device, &pool_sizes, 1)?;
+        let layouts = [descriptor_set_layout.raw()];
+        let allocate_info = vk::DescriptorSetAllocateInfo::builder()
+            .descriptor_pool(descriptor_pool.raw())
+            .set_layouts(&layouts);
+        let descriptor_set = device.allocate_descriptor_sets(&allocate_info)?[0];
+
+        // constant_id = 0 是着色器中的 REVERSE_Z
+        let specialization = SpecializationInfo::default().with_constant(0, desc.reverse_z);
+        let vertex_shader = Shader::new_vert(&ShaderDescriptor {
+            label: Some("Skybox Vert"),
+            device,
+            spv_bytes: &Shader::load_pre_compiled_spv_bytes_from_name(SKYBOX_VERTEX_SHADER),
+            entry_name: "main",
+            specialization: Some(&specialization),
+        })?;
+        let fragment_shader = Shader::new_frag(&ShaderDescriptor {
+            label: Some("Skybox Frag"),
+            device,
+            spv_bytes: &Shader::load_pre_compiled_spv_bytes_from_name(SKYBOX_FRAGMENT_SHADER),
+            entry_name: "main",
+            specialization: None,
+        })?;
+        // 深度缓冲区清除为远平面的深度，远平面上的片段只在没有几何体的地方通过测试
+        let compare_op = if desc.reverse_z {
+            vk::CompareOp::GREATER_OR_EQUAL
+        } else {
+            vk::CompareOp::LESS_OR_EQUAL
+        };
+        let depth_state = DepthState::builder()
+            .write_enable(false)
+            .compare_op(compare_op)
+            .build();
+        let fullscreen_pass = FullscreenPass::new(
+            FullscreenPassDescriptor::builder()
+                .device(device)
+                .render_pass(desc.render_pass)
+                .msaa_samples(desc.msaa_samples)
+                .descriptor_set_layouts(&layouts)
+                .fragment_shader(fragment_shader)
+                .vertex_shader(Some(vertex_shader))
+                .depth_state(depth_state)
+                .build(),
+        )?;
+
+        log::debug!("Skybox Pass created.");
+        Ok(Self {
+            device: device.clone(),
+            fullscreen_pass,
+            descriptor_set,
+            _descriptor_pool: descriptor_pool,
+            _descriptor_set_layout: descriptor_set_layout,
+            has_cubemap: false,
+        })
+    }
+
+    /// `image_view` must be a cube view in `SHADER_READ_ONLY_OPTIMAL`.
+    ///
+    /// The descriptor set is updated in place, so the previous cubemap must no longer be in use by the GPU.
+    pub fn set_cubemap(&mut self, image_view: vk::ImageView, sampler: vk::Sampler) {
+        let image_infos = [vk::DescriptorImageInfo::builder()
+            .image_layout(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)
+            .image_view(image_view)
+            .build()];
+        let image_write = vk::WriteDescriptorSet::builder()
+            .dst_set(self.descriptor_set)
+            .dst_binding(0)
+            .dst_array_element(0)
+            .descriptor_type(vk::DescriptorType::SAMPLED_IMAGE)
+            .image_info(&image_infos)
+            .build();
+        let sampler_infos = [vk::DescriptorImageInfo::builder().sampler(sampler).build()];
+        let sampler_write = vk::WriteDescriptorSet::builder()
+            .dst_set(self.descriptor_set)
+            .dst_binding(1)
+            .dst_array_element(0)
+            .descriptor_type(vk::DescriptorType::SAMPLER)
+            .image_info(&sampler_infos)
+            .build();
+        self.device
+            .update_descriptor_sets(&[image_write, sampler_write], &[]);
+        self.has_cubemap = true;
+    }
+
+    /// Draws nothing until a cubemap is set.
+    pub fn draw(&self, command_buffer: vk::CommandBuffer, inverse_view_projection: &math::Mat4) {
+        if !self.has_cubemap {
+            return;
+        }
+        let pipeline_layout = self.pipeline().raw_pipeline_layout();
+        self.device.cmd_bind_descriptor_sets(
+            command_buffer,
+            vk::PipelineBindPoint::GRAPHICS,
+            pipeline_layout,
+            0,
+            &[self.descriptor_set],
+            &[],
+        );
+        let (_, bytes, _) = unsafe { inverse_view_projection.as_slice().align_to::<u8>() };
+        self.device.cmd_push_constants(
+            command_buffer,
+            pipeline_layout,
+            vk::ShaderStageFlags::VERTEX,
+            0,
+            bytes,
+        );
+        self.fullscreen_pass.draw(command_buffer);
+    }
+}
+
+impl Drop for SkyboxPass {
+    fn drop(&mut self) {
+        log::debug!("Skybox Pass destroyed.");
+    }
+}
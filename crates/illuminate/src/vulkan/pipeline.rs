@@ -20,6 +20,359 @@ pub struct PipelineDescriptor<'a> {
     pub label: Label<'a>,
 }
 
+/// Winding order of front-facing triangles.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FrontFace {
+    CounterClockwise,
+    Clockwise,
+}
+
+impl FrontFace {
+    /// The opposite winding.
+    ///
+    /// 视口高度为负时图元在 Y 方向镜像，光栅化器看到的环绕方向随之反转，翻转正面才能剔除同一批三角形。
+    pub fn flipped(self) -> Self {
+        match self {
+            Self::CounterClockwise => Self::Clockwise,
+            Self::Clockwise => Self::CounterClockwise,
+        }
+    }
+}
+
+impl From<FrontFace> for vk::FrontFace {
+    fn from(front_face: FrontFace) -> Self {
+        match front_face {
+            FrontFace::CounterClockwise => vk::FrontFace::COUNTER_CLOCKWISE,
+            FrontFace::Clockwise => vk::FrontFace::CLOCKWISE,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, TypedBuilder)]
+pub struct PrimitiveState {
+    #[builder(default = vk::PrimitiveTopology::TRIANGLE_LIST)]
+    pub topology: vk::PrimitiveTopology,
+    /// Only used by tessellation pipelines, which must use `PATCH_LIST`.
+    #[builder(default = 0)]
+    pub patch_control_points: u32,
+    #[builder(default = vk::CullModeFlags::BACK)]
+    pub cull_mode: vk::CullModeFlags,
+    /// `LINE` draws wireframes, any mode other than `FILL` needs the `fill_mode_non_solid` feature.
+    #[builder(default = vk::PolygonMode::FILL)]
+    pub polygon_mode: vk::PolygonMode,
+    #[builder(default = FrontFace::CounterClockwise)]
+    pub front_face: FrontFace,
+    /// Set when drawing with a negative viewport height, e.g. `Swapchain::viewport`, to flip
+    /// `front_face` so `cull_mode` keeps culling the same triangles.
+    #[builder(default = false)]
+    pub y_flipped_viewport: bool,
+    /// `false` when the vertex shader generates its vertices, e.g. the fullscreen triangle.
+    #[builder(default = true)]
+    pub vertex_buffer: bool,
+    /// An index of `0xFFFF` / `0xFFFFFFFF` restarts the primitive, only valid with strip and fan topologies.
+    #[builder(default = false)]
+    pub primitive_restart: bool,
+    /// Number of dynamic viewports and scissors, more than one needs the `multi_viewport` feature.
+    #[builder(default = 1)]
+    pub viewport_count: u32,
+}
+
+impl Default for PrimitiveState {
+    fn default() -> Self {
+        Self::builder().build()
+    }
+}
+
+impl PrimitiveState {
+    /// The front face the pipeline is created with.
+    pub fn effective_front_face(&self) -> FrontFace {
+        if self.y_flipped_viewport {
+            self.front_face.flipped()
+        } else {
+            self.front_face
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, TypedBuilder)]
+pub struct DepthBoundsState {
+    /// Keeps only fragments whose stored depth is inside the bounds, needs the `depth_bounds` feature.
+    #[builder(default = false)]
+    pub test_enable: bool,
+    #[builder(default = 0.0)]
+    pub min_depth_bounds: f32,
+    #[builder(default = 1.0)]
+    pub max_depth_bounds: f32,
+    /// Bounds are set by `Device::cmd_set_depth_bounds` instead.
+    #[builder(default = false)]
+    pub dynamic: bool,
+}
+
+impl Default for DepthBoundsState {
+    fn default() -> Self {
+        Self::builder().build()
+    }
+}
+
+/// Attachment formats a pipeline renders to with dynamic rendering, instead of a render pass.
+#[derive(Clone, Debug, Default, TypedBuilder)]
+pub struct RenderingFormats {
+    /// One per color attachment, in the order of `color_blend_states`.
+    #[builder(default)]
+    pub color_formats: Vec<vk::Format>,
+    /// `UNDEFINED` without a depth attachment.
+    #[builder(default = vk::Format::UNDEFINED)]
+    pub depth_format: vk::Format,
+    /// `UNDEFINED` without a stencil attachment.
+    #[builder(default = vk::Format::UNDEFINED)]
+    pub stencil_format: vk::Format,
+}
+
+/// What a graphics pipeline is created for, subpass 0 of a render pass or dynamic rendering.
+#[derive(Clone, Copy, Debug)]
+pub enum PipelineTarget<'a> {
+    RenderPass(vk::RenderPass),
+    /// Needs the `dynamic_rendering` requirement.
+    DynamicRendering(&'a RenderingFormats),
+}
+
+impl From<vk::RenderPass> for PipelineTarget<'_> {
+    fn from(render_pass: vk::RenderPass) -> Self {
+        Self::RenderPass(render_pass)
+    }
+}
+
+impl<'a> From<&'a RenderingFormats> for PipelineTarget<'a> {
+    fn from(formats: &'a RenderingFormats) -> Self {
+        Self::DynamicRendering(formats)
+    }
+}
+
+#[derive(Clone, Debug, TypedBuilder)]
+pub struct MultisampleState {
+    #[builder(default = vk::SampleCountFlags::TYPE_1)]
+    pub samples: vk::SampleCountFlags,
+    /// One word per 32 samples, `None` keeps every sample.
+    #[builder(default)]
+    pub sample_mask: Option<Vec<u32>>,
+}
+
+impl From<vk::SampleCountFlags> for MultisampleState {
+    fn from(samples: vk::SampleCountFlags) -> Self {
+        Self::builder().samples(samples).build()
+    }
+}
+
+impl MultisampleState {
+    /// Vulkan reads `ceil(samples / 32)` words, a mask of another length is padded with
+    /// all-samples words or truncated.
+    pub fn sample_mask_words(&self) -> Option<Vec<u32>> {
+        let mut sample_mask = self.sample_mask.clone()?;
+        let word_count = ((self.samples.as_raw() + 31) / 32) as usize;
+        if sample_mask.len() != word_count {
+            log::warn!(
+                "Sample mask has {} words, but {:?} samples need {}, it is resized.",
+                sample_mask.len(),
+                self.samples,
+                word_count
+            );
+            sample_mask.resize(word_count, u32::MAX);
+        }
+        Some(sample_mask)
+    }
+}
+
+/// Stencil test, the same ops are used for front and back faces.
+///
+/// The depth/stencil attachment must have a stencil component, e.g. `D24_UNORM_S8_UINT`.
+#[derive(Clone, Copy, Debug, TypedBuilder)]
+pub struct StencilState {
+    #[builder(default = false)]
+    pub test_enable: bool,
+    #[builder(default = vk::CompareOp::ALWAYS)]
+    pub compare_op: vk::CompareOp,
+    /// Applied when the stencil test fails.
+    #[builder(default = vk::StencilOp::KEEP)]
+    pub fail_op: vk::StencilOp,
+    /// Applied when both the stencil and the depth test pass.
+    #[builder(default = vk::StencilOp::KEEP)]
+    pub pass_op: vk::StencilOp,
+    /// Applied when the stencil test passes but the depth test fails.
+    #[builder(default = vk::StencilOp::KEEP)]
+    pub depth_fail_op: vk::StencilOp,
+    #[builder(default = 0xFF)]
+    pub compare_mask: u32,
+    #[builder(default = 0xFF)]
+    pub write_mask: u32,
+    #[builder(default = 0)]
+    pub reference: u32,
+    /// Reference is set by `Device::cmd_set_stencil_reference` instead.
+    #[builder(default = false)]
+    pub dynamic_reference: bool,
+}
+
+impl Default for StencilState {
+    fn default() -> Self {
+        Self::builder().build()
+    }
+}
+
+impl StencilState {
+    /// Writes `reference` wherever a fragment is drawn.
+    pub fn write(reference: u32) -> Self {
+        Self::builder()
+            .test_enable(true)
+            .compare_op(vk::CompareOp::ALWAYS)
+            .pass_op(vk::StencilOp::REPLACE)
+            .depth_fail_op(vk::StencilOp::REPLACE)
+            .reference(reference)
+            .build()
+    }
+
+    /// Keeps only fragments where the stencil value differs from `reference`, without writing it.
+    pub fn not_equal(reference: u32) -> Self {
+        Self::builder()
+            .test_enable(true)
+            .compare_op(vk::CompareOp::NOT_EQUAL)
+            .write_mask(0)
+            .reference(reference)
+            .build()
+    }
+
+    pub fn raw(&self) -> vk::StencilOpState {
+        vk::StencilOpState {
+            fail_op: self.fail_op,
+            pass_op: self.pass_op,
+            depth_fail_op: self.depth_fail_op,
+            compare_op: self.compare_op,
+            compare_mask: self.compare_mask,
+            write_mask: self.write_mask,
+            reference: self.reference,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, TypedBuilder)]
+pub struct DepthState {
+    #[builder(default = true)]
+    pub test_enable: bool,
+    /// Disabled for passes drawn behind the scene, e.g. the skybox.
+    #[builder(default = true)]
+    pub write_enable: bool,
+    #[builder(default = vk::CompareOp::LESS)]
+    pub compare_op: vk::CompareOp,
+    #[builder(default)]
+    pub bounds: DepthBoundsState,
+    #[builder(default)]
+    pub stencil: StencilState,
+}
+
+impl Default for DepthState {
+    fn default() -> Self {
+        Self::builder().build()
+    }
+}
+
+impl DepthState {
+    /// For a reversed projection (e.g. `math::reversed_perspective_rh_zo`) that maps near to 1.0
+    /// and far to 0.0, the depth must be cleared to 0.0. The float precision near 0.0 then offsets
+    /// the perspective divide, which keeps distant depth from collapsing onto the same values.
+    pub fn reverse_z() -> Self {
+        Self::builder()
+            .compare_op(vk::CompareOp::GREATER_OR_EQUAL)
+            .build()
+    }
+}
+
+/// Blending of a color attachment, see the pseudocode in `create_graphics_pipeline`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ColorBlendState {
+    pub blend_enable: bool,
+    pub src_color_blend_factor: vk::BlendFactor,
+    pub dst_color_blend_factor: vk::BlendFactor,
+    pub color_blend_op: vk::BlendOp,
+    pub src_alpha_blend_factor: vk::BlendFactor,
+    pub dst_alpha_blend_factor: vk::BlendFactor,
+    pub alpha_blend_op: vk::BlendOp,
+    pub color_write_mask: vk::ColorComponentFlags,
+}
+
+impl Default for ColorBlendState {
+    /// The blending used before the presets existed: `alpha_blend` colors, but the new alpha
+    /// replaces the old one.
+    fn default() -> Self {
+        Self {
+            dst_alpha_blend_factor: vk::BlendFactor::ZERO,
+            ..Self::alpha_blend()
+        }
+    }
+}
+
+impl ColorBlendState {
+    /// Writes the new color as is.
+    pub fn opaque() -> Self {
+        Self {
+            blend_enable: false,
+            src_color_blend_factor: vk::BlendFactor::ONE,
+            dst_color_blend_factor: vk::BlendFactor::ZERO,
+            color_blend_op: vk::BlendOp::ADD,
+            src_alpha_blend_factor: vk::BlendFactor::ONE,
+            dst_alpha_blend_factor: vk::BlendFactor::ZERO,
+            alpha_blend_op: vk::BlendOp::ADD,
+            color_write_mask: vk::ColorComponentFlags::RGBA,
+        }
+    }
+
+    /// `src.rgb * src.a + dst.rgb * (1 - src.a)`, for straight alpha, e.g. the UI.
+    pub fn alpha_blend() -> Self {
+        Self {
+            blend_enable: true,
+            src_color_blend_factor: vk::BlendFactor::SRC_ALPHA,
+            dst_color_blend_factor: vk::BlendFactor::ONE_MINUS_SRC_ALPHA,
+            src_alpha_blend_factor: vk::BlendFactor::ONE,
+            dst_alpha_blend_factor: vk::BlendFactor::ONE_MINUS_SRC_ALPHA,
+            ..Self::opaque()
+        }
+    }
+
+    /// `src.rgb + dst.rgb * (1 - src.a)`, for colors already multiplied by their alpha.
+    pub fn premultiplied_alpha() -> Self {
+        Self {
+            blend_enable: true,
+            src_color_blend_factor: vk::BlendFactor::ONE,
+            dst_color_blend_factor: vk::BlendFactor::ONE_MINUS_SRC_ALPHA,
+            src_alpha_blend_factor: vk::BlendFactor::ONE,
+            dst_alpha_blend_factor: vk::BlendFactor::ONE_MINUS_SRC_ALPHA,
+            ..Self::opaque()
+        }
+    }
+
+    /// `src.rgb * src.a + dst.rgb`, order independent, e.g. particles and glows.
+    pub fn additive() -> Self {
+        Self {
+            blend_enable: true,
+            src_color_blend_factor: vk::BlendFactor::SRC_ALPHA,
+            dst_color_blend_factor: vk::BlendFactor::ONE,
+            src_alpha_blend_factor: vk::BlendFactor::ONE,
+            dst_alpha_blend_factor: vk::BlendFactor::ONE,
+            ..Self::opaque()
+        }
+    }
+
+    pub fn raw(&self) -> vk::PipelineColorBlendAttachmentState {
+        vk::PipelineColorBlendAttachmentState::builder()
+            .color_write_mask(self.color_write_mask)
+            .blend_enable(self.blend_enable)
+            .src_color_blend_factor(self.src_color_blend_factor)
+            .dst_color_blend_factor(self.dst_color_blend_factor)
+            .color_blend_op(self.color_blend_op)
+            .src_alpha_blend_factor(self.src_alpha_blend_factor)
+            .dst_alpha_blend_factor(self.dst_alpha_blend_factor)
+            .alpha_blend_op(self.alpha_blend_op)
+            .build()
+    }
+}
+
 impl Pipeline {
     pub fn raw(&self) -> vk::Pipeline {
         self.raw
@@ -29,20 +382,28 @@ impl Pipeline {
         self.pipeline_layout.raw()
     }
 
-    pub fn new(
+    /// `target` is a `vk::RenderPass` or `&RenderingFormats`, see `PipelineTarget`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new<'a>(
         device: &Rc<Device>,
-        render_pass: vk::RenderPass,
-        msaa_samples: vk::SampleCountFlags,
+        target: impl Into<PipelineTarget<'a>>,
+        multisample_state: MultisampleState,
         descriptor_set_layouts: &[vk::DescriptorSetLayout],
         shaders: &[Shader],
+        primitive_state: PrimitiveState,
+        depth_state: DepthState,
+        color_blend_states: &[ColorBlendState],
     ) -> Result<Self, DeviceError> {
         let pipeline_layout = PipelineLayout::new(device, shaders, descriptor_set_layouts)?;
         let raw = Self::create_graphics_pipeline(
             device,
-            render_pass,
+            target.into(),
             pipeline_layout.raw(),
-            msaa_samples,
+            multisample_state,
             shaders,
+            primitive_state,
+            depth_state,
+            color_blend_states,
         )?[0];
 
         Ok(Self {
@@ -52,30 +413,259 @@ impl Pipeline {
         })
     }
 
+    /// Geometry and tessellation stages need their device features, otherwise pipeline creation fails opaquely.
+    fn validate_shader_stages(device: &Device, shaders: &[Shader]) -> Result<(), DeviceError> {
+        let stages = Shader::combined_stages(shaders);
+        let features = device.enabled_features();
+        if stages.contains(vk::ShaderStageFlags::GEOMETRY) && features.geometry_shader != vk::TRUE {
+            log::error!(
+                "Pipeline has a geometry stage, but the geometry_shader feature was not enabled at device creation!"
+            );
+            return Err(DeviceError::NotMeetRequirement);
+        }
+        if stages.intersects(
+            vk::ShaderStageFlags::TESSELLATION_CONTROL
+                | vk::ShaderStageFlags::TESSELLATION_EVALUATION,
+        ) && features.tessellation_shader != vk::TRUE
+        {
+            log::error!(
+                "Pipeline has a tessellation stage, but the tessellation_shader feature was not enabled at device creation!"
+            );
+            return Err(DeviceError::NotMeetRequirement);
+        }
+        Ok(())
+    }
+
+    fn validate_depth_bounds_state(
+        device: &Device,
+        depth_bounds_state: DepthBoundsState,
+    ) -> Result<(), DeviceError> {
+        if depth_bounds_state.test_enable && device.enabled_features().depth_bounds != vk::TRUE {
+            log::error!(
+                "Pipeline enables the depth bounds test, but the depth_bounds feature was not enabled at device creation!"
+            );
+            return Err(DeviceError::NotMeetRequirement);
+        }
+        Ok(())
+    }
+
+    /// Without the independent_blend feature, all color attachments must use the same blend state.
+    fn validate_color_blend_states(
+        device: &Device,
+        color_blend_states: &[ColorBlendState],
+    ) -> Result<(), DeviceError> {
+        if device.enabled_features().independent_blend == vk::TRUE {
+            return Ok(());
+        }
+        let differing = color_blend_states
+            .iter()
+            .position(|state| state != &color_blend_states[0]);
+        if let Some(index) = differing {
+            log::error!(
+                "Color attachment {} has a different blend state than attachment 0, but the independent_blend feature was not enabled at device creation!",
+                index
+            );
+            return Err(DeviceError::NotMeetRequirement);
+        }
+        Ok(())
+    }
+
+    fn validate_polygon_mode(
+        device: &Device,
+        polygon_mode: vk::PolygonMode,
+    ) -> Result<(), DeviceError> {
+        if polygon_mode != vk::PolygonMode::FILL
+            && device.enabled_features().fill_mode_non_solid != vk::TRUE
+        {
+            log::error!(
+                "Pipeline uses {:?} polygon mode, but the fill_mode_non_solid feature was not enabled at device creation!",
+                polygon_mode
+            );
+            return Err(DeviceError::NotMeetRequirement);
+        }
+        Ok(())
+    }
+
+    /// Dynamic rendering must be enabled, and every color format needs a blend state.
+    fn validate_target(
+        device: &Device,
+        target: PipelineTarget,
+        color_blend_states: &[ColorBlendState],
+    ) -> Result<(), DeviceError> {
+        let PipelineTarget::DynamicRendering(formats) = target else {
+            return Ok(());
+        };
+        if !device.is_dynamic_rendering_enabled() {
+            log::error!(
+                "Pipeline targets dynamic rendering, but the dynamic_rendering feature was not enabled at device creation!"
+            );
+            return Err(DeviceError::NotMeetRequirement);
+        }
+        if formats.color_formats.len() != color_blend_states.len() {
+            log::error!(
+                "Pipeline has {} color formats but {} color blend states.",
+                formats.color_formats.len(),
+                color_blend_states.len()
+            );
+            return Err(DeviceError::Other(
+                "Color formats and color blend states must match one-to-one!",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Viewports and scissors are dynamic, `Device::cmd_set_viewports` and `Device::cmd_set_scissor`
+    /// must set `viewport_count` of each.
+    fn validate_viewport_count(device: &Device, viewport_count: u32) -> Result<(), DeviceError> {
+        if viewport_count == 0 {
+            log::error!("Pipeline needs at least one viewport!");
+            return Err(DeviceError::Other("Pipeline needs at least one viewport"));
+        }
+        if viewport_count > 1 && device.enabled_features().multi_viewport != vk::TRUE {
+            log::error!(
+                "Pipeline has {} viewports, but the multi_viewport feature was not enabled at device creation!",
+                viewport_count
+            );
+            return Err(DeviceError::NotMeetRequirement);
+        }
+        let max_viewports = device.limits().max_viewports;
+        if viewport_count > max_viewports {
+            log::error!(
+                "Pipeline has {} viewports, exceeds maxViewports ({}).",
+                viewport_count,
+                max_viewports
+            );
+            return Err(DeviceError::LimitExceeded {
+                limit: "maxViewports",
+                value: viewport_count,
+                max: max_viewports,
+            });
+        }
+        Ok(())
+    }
+
+    /// Every attribute format needs the `VERTEX_BUFFER` buffer feature, see
+    /// `Device::supports_vertex_buffer_format`.
+    fn validate_vertex_attributes(
+        device: &Device,
+        attributes: &[vk::VertexInputAttributeDescription],
+    ) -> Result<(), DeviceError> {
+        let invalid = attributes
+            .iter()
+            .find(|attribute| !device.supports_vertex_buffer_format(attribute.format));
+        if let Some(attribute) = invalid {
+            log::error!(
+                "Vertex attribute at location {} uses {:?}, which can not be read from a vertex buffer!",
+                attribute.location,
+                attribute.format
+            );
+            return Err(DeviceError::InvalidVertexAttributeFormat {
+                location: attribute.location,
+                format: attribute.format,
+            });
+        }
+        Ok(())
+    }
+
+    /// Tessellation stages consume patches, so they need `PATCH_LIST` with at least one control point.
+    fn validate_primitive_state(
+        stages: vk::ShaderStageFlags,
+        primitive_state: PrimitiveState,
+    ) -> Result<(), DeviceError> {
+        let has_tessellation = stages.intersects(
+            vk::ShaderStageFlags::TESSELLATION_CONTROL
+                | vk::ShaderStageFlags::TESSELLATION_EVALUATION,
+        );
+        let is_patch_list = primitive_state.topology == vk::PrimitiveTopology::PATCH_LIST;
+        if has_tessellation && (!is_patch_list || primitive_state.patch_control_points == 0) {
+            log::error!(
+                "Pipeline has tessellation stages, but topology is {:?} with {} patch control points, PATCH_LIST with at least one control point is required!",
+                primitive_state.topology,
+                primitive_state.patch_control_points
+            );
+            return Err(DeviceError::Other(
+                "Tessellation pipeline requires PATCH_LIST topology",
+            ));
+        }
+        if !has_tessellation && is_patch_list {
+            log::error!("Pipeline uses PATCH_LIST topology, but has no tessellation stages!");
+            return Err(DeviceError::Other(
+                "PATCH_LIST topology requires tessellation stages",
+            ));
+        }
+        // 列表拓扑的图元重启需要 VK_EXT_primitive_topology_list_restart，目前没有启用
+        let is_list = matches!(
+            primitive_state.topology,
+            vk::PrimitiveTopology::POINT_LIST
+                | vk::PrimitiveTopology::LINE_LIST
+                | vk::PrimitiveTopology::TRIANGLE_LIST
+                | vk::PrimitiveTopology::LINE_LIST_WITH_ADJACENCY
+                | vk::PrimitiveTopology::TRIANGLE_LIST_WITH_ADJACENCY
+                | vk::PrimitiveTopology::PATCH_LIST
+        );
+        if primitive_state.primitive_restart && is_list {
+            log::error!(
+                "Pipeline enables primitive restart with {:?} topology, only strip and fan topologies support it!",
+                primitive_state.topology
+            );
+            return Err(DeviceError::Other(
+                "Primitive restart requires a strip or fan topology",
+            ));
+        }
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
     pub fn create_graphics_pipeline(
         device: &Rc<Device>,
-        render_pass: vk::RenderPass,
+        target: PipelineTarget,
         pipeline_layout: vk::PipelineLayout,
-        msaa_samples: vk::SampleCountFlags,
+        multisample_state: MultisampleState,
         shaders: &[Shader],
+        primitive_state: PrimitiveState,
+        depth_state: DepthState,
+        color_blend_states: &[ColorBlendState],
     ) -> Result<Vec<vk::Pipeline>, DeviceError> {
         profiling::scope!("create_graphics_pipeline");
+        Self::validate_shader_stages(device, shaders)?;
+        Self::validate_primitive_state(Shader::combined_stages(shaders), primitive_state)?;
+        Self::validate_depth_bounds_state(device, depth_state.bounds)?;
+        Self::validate_viewport_count(device, primitive_state.viewport_count)?;
+        Self::validate_polygon_mode(device, primitive_state.polygon_mode)?;
+        Self::validate_color_blend_states(device, color_blend_states)?;
+        Self::validate_target(device, target, color_blend_states)?;
 
+        // 特化信息需要在创建管线期间保持有效
+        let specializations = shaders
+            .iter()
+            .map(|shader| shader.specialization().map(|s| s.raw()))
+            .collect::<Vec<_>>();
         let shader_stages = shaders
             .iter()
-            .map(|shader| {
-                vk::PipelineShaderStageCreateInfo::builder()
+            .zip(specializations.iter())
+            .map(|(shader, specialization)| {
+                let mut builder = vk::PipelineShaderStageCreateInfo::builder()
                     .module(shader.shader_module())
                     .name(shader.name())
-                    .stage(shader.stage())
-                    .build()
+                    .stage(shader.stage());
+                if let Some(specialization) = specialization {
+                    builder = builder.specialization_info(specialization);
+                }
+                builder.build()
             })
             .collect::<Vec<_>>();
 
         let shader_stages = &shader_stages;
 
-        let binding_descriptions = Vertex3D::get_binding_descriptions();
-        let attribute_descriptions = Vertex3D::get_attribute_descriptions();
+        let (binding_descriptions, attribute_descriptions) = if primitive_state.vertex_buffer {
+            (
+                Vertex3D::get_binding_descriptions(),
+                Vertex3D::get_attribute_descriptions(),
+            )
+        } else {
+            (Vec::new(), Vec::new())
+        };
+        Self::validate_vertex_attributes(device, &attribute_descriptions)?;
         let vertex_input_state_create_info = vk::PipelineVertexInputStateCreateInfo::builder()
             .vertex_binding_descriptions(&binding_descriptions)
             .vertex_attribute_descriptions(&attribute_descriptions);
@@ -86,12 +676,16 @@ impl Pipeline {
             // you to perform optimizations like reusing vertices. If you set the `primitive_restart_enable`
             // member to true, then it's possible to break up lines and triangles in the STRIP
             // topology modes by using a special index of 0xFFFF or 0xFFFFFFFF.
-            .primitive_restart_enable(false)
-            .topology(vk::PrimitiveTopology::TRIANGLE_LIST);
+            .primitive_restart_enable(primitive_state.primitive_restart)
+            .topology(primitive_state.topology);
+
+        let tessellation_state_create_info = vk::PipelineTessellationStateCreateInfo::builder()
+            .patch_control_points(primitive_state.patch_control_points);
 
+        // 每个视口对应一个裁剪矩形，两者数量必须相同
         let viewport_state_create_info = vk::PipelineViewportStateCreateInfo::builder()
-            .scissor_count(1)
-            .viewport_count(1);
+            .scissor_count(primitive_state.viewport_count)
+            .viewport_count(primitive_state.viewport_count);
 
         let rasterization_state_create_info = vk::PipelineRasterizationStateCreateInfo::builder()
             // If depth_clamp_enable is set to true, then fragments that are beyond the near and far
@@ -102,46 +696,42 @@ impl Pipeline {
             // rasterizer stage. This basically disables any output to the framebuffer.
             .rasterizer_discard_enable(false)
             // Using any mode other than fill requires enabling a GPU feature.
-            .polygon_mode(vk::PolygonMode::FILL)
+            .polygon_mode(primitive_state.polygon_mode)
             .line_width(1.0)
-            .cull_mode(vk::CullModeFlags::BACK)
-            .front_face(vk::FrontFace::COUNTER_CLOCKWISE)
+            .cull_mode(primitive_state.cull_mode)
+            .front_face(primitive_state.effective_front_face().into())
             // 光栅化器可以通过添加一个常数值或根据片段的斜率偏置它们来改变深度值。这有时用于阴影映射，但我们不会使用它。
             .depth_bias_enable(false);
 
-        let multisample_state_create_info = vk::PipelineMultisampleStateCreateInfo::builder()
+        let sample_mask = multisample_state.sample_mask_words();
+        let mut multisample_state_create_info = vk::PipelineMultisampleStateCreateInfo::builder()
             // Enable sample shading in the pipeline.
             .sample_shading_enable(true)
             .min_sample_shading(0.2)
-            .rasterization_samples(msaa_samples);
-
-        // let stencil_state = vk::StencilOpState {
-        //     fail_op: vk::StencilOp::KEEP,
-        //     pass_op: vk::StencilOp::KEEP,
-        //     depth_fail_op: vk::StencilOp::KEEP,
-        //     compare_op: vk::CompareOp::ALWAYS,
-        //     compare_mask: 0,
-        //     write_mask: 0,
-        //     reference: 0,
-        // };
+            .rasterization_samples(multisample_state.samples);
+        if let Some(sample_mask) = &sample_mask {
+            multisample_state_create_info = multisample_state_create_info.sample_mask(sample_mask);
+        }
+
+        let stencil_op_state = depth_state.stencil.raw();
 
         let depth_stencil_state_create_info = vk::PipelineDepthStencilStateCreateInfo::builder()
             // depth_test_enable 字段指定是否应将新片段的深度与深度缓冲区进行比较，看它们是否应被丢弃。
-            .depth_test_enable(true)
+            .depth_test_enable(depth_state.test_enable)
             // depth_write_enable 字段指定是否应将通过深度测试的新片段的深度实际写入深度缓冲区。
-            .depth_write_enable(true)
+            .depth_write_enable(depth_state.write_enable)
             // depth_compare_op 字段指定了为保留或丢弃片段所进行的比较。我们坚持较低的深度 = 较近的惯例，所以新片段的深度应该较小。
-            .depth_compare_op(vk::CompareOp::LESS)
+            .depth_compare_op(depth_state.compare_op)
             // depth_bounds_test_enable、min_depth_bounds 和 max_depth_bounds 字段用于可选的深度边界测试。
-            // 基本上，这允许你只保留落在指定深度范围内的片段。我们将不会使用这个功能。
-            .depth_bounds_test_enable(false)
-            .min_depth_bounds(0.0) // Optional.
-            .max_depth_bounds(1.0) // Optional.
+            // 基本上，这允许你只保留落在指定深度范围内的片段。需要开启 depth_bounds 特性。
+            .depth_bounds_test_enable(depth_state.bounds.test_enable)
+            .min_depth_bounds(depth_state.bounds.min_depth_bounds)
+            .max_depth_bounds(depth_state.bounds.max_depth_bounds)
             // 最后三个字段配置了模板缓冲区的操作，
             // 如果你想使用这些操作，那么你必须确保深度 / 模板图像的格式包含一个模板组件。
-            .stencil_test_enable(false)
-            // .front(/* vk::StencilOpState */) // Optional.
-            // .back(/* vk::StencilOpState */); // Optional.
+            .stencil_test_enable(depth_state.stencil.test_enable)
+            .front(stencil_op_state)
+            .back(stencil_op_state)
             .build();
 
         // pseudocode:
@@ -156,29 +746,28 @@ impl Pipeline {
         //
         // final_color = final_color & color_write_mask;
 
-        let color_blend_attachment_state = vk::PipelineColorBlendAttachmentState::builder()
-            .color_write_mask(vk::ColorComponentFlags::RGBA)
-            .blend_enable(true)
-            .src_color_blend_factor(vk::BlendFactor::SRC_ALPHA)
-            .dst_color_blend_factor(vk::BlendFactor::ONE_MINUS_SRC_ALPHA)
-            .color_blend_op(vk::BlendOp::ADD)
-            .src_alpha_blend_factor(vk::BlendFactor::ONE)
-            .dst_alpha_blend_factor(vk::BlendFactor::ZERO)
-            .alpha_blend_op(vk::BlendOp::ADD)
-            .build();
-
-        let color_blend_attachment_states = &[color_blend_attachment_state];
+        // 每个颜色附件一个混合状态，数量必须与子通道的颜色附件数量相同
+        let color_blend_attachment_states = color_blend_states
+            .iter()
+            .map(ColorBlendState::raw)
+            .collect::<Vec<_>>();
         let color_blend_state_create_info = vk::PipelineColorBlendStateCreateInfo::builder()
             .logic_op_enable(false)
             .logic_op(vk::LogicOp::COPY)
-            .attachments(color_blend_attachment_states)
+            .attachments(&color_blend_attachment_states)
             .blend_constants([0.0, 0.0, 0.0, 0.0]);
 
-        let dynamic_states = [vk::DynamicState::VIEWPORT, vk::DynamicState::SCISSOR];
+        let mut dynamic_states = vec![vk::DynamicState::VIEWPORT, vk::DynamicState::SCISSOR];
+        if depth_state.bounds.dynamic {
+            dynamic_states.push(vk::DynamicState::DEPTH_BOUNDS);
+        }
+        if depth_state.stencil.dynamic_reference {
+            dynamic_states.push(vk::DynamicState::STENCIL_REFERENCE);
+        }
         let dynamic_state_create_info =
             vk::PipelineDynamicStateCreateInfo::builder().dynamic_states(&dynamic_states);
 
-        let graphic_pipeline_create_info = vk::GraphicsPipelineCreateInfo::builder()
+        let mut graphic_pipeline_create_info = vk::GraphicsPipelineCreateInfo::builder()
             .stages(&shader_stages)
             .vertex_input_state(&vertex_input_state_create_info)
             .input_assembly_state(&vertex_input_assembly_state_info)
@@ -189,9 +778,30 @@ impl Pipeline {
             .color_blend_state(&color_blend_state_create_info)
             .dynamic_state(&dynamic_state_create_info)
             .layout(pipeline_layout)
-            .render_pass(render_pass)
-            .subpass(0)
-            .build();
+            .subpass(0);
+        // 格式数组需要在创建管线期间保持有效
+        let mut rendering_create_info = match target {
+            PipelineTarget::RenderPass(render_pass) => {
+                graphic_pipeline_create_info =
+                    graphic_pipeline_create_info.render_pass(render_pass);
+                None
+            }
+            PipelineTarget::DynamicRendering(formats) => Some(
+                vk::PipelineRenderingCreateInfo::builder()
+                    .color_attachment_formats(&formats.color_formats)
+                    .depth_attachment_format(formats.depth_format)
+                    .stencil_attachment_format(formats.stencil_format),
+            ),
+        };
+        if let Some(rendering_create_info) = rendering_create_info.as_mut() {
+            graphic_pipeline_create_info =
+                graphic_pipeline_create_info.push_next(rendering_create_info);
+        }
+        if primitive_state.topology == vk::PrimitiveTopology::PATCH_LIST {
+            graphic_pipeline_create_info =
+                graphic_pipeline_create_info.tessellation_state(&tessellation_state_create_info);
+        }
+        let graphic_pipeline_create_info = graphic_pipeline_create_info.build();
 
         let graphic_pipeline_create_infos = [graphic_pipeline_create_info];
         let pipelines = device.create_graphics_pipelines(&graphic_pipeline_create_infos)?;
@@ -206,3 +816,139 @@ impl Drop for Pipeline {
         log::debug!("Pipeline destroyed.");
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vulkan::shader::VERTEX_FRAGMENT;
+
+    #[test]
+    fn default_color_blend_state_keeps_the_old_factors() {
+        let state = ColorBlendState::default();
+        assert!(state.blend_enable);
+        assert_eq!(state.src_color_blend_factor, vk::BlendFactor::SRC_ALPHA);
+        assert_eq!(
+            state.dst_color_blend_factor,
+            vk::BlendFactor::ONE_MINUS_SRC_ALPHA
+        );
+        assert_eq!(state.src_alpha_blend_factor, vk::BlendFactor::ONE);
+        assert_eq!(state.dst_alpha_blend_factor, vk::BlendFactor::ZERO);
+    }
+
+    #[test]
+    fn primitive_restart_is_rejected_with_list_topology() {
+        let primitive_state = PrimitiveState::builder()
+            .topology(vk::PrimitiveTopology::TRIANGLE_LIST)
+            .primitive_restart(true)
+            .build();
+        assert!(Pipeline::validate_primitive_state(VERTEX_FRAGMENT, primitive_state).is_err());
+    }
+
+    #[test]
+    fn primitive_restart_is_accepted_with_strip_topology() {
+        let primitive_state = PrimitiveState::builder()
+            .topology(vk::PrimitiveTopology::TRIANGLE_STRIP)
+            .primitive_restart(true)
+            .build();
+        assert!(Pipeline::validate_primitive_state(VERTEX_FRAGMENT, primitive_state).is_ok());
+    }
+
+    #[test]
+    fn sample_mask_is_padded_to_the_sample_count() {
+        let multisample_state = MultisampleState::builder()
+            .samples(vk::SampleCountFlags::TYPE_4)
+            .sample_mask(Some(vec![]))
+            .build();
+        assert_eq!(multisample_state.sample_mask_words(), Some(vec![u32::MAX]));
+    }
+
+    #[test]
+    fn sample_mask_is_truncated_to_the_sample_count() {
+        let multisample_state = MultisampleState::builder()
+            .samples(vk::SampleCountFlags::TYPE_4)
+            .sample_mask(Some(vec![0b0101, 0b1111]))
+            .build();
+        assert_eq!(multisample_state.sample_mask_words(), Some(vec![0b0101]));
+        assert_eq!(
+            MultisampleState::from(vk::SampleCountFlags::TYPE_4).sample_mask_words(),
+            None
+        );
+    }
+
+    #[test]
+    fn flipped_front_face_is_the_opposite_winding() {
+        assert_eq!(FrontFace::CounterClockwise.flipped(), FrontFace::Clockwise);
+        assert_eq!(FrontFace::Clockwise.flipped(), FrontFace::CounterClockwise);
+    }
+
+    #[test]
+    fn front_face_is_flipped_only_for_y_flipped_viewports() {
+        let primitive_state = PrimitiveState::builder()
+            .front_face(FrontFace::CounterClockwise)
+            .build();
+        assert_eq!(
+            primitive_state.effective_front_face(),
+            FrontFace::CounterClockwise
+        );
+        let primitive_state = PrimitiveState::builder()
+            .front_face(FrontFace::CounterClockwise)
+            .y_flipped_viewport(true)
+            .build();
+        assert_eq!(primitive_state.effective_front_face(), FrontFace::Clockwise);
+    }
+
+    fn blend_factors(state: ColorBlendState) -> [vk::BlendFactor; 4] {
+        [
+            state.src_color_blend_factor,
+            state.dst_color_blend_factor,
+            state.src_alpha_blend_factor,
+            state.dst_alpha_blend_factor,
+        ]
+    }
+
+    #[test]
+    fn color_blend_presets_use_the_documented_factors() {
+        use vk::BlendFactor as F;
+
+        let opaque = ColorBlendState::opaque();
+        assert!(!opaque.blend_enable);
+        assert_eq!(blend_factors(opaque), [F::ONE, F::ZERO, F::ONE, F::ZERO]);
+
+        let alpha_blend = ColorBlendState::alpha_blend();
+        assert!(alpha_blend.blend_enable);
+        assert_eq!(
+            blend_factors(alpha_blend),
+            [
+                F::SRC_ALPHA,
+                F::ONE_MINUS_SRC_ALPHA,
+                F::ONE,
+                F::ONE_MINUS_SRC_ALPHA
+            ]
+        );
+
+        let premultiplied_alpha = ColorBlendState::premultiplied_alpha();
+        assert!(premultiplied_alpha.blend_enable);
+        assert_eq!(
+            blend_factors(premultiplied_alpha),
+            [
+                F::ONE,
+                F::ONE_MINUS_SRC_ALPHA,
+                F::ONE,
+                F::ONE_MINUS_SRC_ALPHA
+            ]
+        );
+
+        let additive = ColorBlendState::additive();
+        assert!(additive.blend_enable);
+        assert_eq!(
+            blend_factors(additive),
+            [F::SRC_ALPHA, F::ONE, F::ONE, F::ONE]
+        );
+
+        for state in [opaque, alpha_blend, premultiplied_alpha, additive] {
+            assert_eq!(state.color_blend_op, vk::BlendOp::ADD);
+            assert_eq!(state.alpha_blend_op, vk::BlendOp::ADD);
+            assert_eq!(state.color_write_mask, vk::ColorComponentFlags::RGBA);
+        }
+    }
+}
@@ -1,6 +1,7 @@
 use crate::vulkan::command_buffer::CommandBuffer;
-use crate::vulkan::conv;
+use crate::vulkan::conv::{self, ClearColor};
 use crate::vulkan::device::Device;
+use crate::vulkan::format::FormatExt;
 use crate::vulkan::render_pass::RenderPassState::{InRenderPass, Recording};
 use crate::{Color, DeviceError};
 use ash::vk;
@@ -13,6 +14,8 @@ pub struct RenderPass {
     state: RenderPassState,
     render_area: math::Rect2D,
     clear_values: Vec<vk::ClearValue>,
+    /// 清除值按附件索引对应，数量至少为最后一个 CLEAR 附件的索引加一
+    required_clear_value_count: usize,
 }
 
 pub enum RenderPassState {
@@ -29,11 +32,76 @@ pub enum RenderPassState {
     NotAllocated,
 }
 
+/// 深度/模板附件包含的分量，决定了附件格式以及深度和模板的 load/store 操作。
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum DepthAttachmentType {
+    Depth,
+    DepthStencil,
+    StencilOnly,
+}
+
+impl DepthAttachmentType {
+    pub fn has_depth(&self) -> bool {
+        matches!(self, Self::Depth | Self::DepthStencil)
+    }
+
+    pub fn has_stencil(&self) -> bool {
+        matches!(self, Self::DepthStencil | Self::StencilOnly)
+    }
+
+    /// Candidate formats in order of preference.
+    pub fn candidate_formats(&self) -> &'static [vk::Format] {
+        match self {
+            Self::Depth => &[
+                vk::Format::D32_SFLOAT,
+                vk::Format::D32_SFLOAT_S8_UINT,
+                vk::Format::D24_UNORM_S8_UINT,
+            ],
+            Self::DepthStencil => &[
+                vk::Format::D32_SFLOAT_S8_UINT,
+                vk::Format::D24_UNORM_S8_UINT,
+                vk::Format::D16_UNORM_S8_UINT,
+            ],
+            Self::StencilOnly => &[
+                vk::Format::S8_UINT,
+                vk::Format::D16_UNORM_S8_UINT,
+                vk::Format::D24_UNORM_S8_UINT,
+                vk::Format::D32_SFLOAT_S8_UINT,
+            ],
+        }
+    }
+
+    pub fn load_op(&self) -> vk::AttachmentLoadOp {
+        if self.has_depth() {
+            vk::AttachmentLoadOp::CLEAR
+        } else {
+            vk::AttachmentLoadOp::DONT_CARE
+        }
+    }
+
+    pub fn stencil_load_op(&self) -> vk::AttachmentLoadOp {
+        if self.has_stencil() {
+            vk::AttachmentLoadOp::CLEAR
+        } else {
+            vk::AttachmentLoadOp::DONT_CARE
+        }
+    }
+
+    pub fn stencil_store_op(&self) -> vk::AttachmentStoreOp {
+        if self.has_stencil() {
+            vk::AttachmentStoreOp::STORE
+        } else {
+            vk::AttachmentStoreOp::DONT_CARE
+        }
+    }
+}
+
 #[derive(Clone, TypedBuilder)]
 pub struct RenderPassDescriptor<'a> {
     pub device: &'a Rc<Device>,
     pub surface_format: vk::Format,
     pub depth_format: vk::Format,
+    pub depth_attachment_type: DepthAttachmentType,
     pub render_area: math::Rect2D,
     pub clear_color: Color,
     pub max_msaa_samples: vk::SampleCountFlags,
@@ -79,19 +147,24 @@ impl RenderPass {
         // 我们把 finalLayout 从 vk::ImageLayout::PRESENT_SRC_KHR 改为 vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL。
         // 这是因为多采样图像不能直接呈现。我们首先需要将它们解析为普通图像。这个要求并不适用于深度缓冲区，
         // 因为它不会在任何时候被呈现。因此，我们只需要为颜色添加一个新的附件，这是一个 resolve attachment。
+        // 只有深度或模板分量的格式在支持时使用各自的布局
+        let depth_stencil_layout = conv::depth_stencil_attachment_layout(
+            desc.depth_format.aspect_mask(),
+            desc.device.is_separate_depth_stencil_layouts_enabled(),
+        );
         let depth_stencil_attachment = vk::AttachmentDescription::builder()
             .format(desc.depth_format)
             .samples(desc.max_msaa_samples)
-            .load_op(vk::AttachmentLoadOp::CLEAR)
+            .load_op(desc.depth_attachment_type.load_op())
             .store_op(vk::AttachmentStoreOp::DONT_CARE)
-            .stencil_load_op(vk::AttachmentLoadOp::DONT_CARE)
-            .stencil_store_op(vk::AttachmentStoreOp::DONT_CARE)
+            .stencil_load_op(desc.depth_attachment_type.stencil_load_op())
+            .stencil_store_op(desc.depth_attachment_type.stencil_store_op())
             .initial_layout(vk::ImageLayout::UNDEFINED)
-            .final_layout(vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL)
+            .final_layout(depth_stencil_layout)
             .build();
         let depth_stencil_attachment_ref = vk::AttachmentReference::builder()
             .attachment(1)
-            .layout(vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL)
+            .layout(depth_stencil_layout)
             .build();
 
         // multisampling
@@ -114,6 +187,16 @@ impl RenderPass {
 
         let color_attachments = [color_attachment_ref];
         let color_resolve_attachments = [color_resolve_attachment_ref];
+        let attachments = &[
+            color_attachment,
+            depth_stencil_attachment,
+            color_resolve_attachment,
+        ];
+        Self::validate_resolve_attachments(
+            &color_attachments,
+            &color_resolve_attachments,
+            attachments.len(),
+        )?;
         let subpass = vk::SubpassDescription::builder()
             .pipeline_bind_point(vk::PipelineBindPoint::GRAPHICS)
             .color_attachments(&color_attachments)
@@ -142,12 +225,6 @@ impl RenderPass {
             )
             .build();
 
-        let attachments = &[
-            color_attachment,
-            depth_stencil_attachment,
-            color_resolve_attachment,
-        ];
-
         // don't do the `.subpasses(&[subpass])` + `build()` will cause the temporary array pointer
         // live shorter before the vulkan call  https://github.com/ash-rs/ash/issues/158
         let subpasses = [subpass];
@@ -167,9 +244,43 @@ impl RenderPass {
             state: InRenderPass,
             render_area: desc.render_area,
             clear_values,
+            required_clear_value_count: Self::required_clear_value_count(attachments),
         })
     }
 
+    /// `resolve_attachments` is either empty or corresponds one-to-one with `color_attachments`:
+    /// color attachment `i` is resolved into resolve attachment `i`, and
+    /// `vk::ATTACHMENT_UNUSED` skips the resolve of that color attachment.
+    pub fn validate_resolve_attachments(
+        color_attachments: &[vk::AttachmentReference],
+        resolve_attachments: &[vk::AttachmentReference],
+        attachment_count: usize,
+    ) -> Result<(), DeviceError> {
+        if resolve_attachments.is_empty() {
+            return Ok(());
+        }
+        if resolve_attachments.len() != color_attachments.len() {
+            log::error!(
+                "Subpass has {} resolve attachments but {} color attachments.",
+                resolve_attachments.len(),
+                color_attachments.len()
+            );
+            return Err(DeviceError::Other(
+                "Resolve attachments must be empty or match the color attachments one-to-one!",
+            ));
+        }
+        let out_of_range = resolve_attachments.iter().any(|reference| {
+            reference.attachment != vk::ATTACHMENT_UNUSED
+                && reference.attachment as usize >= attachment_count
+        });
+        if out_of_range {
+            return Err(DeviceError::Other(
+                "Resolve attachment index is out of range of the render pass attachments!",
+            ));
+        }
+        Ok(())
+    }
+
     pub fn new_imgui_render_pass(desc: &ImguiRenderPassDescriptor) -> Result<Self, DeviceError> {
         profiling::scope!("create_render_pass imgui");
 
@@ -215,15 +326,61 @@ impl RenderPass {
             device: desc.device.clone(),
             state: InRenderPass,
             render_area: desc.render_area,
-            clear_values: vec![vk::ClearValue {
-                color: vk::ClearColorValue {
-                    float32: [1.0, 1.0, 1.0, 1.0],
-                },
-            }],
+            clear_values: vec![conv::convert_clear_color_value(ClearColor::Float([
+                1.0, 1.0, 1.0, 1.0,
+            ]))],
+            required_clear_value_count: Self::required_clear_value_count(&attachment_descs),
         })
     }
 
-    pub fn begin(&mut self, command_buffer: &CommandBuffer, framebuffer: vk::Framebuffer) {
+    pub fn clear_values(&self) -> &[vk::ClearValue] {
+        &self.clear_values
+    }
+
+    /// Replaces the clear value of the attachment at `index`, used by the next `begin`.
+    pub fn set_clear_value(&mut self, index: usize, clear_value: vk::ClearValue) {
+        if index >= self.clear_values.len() {
+            self.clear_values
+                .resize(index + 1, vk::ClearValue::default());
+        }
+        self.clear_values[index] = clear_value;
+    }
+
+    /// Clear values are indexed by attachment, so every attachment up to the last one whose
+    /// color or stencil `load_op` is `CLEAR` needs one.
+    pub fn required_clear_value_count(attachments: &[vk::AttachmentDescription]) -> usize {
+        attachments
+            .iter()
+            .rposition(|attachment| {
+                attachment.load_op == vk::AttachmentLoadOp::CLEAR
+                    || attachment.stencil_load_op == vk::AttachmentLoadOp::CLEAR
+            })
+            .map_or(0, |index| index + 1)
+    }
+
+    fn validate_clear_value_count(
+        clear_value_count: usize,
+        required_clear_value_count: usize,
+    ) -> Result<(), DeviceError> {
+        if clear_value_count < required_clear_value_count {
+            log::error!(
+                "Render pass needs {} clear values for its CLEAR attachments, but {} are given.",
+                required_clear_value_count,
+                clear_value_count
+            );
+            return Err(DeviceError::Other(
+                "Not enough clear values for the attachments with CLEAR load op!",
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn begin(
+        &mut self,
+        command_buffer: &CommandBuffer,
+        framebuffer: vk::Framebuffer,
+    ) -> Result<(), DeviceError> {
+        Self::validate_clear_value_count(self.clear_values.len(), self.required_clear_value_count)?;
         let begin_info = vk::RenderPassBeginInfo::builder()
             .render_pass(self.raw)
             .framebuffer(framebuffer)
@@ -236,6 +393,7 @@ impl RenderPass {
             vk::SubpassContents::INLINE,
         );
         self.state = InRenderPass;
+        Ok(())
     }
 
     pub fn end(&mut self, command_buffer: &CommandBuffer) {
@@ -250,3 +408,87 @@ impl Drop for RenderPass {
         log::debug!("Render Pass destroyed.");
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reference(attachment: u32) -> vk::AttachmentReference {
+        vk::AttachmentReference {
+            attachment,
+            layout: vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+        }
+    }
+
+    #[test]
+    fn resolve_attachments_must_match_color_attachments() {
+        let color_attachments = [reference(0), reference(1)];
+        assert!(
+            RenderPass::validate_resolve_attachments(&color_attachments, &[reference(2)], 3)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn one_to_one_resolve_attachments_are_valid() {
+        let color_attachments = [reference(0), reference(1)];
+        let resolve_attachments = [reference(2), reference(vk::ATTACHMENT_UNUSED)];
+        assert!(RenderPass::validate_resolve_attachments(
+            &color_attachments,
+            &resolve_attachments,
+            3
+        )
+        .is_ok());
+        assert!(RenderPass::validate_resolve_attachments(&color_attachments, &[], 3).is_ok());
+    }
+
+    fn attachment(
+        load_op: vk::AttachmentLoadOp,
+        stencil_load_op: vk::AttachmentLoadOp,
+    ) -> vk::AttachmentDescription {
+        vk::AttachmentDescription {
+            load_op,
+            stencil_load_op,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn clear_attachment_without_clear_value_is_rejected() {
+        let attachments = [
+            attachment(vk::AttachmentLoadOp::LOAD, vk::AttachmentLoadOp::DONT_CARE),
+            attachment(vk::AttachmentLoadOp::DONT_CARE, vk::AttachmentLoadOp::CLEAR),
+            attachment(vk::AttachmentLoadOp::LOAD, vk::AttachmentLoadOp::DONT_CARE),
+        ];
+        let required = RenderPass::required_clear_value_count(&attachments);
+        assert_eq!(required, 2);
+        assert!(RenderPass::validate_clear_value_count(1, required).is_err());
+        assert!(RenderPass::validate_clear_value_count(2, required).is_ok());
+    }
+
+    #[test]
+    fn no_clear_attachments_need_no_clear_values() {
+        let attachments = [attachment(
+            vk::AttachmentLoadOp::LOAD,
+            vk::AttachmentLoadOp::DONT_CARE,
+        )];
+        assert_eq!(RenderPass::required_clear_value_count(&attachments), 0);
+        assert!(RenderPass::validate_clear_value_count(0, 0).is_ok());
+    }
+
+    #[test]
+    fn only_stencil_attachments_clear_stencil() {
+        assert_eq!(
+            DepthAttachmentType::DepthStencil.stencil_load_op(),
+            vk::AttachmentLoadOp::CLEAR
+        );
+        assert_eq!(
+            DepthAttachmentType::Depth.stencil_load_op(),
+            vk::AttachmentLoadOp::DONT_CARE
+        );
+        assert_eq!(
+            DepthAttachmentType::StencilOnly.load_op(),
+            vk::AttachmentLoadOp::DONT_CARE
+        );
+    }
+}
@@ -1,3 +1,4 @@
+use std::path::Path;
 use std::rc::Rc;
 use std::time::Instant;
 
@@ -12,23 +13,54 @@ use eureka_imgui::gui::GuiContext;
 use math::vec2;
 
 use crate::gui::GuiState;
-use crate::vulkan::adapter::Adapter;
+use crate::vulkan::adapter::{Adapter, AdapterSelector, HeapBudget};
+use crate::vulkan::capture::{save_screenshot, ScreenshotDescriptor};
+use crate::vulkan::command_buffer::CommandBuffer;
 use crate::vulkan::command_buffer_allocator::CommandBufferAllocator;
 use crate::vulkan::debug::DebugUtils;
 use crate::vulkan::descriptor_set_allocator::DescriptorSetAllocator;
+use crate::vulkan::fence::Fence;
 use crate::vulkan::imgui::{ImguiRenderer, ImguiRendererDescriptor};
 use crate::vulkan::model::{Model, ModelDescriptor};
+use crate::vulkan::semaphore::Semaphore;
 use crate::vulkan::swapchain::SwapchainDescriptor;
-use crate::vulkan::texture::{VulkanTexture, VulkanTextureFromPathDescriptor};
-use crate::vulkan::utils;
+use crate::vulkan::texture::{
+    VulkanTexture, VulkanTextureFromPathDescriptor, VulkanTextureFromPixelsDescriptor,
+};
+use crate::vulkan::upload::{UploadQueue, UploadQueueDescriptor};
+use crate::vulkan::{conv, utils};
 use crate::{
-    AdapterRequirements, InstanceDescriptor, QueueFamilyIndices, SurfaceError, MAX_FRAMES_IN_FLIGHT,
+    AdapterRequirements, Color, DeviceError, InstanceDescriptor, QueueFamilyIndices, SurfaceError,
+    MAX_FRAMES_IN_FLIGHT,
 };
 
 use super::device::Device;
 use super::instance::Instance;
-use super::surface::Surface;
-use super::swapchain::Swapchain;
+use super::surface::{Surface, SurfaceCapabilities};
+use super::swapchain::{AcquiredImage, Swapchain, SwapchainRecreated, DEFAULT_CLEAR_COLOR};
+
+/// Queue a transient command buffer is submitted to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QueueKind {
+    Graphics,
+    /// May be the graphics queue if the device has no dedicated transfer queue. Otherwise
+    /// `EXCLUSIVE` resources written on it must be released to the graphics family by the
+    /// recorded commands and acquired on the graphics queue before use, see `queue_family_index`.
+    Transfer,
+}
+
+/// Range of `VulkanRenderer::set_render_scale`.
+pub const RENDER_SCALE_RANGE: core::ops::RangeInclusive<f32> = 0.25..=2.0;
+
+/// What `end_frame` did with the swapchain.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PresentOutcome {
+    Presented,
+    /// The swapchain was out of date and has been recreated, the frame was not presented.
+    Recreated,
+    /// Presented, but the swapchain no longer matches the surface exactly, e.g. after a resize.
+    Suboptimal,
+}
 
 pub struct VulkanRenderer {
     adapter: Rc<Adapter>,
@@ -45,14 +77,30 @@ pub struct VulkanRenderer {
     image_available_semaphores: Vec<vk::Semaphore>,
     render_finished_semaphores: Vec<vk::Semaphore>,
     in_flight_fences: Vec<vk::Fence>,
+    /// Swapchain the last submission of each frame rendered to, `None` once it is known to be done.
+    in_flight_swapchains: Vec<Option<vk::SwapchainKHR>>,
     indices: QueueFamilyIndices,
     command_buffer_allocator: Rc<CommandBufferAllocator>,
+    /// Allocators backed by `TRANSIENT` pools for `run_transient`.
+    graphics_transient_allocator: CommandBufferAllocator,
+    transfer_transient_allocator: CommandBufferAllocator,
+    upload_queue: UploadQueue,
     model: Rc<Model>,
     mip_levels: u32,
     frame: usize,
+    last_presented_image: Option<u32>,
+    /// Image acquired by `begin_frame`, presented by `end_frame`.
+    acquired_image: Option<u32>,
+    pending_command_buffers: Vec<vk::CommandBuffer>,
     instant: Instant,
     imgui_renderer: ImguiRenderer,
     gui_state: GuiState,
+    reverse_z: bool,
+    /// Passed to every new swapchain, see `set_preferred_depth_format`.
+    preferred_depth_format: Option<vk::Format>,
+    clear_color: Color,
+    render_scale: f32,
+    depth_prepass: bool,
     misc: Misc,
 }
 
@@ -62,6 +110,15 @@ pub struct Misc {
 
 impl VulkanRenderer {
     pub fn new(window: &Window, gui_context: &mut ImguiContext) -> anyhow::Result<Self> {
+        Self::new_with_adapter_selector(window, gui_context, &Adapter::select_discrete_preferred)
+    }
+
+    /// `adapter_selector` chooses among the adapters meeting the requirements, see `Adapter::info`.
+    pub fn new_with_adapter_selector(
+        window: &Window,
+        gui_context: &mut ImguiContext,
+        adapter_selector: &AdapterSelector,
+    ) -> anyhow::Result<Self> {
         let instance_desc = InstanceDescriptor::builder()
             // .flags(crate::vulkan::instance::InstanceFlags::empty())
             // .debug_level_filter(log::LevelFilter::Info)
@@ -75,19 +132,22 @@ impl VulkanRenderer {
             .compute(true)
             .adapter_extension_names(vec![])
             .build();
-        let mut selected_adapter = None;
-        for adapter in adapters {
-            if unsafe { adapter.meet_requirements(&instance.raw(), &surface, &requirements) }
-                .is_ok()
-            {
-                selected_adapter = Some(adapter);
-                break;
-            }
+        let mut candidates = adapters
+            .into_iter()
+            .filter(|adapter| {
+                unsafe { adapter.meet_requirements(&instance, &surface, &requirements) }.is_ok()
+            })
+            .collect::<Vec<_>>();
+        if candidates.is_empty() {
+            panic!("Cannot find the require device.");
         }
-        let adapter = match selected_adapter {
-            None => panic!("Cannot find the require device."),
-            Some(adapter) => adapter,
-        };
+        let infos = candidates
+            .iter()
+            .map(|adapter| adapter.info().clone())
+            .collect::<Vec<_>>();
+        let index = Adapter::select(&infos, adapter_selector);
+        let adapter = candidates.swap_remove(index);
+        log::info!("Selected adapter: {}.", adapter.info().name);
 
         let adapter = Rc::new(adapter);
         let instance = Rc::new(instance);
@@ -107,7 +167,8 @@ impl VulkanRenderer {
             physical_device: adapter.raw(),
             debug_settings: Default::default(),
             // check https://stackoverflow.com/questions/73341075/rust-gpu-allocator-bufferdeviceaddress-must-be-enabbled
-            buffer_device_address: false,
+            // 与设备特性保持一致，否则分配的内存没有 DEVICE_ADDRESS 标志
+            buffer_device_address: device.is_buffer_device_address_enabled(),
         });
 
         let allocator = match allocator {
@@ -139,6 +200,26 @@ impl VulkanRenderer {
         let allocator = Rc::new(Mutex::new(allocator));
         let instant = Instant::now();
 
+        let transfer_family = indices.transfer_family.unwrap();
+        let graphics_transient_allocator = Self::create_transient_allocator(
+            &device,
+            indices.graphics_family.unwrap(),
+            graphics_queue,
+        )?;
+        let transfer_transient_allocator = Self::create_transient_allocator(
+            &device,
+            transfer_family,
+            device.get_device_queue(transfer_family, 0),
+        )?;
+        let upload_queue = UploadQueue::new(&UploadQueueDescriptor {
+            device: &device,
+            allocator: allocator.clone(),
+            queue_family_index: transfer_family,
+            queue: device.get_device_queue(transfer_family, 0),
+            owner_queue_family_index: indices.graphics_family.unwrap(),
+            owner_queue: graphics_queue,
+        })?;
+
         let model_desc = ModelDescriptor {
             file_name: "viking_room",
             device: &device,
@@ -167,6 +248,12 @@ impl VulkanRenderer {
             old_swapchain: None,
             instant,
             mip_levels,
+            preferred_depth_format: None,
+            old_depth_texture: None,
+            reverse_z: false,
+            clear_color: DEFAULT_CLEAR_COLOR,
+            render_scale: 1.0,
+            depth_prepass: false,
         };
 
         let swapchain = Swapchain::new(&swapchain_desc)?;
@@ -242,99 +329,506 @@ impl VulkanRenderer {
             image_available_semaphores,
             render_finished_semaphores,
             in_flight_fences,
+            in_flight_swapchains: vec![None; MAX_FRAMES_IN_FLIGHT],
             indices,
             command_buffer_allocator,
+            graphics_transient_allocator,
+            transfer_transient_allocator,
+            upload_queue,
             model,
             mip_levels,
             frame: 0,
+            last_presented_image: None,
+            acquired_image: None,
+            pending_command_buffers: Vec::new(),
             instant,
             imgui_renderer,
             gui_state: GuiState::new(
                 vec2(inner_size.width as f32, inner_size.height as f32),
                 Some(test_texture_id),
             ),
+            reverse_z: false,
+            preferred_depth_format: None,
+            clear_color: DEFAULT_CLEAR_COLOR,
+            render_scale: 1.0,
+            depth_prepass: false,
             misc: Misc { test_texture },
         })
     }
 
     pub fn render(&mut self, window: &Window, gui_context: &mut GuiContext) -> anyhow::Result<()> {
+        let Some(image_index) = self.begin_frame()? else {
+            return Ok(());
+        };
+
+        let swapchain = self.swapchain.as_mut().unwrap();
+        let command_buffer = swapchain.render(
+            self.frame,
+            image_index as usize,
+            window,
+            gui_context,
+            self.imgui_renderer.renderer_mut(),
+            &mut self.gui_state,
+            crate::gui::draw_imgui,
+        )?;
+        self.queue_command_buffer(command_buffer);
+        self.end_frame()?;
+        Ok(())
+    }
+
+    /// Waits for the current frame's previous submission and acquires the next swapchain image.
+    ///
+    /// Returns `None` if the swapchain is out of date, it is dropped and recreated by the next
+    /// `begin_frame`. Otherwise commands recorded for the image are queued with `queue_command_buffer`
+    /// and submitted by `end_frame`.
+    pub fn begin_frame(&mut self) -> anyhow::Result<Option<u32>> {
         if self.swapchain.is_none() {
-            self.recreate_swapchain(PhysicalSize {
-                width: self.extent.width,
-                height: self.extent.height,
-            })?;
+            self.recreate_swapchain(conv::convert_extent2d(self.extent))?;
         }
 
-        let in_flight_fence = self.in_flight_fences[self.frame];
-        let in_flight_fences = [in_flight_fence];
+        let in_flight_fences = [self.in_flight_fences[self.frame]];
         self.device
             .wait_for_fence(&in_flight_fences, true, u64::MAX)?;
+        self.upload_queue.collect_completed()?;
 
-        let swapchain = self.swapchain.as_mut().unwrap();
-        let result =
-            swapchain.acquire_next_image(u64::MAX, self.image_available_semaphores[self.frame]);
-        let image_index = match result {
-            Ok((image_index, _)) => image_index,
+        let image_index = match self.acquire_next_image(u64::MAX) {
+            Ok(acquired) => acquired.index,
             Err(SurfaceError::OutOfDate) => {
+                // 交换链的命令缓冲和帧缓冲随之释放，不能仍在执行
+                self.device.wait_idle()?;
                 self.swapchain = None;
-                return Ok(());
+                return Ok(None);
             }
             Err(e) => panic!("failed to acquire_next_image. Err: {}", e),
         };
         self.device.reset_fence(&in_flight_fences)?;
+        // 提交失败时 fence 不会被 signal，重建交换链时不能等待它
+        self.in_flight_swapchains[self.frame] = None;
+        self.acquired_image = Some(image_index);
+        Ok(Some(image_index))
+    }
 
-        let command_buffer = swapchain.render(
-            image_index as usize,
-            window,
-            gui_context,
-            self.imgui_renderer.renderer_mut(),
-            &mut self.gui_state,
-            crate::gui::draw_imgui,
-        )?;
-
-        let wait_stages = &[vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT];
-
-        let wait_semaphores = &[self.image_available_semaphores[self.frame]];
+    /// Submits the queued command buffers, presents the image acquired by `begin_frame` and
+    /// advances to the next frame in flight.
+    ///
+    /// An out of date swapchain is recreated right away, a suboptimal one is left to the caller.
+    pub fn end_frame(&mut self) -> anyhow::Result<PresentOutcome> {
+        let image_index = self
+            .acquired_image
+            .take()
+            .ok_or_else(|| anyhow::anyhow!("end_frame called without an acquired image"))?;
+        let in_flight_fence = self.in_flight_fences[self.frame];
+        let wait = &[(
+            self.image_available_semaphores[self.frame],
+            vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+        )];
         let signal_semaphores = &[self.render_finished_semaphores[self.frame]];
 
-        let command_buffers = [command_buffer];
-        let submit_info = vk::SubmitInfo::builder()
-            .wait_semaphores(wait_semaphores)
-            .wait_dst_stage_mask(wait_stages)
-            .command_buffers(&command_buffers)
-            .signal_semaphores(signal_semaphores)
-            .build();
-
-        self.device
-            .queue_submit(self.graphics_queue, &[submit_info], in_flight_fence)?;
+        // 额外的命令缓冲在帧命令缓冲之前按顺序执行
+        let result = Self::submit_command_buffers(
+            &self.device,
+            self.graphics_queue,
+            &self.pending_command_buffers,
+            wait,
+            signal_semaphores,
+            in_flight_fence,
+        );
+        self.pending_command_buffers.clear();
+        result?;
+        let swapchain = self.swapchain.as_mut().unwrap();
+        self.in_flight_swapchains[self.frame] = Some(swapchain.raw());
         swapchain.update_submitted_command_buffer(self.frame);
 
-        let swapchains = [swapchain.raw()];
-        let image_indices = [image_index];
-        let present_info = vk::PresentInfoKHR::builder()
-            .wait_semaphores(signal_semaphores)
-            .swapchains(&swapchains)
-            .image_indices(&image_indices);
-
-        match swapchain.queue_present(&present_info) {
-            Ok(suboptimal) => suboptimal,
+        let outcome = match self.queue_present(image_index, signal_semaphores) {
+            Ok(false) => PresentOutcome::Presented,
+            Ok(true) => PresentOutcome::Suboptimal,
             Err(SurfaceError::OutOfDate) => {
-                self.swapchain = None;
-                return Ok(());
+                self.recreate_swapchain(conv::convert_extent2d(self.extent))?;
+                PresentOutcome::Recreated
             }
-            Err(e) => panic!("failed to acquire_next_image. Err: {}", e),
+            Err(e) => panic!("failed to queue_present. Err: {}", e),
         };
         self.frame = (self.frame + 1) % MAX_FRAMES_IN_FLIGHT;
+        Ok(outcome)
+    }
+
+    fn create_transient_allocator(
+        device: &Rc<Device>,
+        queue_family_index: u32,
+        queue: vk::Queue,
+    ) -> Result<CommandBufferAllocator, DeviceError> {
+        let create_info = vk::CommandPoolCreateInfo::builder()
+            .queue_family_index(queue_family_index)
+            .flags(vk::CommandPoolCreateFlags::TRANSIENT)
+            .build();
+        let command_pool = device.create_command_pool(&create_info)?;
+        Ok(CommandBufferAllocator::new(device, command_pool, queue))
+    }
+
+    pub fn queue_family_index(&self, queue_kind: QueueKind) -> u32 {
+        match queue_kind {
+            QueueKind::Graphics => self.indices.graphics_family.unwrap(),
+            QueueKind::Transfer => self.indices.transfer_family.unwrap(),
+        }
+    }
+
+    /// Records a throwaway command buffer with `record`, submits it to the queue of `queue_kind`
+    /// and blocks until it has finished executing.
+    ///
+    /// Nothing is transferred between the queue families, see `QueueKind::Transfer`.
+    pub fn run_transient<F>(&self, queue_kind: QueueKind, record: F) -> Result<(), DeviceError>
+    where
+        F: FnOnce(&Rc<Device>, &CommandBuffer),
+    {
+        let allocator = match queue_kind {
+            QueueKind::Graphics => &self.graphics_transient_allocator,
+            QueueKind::Transfer => &self.transfer_transient_allocator,
+        };
+        allocator.run_transient(record)
+    }
+
+    pub fn upload_queue(&mut self) -> &mut UploadQueue {
+        &mut self.upload_queue
+    }
+
+    pub fn extent(&self) -> vk::Extent2D {
+        self.extent
+    }
+
+    /// See `Swapchain::image_count`, 0 while there is no swapchain.
+    pub fn swapchain_image_count(&self) -> u32 {
+        self.swapchain
+            .as_ref()
+            .map_or(0, |swapchain| swapchain.image_count())
+    }
+
+    /// Viewport of the current swapchain, flipped on Y.
+    pub fn viewport(&self) -> Option<math::Rect2D> {
+        self.swapchain
+            .as_ref()
+            .map(|swapchain| swapchain.viewport())
+    }
+
+    /// See `Swapchain::cmd_set_full_viewport_scissor`, does nothing while there is no swapchain.
+    pub fn cmd_set_full_viewport_scissor(&self, command_buffer: vk::CommandBuffer) {
+        if let Some(swapchain) = &self.swapchain {
+            swapchain.cmd_set_full_viewport_scissor(command_buffer);
+        }
+    }
+
+    /// Semaphore signaled by `acquire_next_image` in the current frame.
+    pub fn image_available_semaphore(&self) -> vk::Semaphore {
+        self.image_available_semaphores[self.frame]
+    }
+
+    /// Semaphore the current frame's rendering signals and presentation waits on.
+    pub fn render_finished_semaphore(&self) -> vk::Semaphore {
+        self.render_finished_semaphores[self.frame]
+    }
+
+    /// Acquires the next swapchain image, `image_available_semaphore` is signaled once it's ready.
+    pub fn acquire_next_image(&mut self, timeout: u64) -> Result<AcquiredImage, SurfaceError> {
+        let swapchain = self.swapchain.as_ref().ok_or(SurfaceError::OutOfDate)?;
+        swapchain.acquire_next_image(timeout, self.image_available_semaphores[self.frame])
+    }
+
+    /// Presents `image_index` after `wait_semaphores` are signaled, returns whether the swapchain is suboptimal.
+    pub fn queue_present(
+        &mut self,
+        image_index: u32,
+        wait_semaphores: &[vk::Semaphore],
+    ) -> Result<bool, SurfaceError> {
+        let swapchain = self.swapchain.as_ref().ok_or(SurfaceError::OutOfDate)?;
+        let suboptimal = swapchain.present(image_index, wait_semaphores)?;
+        self.last_presented_image = Some(image_index);
+        Ok(suboptimal)
+    }
+
+    /// Queues a recorded command buffer (e.g. a shadow pass) to be submitted together with, and
+    /// before, the next frame's command buffer. It must stay alive until that frame's fence signals.
+    pub fn queue_command_buffer(&mut self, command_buffer: vk::CommandBuffer) {
+        self.pending_command_buffers.push(command_buffer);
+    }
+
+    pub fn create_fence(&self, signaled: bool) -> Result<Fence, DeviceError> {
+        Fence::new(&self.device, signaled)
+    }
+
+    pub fn create_semaphore(&self) -> Result<Semaphore, DeviceError> {
+        Semaphore::new(&self.device)
+    }
+
+    /// Submits the command buffers in order with a single `vkQueueSubmit` on the graphics queue.
+    ///
+    /// Waits on `wait` at the given stages, signals `signal` and, if given, `fence` once the command
+    /// buffers have completed. Semaphores and the fence must stay alive until then.
+    pub fn submit(
+        &self,
+        command_buffers: &[vk::CommandBuffer],
+        wait: &[(vk::Semaphore, vk::PipelineStageFlags)],
+        signal: &[vk::Semaphore],
+        fence: Option<&Fence>,
+    ) -> Result<(), DeviceError> {
+        Self::submit_command_buffers(
+            &self.device,
+            self.graphics_queue,
+            command_buffers,
+            wait,
+            signal,
+            fence.map_or(vk::Fence::null(), Fence::raw),
+        )
+    }
+
+    fn submit_command_buffers(
+        device: &Device,
+        queue: vk::Queue,
+        command_buffers: &[vk::CommandBuffer],
+        wait: &[(vk::Semaphore, vk::PipelineStageFlags)],
+        signal: &[vk::Semaphore],
+        fence: vk::Fence,
+    ) -> Result<(), DeviceError> {
+        let (wait_semaphores, wait_stages): (Vec<_>, Vec<_>) = wait.iter().copied().unzip();
+        let submit_info = vk::SubmitInfo::builder()
+            .wait_semaphores(&wait_semaphores)
+            .wait_dst_stage_mask(&wait_stages)
+            .command_buffers(command_buffers)
+            .signal_semaphores(signal)
+            .build();
+        device.queue_submit(queue, &[submit_info], fence)
+    }
+
+    /// Saves the last presented swapchain image as a PNG.
+    pub fn save_screenshot<P: AsRef<Path>>(&self, path: P) -> anyhow::Result<()> {
+        let (swapchain, image_index) = match (&self.swapchain, self.last_presented_image) {
+            (Some(swapchain), Some(image_index)) => (swapchain, image_index),
+            _ => anyhow::bail!("No presented swapchain image to capture"),
+        };
+        self.device.wait_idle()?;
+        let desc = ScreenshotDescriptor::builder()
+            .device(&self.device)
+            .allocator(self.allocator.clone())
+            .command_buffer_allocator(&self.command_buffer_allocator)
+            .image(swapchain.images()[image_index as usize])
+            .image_layout(vk::ImageLayout::PRESENT_SRC_KHR)
+            .extent(swapchain.extent())
+            .format(swapchain.surface_format().format)
+            .path(path.as_ref())
+            .build();
+        save_screenshot(&desc)
+    }
+
+    /// Uploads `bytes` into a sampled texture with its own view and sampler, e.g. for
+    /// `ImguiRenderer::add_texture`. Blocks until the upload is done.
+    pub fn create_texture_from_pixels(
+        &self,
+        format: vk::Format,
+        extent: [u32; 2],
+        bytes: &[u8],
+        enable_mip_levels: bool,
+    ) -> Result<VulkanTexture, DeviceError> {
+        let desc = VulkanTextureFromPixelsDescriptor::builder()
+            .adapter(&self.adapter)
+            .instance(&self.instance)
+            .device(&self.device)
+            .allocator(self.allocator.clone())
+            .command_buffer_allocator(&self.command_buffer_allocator)
+            .format(format)
+            .extent(extent)
+            .bytes(bytes)
+            .enable_mip_levels(enable_mip_levels)
+            .build();
+        VulkanTexture::new_from_pixels(desc)
+    }
+
+    /// Waits until the GPU has finished all submitted work, call it before tearing down resources.
+    pub fn wait_idle(&self) -> Result<(), DeviceError> {
+        self.device.wait_idle()
+    }
+
+    /// Waits for the frames in flight submitted by `end_frame`, e.g. before replacing a pipeline
+    /// or texture their command buffers use. Unlike `wait_idle`, other queue work such as
+    /// `submit` or uploads is not waited for.
+    pub fn drain_frames(&self) -> Result<(), DeviceError> {
+        // 只等待已提交的帧，begin_frame 之后尚未提交的帧的 fence 已被重置，永远不会 signal
+        let fences = self
+            .in_flight_swapchains
+            .iter()
+            .zip(self.in_flight_fences.iter())
+            .filter(|(swapchain, _)| swapchain.is_some())
+            .map(|(_, &fence)| fence)
+            .collect::<Vec<_>>();
+        if fences.is_empty() {
+            return Ok(());
+        }
+        self.device.wait_for_fence(&fences, true, u64::MAX)
+    }
+
+    /// See `Adapter::memory_budget`.
+    pub fn memory_budget(&self) -> Vec<HeapBudget> {
+        self.adapter.memory_budget(&self.instance, &self.device)
+    }
+
+    /// See `Surface::capabilities`.
+    pub fn surface_capabilities(&self) -> Result<SurfaceCapabilities, SurfaceError> {
+        self.surface.capabilities(self.adapter.raw())
+    }
+
+    pub fn is_reverse_z(&self) -> bool {
+        self.reverse_z
+    }
+
+    pub fn clear_color(&self) -> Color {
+        self.clear_color
+    }
+
+    /// Background color of the main pass, used from the next frame on.
+    pub fn set_clear_color(&mut self, clear_color: Color) {
+        self.clear_color = clear_color;
+        if let Some(swapchain) = self.swapchain.as_mut() {
+            swapchain.set_clear_color(clear_color);
+        }
+    }
+
+    /// Rebuilds the swapchain pipeline and render pass with the reverse-Z depth setup, see
+    /// `DepthState::reverse_z`.
+    pub fn set_reverse_z(
+        &mut self,
+        reverse_z: bool,
+        inner_size: PhysicalSize<u32>,
+    ) -> anyhow::Result<()> {
+        if self.reverse_z != reverse_z {
+            self.reverse_z = reverse_z;
+            self.recreate_swapchain(inner_size)?;
+        }
+        Ok(())
+    }
+
+    pub fn preferred_depth_format(&self) -> Option<vk::Format> {
+        self.preferred_depth_format
+    }
+
+    /// Depth format tried first for the depth attachment, falls back to a supported one if it
+    /// can't be used. Rebuilds the swapchain.
+    pub fn set_preferred_depth_format(
+        &mut self,
+        preferred_depth_format: Option<vk::Format>,
+        inner_size: PhysicalSize<u32>,
+    ) -> anyhow::Result<()> {
+        if self.preferred_depth_format != preferred_depth_format {
+            self.preferred_depth_format = preferred_depth_format;
+            self.recreate_swapchain(inner_size)?;
+        }
+        Ok(())
+    }
+
+    pub fn is_depth_prepass(&self) -> bool {
+        self.depth_prepass
+    }
+
+    /// Draws the scene depth-only before the main pass, which then shades each pixel once with an
+    /// `EQUAL` depth compare. Pays off when fragment shading is expensive, rebuilds the swapchain.
+    pub fn set_depth_prepass(
+        &mut self,
+        depth_prepass: bool,
+        inner_size: PhysicalSize<u32>,
+    ) -> anyhow::Result<()> {
+        if self.depth_prepass != depth_prepass {
+            self.depth_prepass = depth_prepass;
+            self.recreate_swapchain(inner_size)?;
+        }
+        Ok(())
+    }
+
+    pub fn render_scale(&self) -> f32 {
+        self.render_scale
+    }
+
+    /// Extent the main pass renders at, see `set_render_scale`.
+    pub fn render_extent(&self) -> vk::Extent2D {
+        self.swapchain
+            .as_ref()
+            .map_or(self.extent, |swapchain| swapchain.render_extent())
+    }
+
+    /// Renders the main pass at the window extent times `scale` and blits it to the swapchain image,
+    /// e.g. for dynamic resolution. `scale` is clamped to `RENDER_SCALE_RANGE`.
+    pub fn set_render_scale(&mut self, scale: f32) -> anyhow::Result<()> {
+        let clamped = scale.clamp(*RENDER_SCALE_RANGE.start(), *RENDER_SCALE_RANGE.end());
+        if clamped != scale {
+            log::warn!(
+                "Render scale {} is out of range {:?}, clamped to {}.",
+                scale,
+                RENDER_SCALE_RANGE,
+                clamped
+            );
+        }
+        if self.render_scale != clamped {
+            self.render_scale = clamped;
+            self.recreate_swapchain(conv::convert_extent2d(self.extent))?;
+        }
         Ok(())
     }
 
-    pub fn recreate_swapchain(&mut self, inner_size: PhysicalSize<u32>) -> anyhow::Result<()> {
-        self.device.wait_idle();
+    /// Waits until the device is idle, then replaces the swapchain.
+    pub fn recreate_swapchain(
+        &mut self,
+        inner_size: PhysicalSize<u32>,
+    ) -> anyhow::Result<SwapchainRecreated> {
+        self.device.wait_idle()?;
+        self.in_flight_swapchains.fill(None);
+        self.replace_swapchain(inner_size)
+    }
+
+    /// Like `recreate_swapchain`, but only waits for the frames whose last submission rendered to
+    /// the current swapchain, instead of stalling the whole device, which reduces resize stutter.
+    ///
+    /// The old swapchain is passed as `old_swapchain` so the presentation engine can hand over
+    /// seamlessly. Work submitted outside of `render`, e.g. through `submit`, is not waited for.
+    pub fn recreate_swapchain_smooth(
+        &mut self,
+        inner_size: PhysicalSize<u32>,
+    ) -> anyhow::Result<SwapchainRecreated> {
+        // 交换链已被丢弃时（例如 OutOfDate）无法区分，等待所有引用过交换链的帧
+        let old_swapchain = self.swapchain.as_ref().map(|swapchain| swapchain.raw());
+        let fences = self
+            .in_flight_swapchains
+            .iter()
+            .zip(self.in_flight_fences.iter())
+            .filter(|(swapchain, _)| match (swapchain, old_swapchain) {
+                (Some(swapchain), Some(old_swapchain)) => *swapchain == old_swapchain,
+                (Some(_), None) => true,
+                (None, _) => false,
+            })
+            .map(|(_, &fence)| fence)
+            .collect::<Vec<_>>();
+        if !fences.is_empty() {
+            self.device.wait_for_fence(&fences, true, u64::MAX)?;
+        }
+        log::debug!(
+            "Waited for {} of {} frames in flight before recreating the swapchain.",
+            fences.len(),
+            MAX_FRAMES_IN_FLIGHT
+        );
+        self.in_flight_swapchains.fill(None);
+        self.replace_swapchain(inner_size)
+    }
+
+    /// The frames in flight must no longer use the current swapchain.
+    fn replace_swapchain(
+        &mut self,
+        inner_size: PhysicalSize<u32>,
+    ) -> anyhow::Result<SwapchainRecreated> {
         log::debug!("======== Swapchain start recreate.========");
+        self.last_presented_image = None;
 
         let mut old_swapchain = None;
+        let mut old_depth_texture = None;
+        let mut old_format = None;
         if let Some(swapchain) = &self.swapchain {
-            old_swapchain = Some(swapchain.raw())
+            old_swapchain = Some(swapchain.raw());
+            old_format = Some(swapchain.surface_format());
+            // 尺寸没变时（比如多余的 resize 事件）复用深度图，避免重新分配
+            old_depth_texture = Some(swapchain.depth_texture().clone());
         }
         let swapchain_desc = SwapchainDescriptor {
             adapter: self.adapter.clone(),
@@ -353,22 +847,46 @@ impl VulkanRenderer {
             mip_levels: self.mip_levels,
             old_swapchain,
             instant: self.instant,
+            preferred_depth_format: self.preferred_depth_format,
+            old_depth_texture,
+            reverse_z: self.reverse_z,
+            clear_color: self.clear_color,
+            render_scale: self.render_scale,
+            depth_prepass: self.depth_prepass,
         };
 
         let swapchain = Swapchain::new(&swapchain_desc)?;
-        self.swapchain = Some(swapchain);
-        self.extent = vk::Extent2D {
-            width: inner_size.width,
-            height: inner_size.height,
+        let recreated = SwapchainRecreated {
+            old_format,
+            new_format: swapchain.surface_format(),
         };
+        if recreated.format_changed() {
+            // 格式相同时 render pass 兼容，只有格式变化才需要重建 imgui 管线
+            log::debug!(
+                "Swapchain format changed from {:?} to {:?}.",
+                recreated.old_format,
+                recreated.new_format
+            );
+            self.imgui_renderer
+                .renderer_mut()
+                .set_render_pass(swapchain.imgui_render_pass().raw())?;
+        }
+        // 交换链尺寸会被 surface capabilities 限制，不一定等于窗口尺寸
+        self.extent = swapchain.extent();
+        self.swapchain = Some(swapchain);
         log::debug!("======== Swapchain recreated.========");
-        Ok(())
+        Ok(recreated)
     }
 }
 
 impl Drop for VulkanRenderer {
     fn drop(&mut self) {
-        self.device.wait_idle();
+        if let Err(e) = self.device.wait_idle() {
+            log::error!(
+                "failed to wait device idle before destroy renderer. Err: {}",
+                e
+            );
+        }
         self.swapchain = None; // drop first
         self.image_available_semaphores
             .iter()
@@ -380,9 +898,14 @@ impl Drop for VulkanRenderer {
             .iter()
             .for_each(|s| self.device.destroy_fence(*s));
         self.device.destroy_command_pool(self.command_pool);
+        self.device
+            .destroy_command_pool(self.graphics_transient_allocator.command_pool());
+        self.device
+            .destroy_command_pool(self.transfer_transient_allocator.command_pool());
         if let Some(DebugUtils {
             extension,
             messenger,
+            ..
         }) = self.debug_utils.take()
         {
             unsafe {
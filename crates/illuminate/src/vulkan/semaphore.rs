@@ -0,0 +1,33 @@
+use alloc::rc::Rc;
+
+use ash::vk;
+
+use crate::vulkan::device::Device;
+use crate::DeviceError;
+
+/// A binary semaphore owned by the caller, e.g. to chain submits on the GPU.
+pub struct Semaphore {
+    raw: vk::Semaphore,
+    device: Rc<Device>,
+}
+
+impl Semaphore {
+    pub fn raw(&self) -> vk::Semaphore {
+        self.raw
+    }
+
+    pub fn new(device: &Rc<Device>) -> Result<Self, DeviceError> {
+        let raw = device.create_semaphore(&vk::SemaphoreCreateInfo::default())?;
+        Ok(Self {
+            raw,
+            device: device.clone(),
+        })
+    }
+}
+
+impl Drop for Semaphore {
+    fn drop(&mut self) {
+        self.device.destroy_semaphore(self.raw);
+        log::debug!("Semaphore destroyed.");
+    }
+}
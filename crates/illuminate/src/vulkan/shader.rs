@@ -3,7 +3,7 @@ use crate::{Label, ShaderError};
 use ash::vk;
 use math::{Vec3, Vertex3D};
 use spirq::ty::Type;
-use spirq::{EntryPoint, ReflectConfig, Variable};
+use spirq::{EntryPoint, ExecutionModel, ReflectConfig, Variable};
 use std::borrow::Cow;
 use std::ffi::CString;
 use std::mem::size_of;
@@ -15,12 +15,79 @@ pub enum ShaderStage {
     S,
 }
 
+/// Stages of the common vertex + fragment pipeline, for descriptor bindings and push constants
+/// shared by both. `vk::ShaderStageFlags::ALL_GRAPHICS` also covers tessellation and geometry.
+pub const VERTEX_FRAGMENT: vk::ShaderStageFlags = vk::ShaderStageFlags::from_raw(
+    vk::ShaderStageFlags::VERTEX.as_raw() | vk::ShaderStageFlags::FRAGMENT.as_raw(),
+);
+
 pub struct Shader {
     device: Rc<Device>,
     shader: vk::ShaderModule,
     entry_point: EntryPoint,
     name: CString,
     stage: vk::ShaderStageFlags,
+    specialization: Option<SpecializationInfo>,
+}
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// Scalar types of a specialization constant, written without padding. A `bool` is written as
+/// a 32-bit `VkBool32`, like `bool` constants in SPIR-V.
+pub trait SpecializationConstant: sealed::Sealed + Copy {
+    fn write_bytes(self, data: &mut Vec<u8>);
+}
+
+macro_rules! impl_specialization_constant {
+    ($($ty:ty),*) => {
+        $(
+            impl sealed::Sealed for $ty {}
+            impl SpecializationConstant for $ty {
+                fn write_bytes(self, data: &mut Vec<u8>) {
+                    data.extend_from_slice(&self.to_ne_bytes());
+                }
+            }
+        )*
+    };
+}
+
+impl_specialization_constant!(u32, i32, f32, u64, f64);
+
+impl sealed::Sealed for bool {}
+impl SpecializationConstant for bool {
+    fn write_bytes(self, data: &mut Vec<u8>) {
+        (if self { vk::TRUE } else { vk::FALSE }).write_bytes(data);
+    }
+}
+
+/// Constant values baked into the shader when the pipeline is created.
+#[derive(Clone, Debug, Default)]
+pub struct SpecializationInfo {
+    pub map_entries: Vec<vk::SpecializationMapEntry>,
+    pub data: Vec<u8>,
+}
+
+impl SpecializationInfo {
+    /// Appends the value of `layout(constant_id = constant_id)` to the data blob.
+    pub fn with_constant<T: SpecializationConstant>(mut self, constant_id: u32, value: T) -> Self {
+        let offset = self.data.len();
+        value.write_bytes(&mut self.data);
+        self.map_entries.push(vk::SpecializationMapEntry {
+            constant_id,
+            offset: offset as u32,
+            size: self.data.len() - offset,
+        });
+        self
+    }
+
+    pub fn raw(&self) -> vk::SpecializationInfo {
+        vk::SpecializationInfo::builder()
+            .map_entries(&self.map_entries)
+            .data(&self.data)
+            .build()
+    }
 }
 
 #[derive(Clone, TypedBuilder)]
@@ -29,6 +96,8 @@ pub struct ShaderDescriptor<'a> {
     pub device: &'a Rc<Device>,
     pub spv_bytes: &'a [u32],
     pub entry_name: &'a str,
+    #[builder(default)]
+    pub specialization: Option<&'a SpecializationInfo>,
 }
 
 pub trait ShaderPropertyInfo {
@@ -53,10 +122,23 @@ impl Shader {
         self.stage
     }
 
+    pub fn specialization(&self) -> Option<&SpecializationInfo> {
+        self.specialization.as_ref()
+    }
+
+    /// All stages used by `shaders`.
+    pub fn combined_stages(shaders: &[Shader]) -> vk::ShaderStageFlags {
+        shaders
+            .iter()
+            .fold(vk::ShaderStageFlags::empty(), |stages, shader| {
+                stages | shader.stage
+            })
+    }
+
     pub fn new(desc: &ShaderDescriptor, stage: vk::ShaderStageFlags) -> Result<Self, ShaderError> {
+        // 先检查入口点，避免名字写错时到创建管线才得到难以理解的错误
+        let entry_point = Self::reflect_entry_point(desc.entry_name, stage, desc.spv_bytes)?;
         let shader = Self::create_shader_module(desc.label, desc.device, desc.spv_bytes)?;
-
-        let entry_point = Self::reflect_entry_point(desc.entry_name, desc.spv_bytes);
         log::debug!("shader module created.");
         Ok(Self {
             device: desc.device.clone(),
@@ -64,6 +146,7 @@ impl Shader {
             entry_point,
             stage,
             name: CString::new(desc.entry_name).unwrap(),
+            specialization: desc.specialization.cloned(),
         })
     }
 
@@ -75,28 +158,46 @@ impl Shader {
         Self::new(desc, vk::ShaderStageFlags::FRAGMENT)
     }
 
-    fn reflect_entry_point(entry_name: &str, spv: &[u32]) -> EntryPoint {
+    /// Finds the entry point named `entry_name` whose execution model matches `stage`.
+    fn reflect_entry_point(
+        entry_name: &str,
+        stage: vk::ShaderStageFlags,
+        spv: &[u32],
+    ) -> Result<EntryPoint, ShaderError> {
         let entry_points = ReflectConfig::new()
-            // Load SPIR-V data into `[u32]` buffer `spv_words`.
             .spv(spv)
             // Set this true if you want to reflect all resources no matter it's
             // used by an entry point or not.
             .ref_all_rscs(true)
             .reflect()
-            .map_err(|_| {
-                log::error!("Unable to reflect spirv");
-            })
-            .unwrap();
-        // println!("{:#?}", &entry_points);
-        let entry_point = entry_points
+            .map_err(|err| ShaderError::Reflection(err.to_string()))?;
+        let available = entry_points
+            .iter()
+            .map(|entry_point| format!("{} ({:?})", entry_point.name, entry_point.exec_model))
+            .collect::<Vec<_>>();
+        entry_points
             .into_iter()
-            .find(|entry_point| entry_point.name == entry_name)
-            .ok_or_else(|| {
-                log::error!("Entry point not found");
+            .find(|entry_point| {
+                entry_point.name == entry_name
+                    && Self::execution_model_stage(entry_point.exec_model) == stage
             })
-            .unwrap();
+            .ok_or_else(|| ShaderError::EntryPointNotFound {
+                name: entry_name.to_owned(),
+                stage,
+                available,
+            })
+    }
 
-        entry_point
+    fn execution_model_stage(exec_model: ExecutionModel) -> vk::ShaderStageFlags {
+        match exec_model {
+            ExecutionModel::Vertex => vk::ShaderStageFlags::VERTEX,
+            ExecutionModel::TessellationControl => vk::ShaderStageFlags::TESSELLATION_CONTROL,
+            ExecutionModel::TessellationEvaluation => vk::ShaderStageFlags::TESSELLATION_EVALUATION,
+            ExecutionModel::Geometry => vk::ShaderStageFlags::GEOMETRY,
+            ExecutionModel::Fragment => vk::ShaderStageFlags::FRAGMENT,
+            ExecutionModel::GLCompute => vk::ShaderStageFlags::COMPUTE,
+            _ => vk::ShaderStageFlags::empty(),
+        }
     }
 
     pub fn create_shader_module(
@@ -122,12 +223,40 @@ impl Shader {
     }
 
     pub fn load_pre_compiled_spv_bytes_from_path<P: AsRef<Path>>(path: P) -> Vec<u32> {
-        use std::fs::File;
-        use std::io::Read;
-        let spv_file = File::open(path).unwrap();
-        let bytes_code: Vec<u8> = spv_file.bytes().filter_map(|byte| byte.ok()).collect();
-        let (_prefix, bytes, _suffix) = unsafe { bytes_code.align_to::<u32>() };
-        bytes.into()
+        let path = path.as_ref();
+        let bytes_code = std::fs::read(path).unwrap();
+        Self::spv_from_bytes(&bytes_code)
+            .unwrap_or_else(|err| panic!("{}: {}", path.display(), err))
+    }
+
+    /// Copies a SPIR-V binary into words, so `bytes` needs no alignment.
+    ///
+    /// 字节序由魔数判断，大端模块会被转换为本机字节序。
+    pub fn spv_from_bytes(bytes: &[u8]) -> Result<Vec<u32>, ShaderError> {
+        const SPIRV_MAGIC: u32 = 0x0723_0203;
+        // 魔数、版本、生成器、bound 和保留字
+        const SPIRV_HEADER_WORDS: usize = 5;
+        if bytes.len() % 4 != 0 {
+            return Err(ShaderError::InvalidSpirv("length is not a multiple of 4"));
+        }
+        let mut words = bytes
+            .chunks_exact(4)
+            .map(|word| u32::from_le_bytes([word[0], word[1], word[2], word[3]]))
+            .collect::<Vec<_>>();
+        match words.first() {
+            Some(&SPIRV_MAGIC) => {}
+            Some(magic) if magic.swap_bytes() == SPIRV_MAGIC => {
+                words.iter_mut().for_each(|word| *word = word.swap_bytes());
+            }
+            Some(_) => return Err(ShaderError::InvalidSpirv("wrong magic number")),
+            None => return Err(ShaderError::InvalidSpirv("empty module")),
+        }
+        if words.len() < SPIRV_HEADER_WORDS {
+            return Err(ShaderError::InvalidSpirv(
+                "module is shorter than its header",
+            ));
+        }
+        Ok(words)
     }
 
     pub fn get_push_constant_range(&self) -> Option<vk::PushConstantRange> {
@@ -194,3 +323,76 @@ impl ShaderPropertyInfo for Vertex3D {
         vec![pos, color, tex_coord]
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEADER: [u32; 5] = [0x0723_0203, 0x0001_0000, 0, 1, 0];
+
+    fn to_bytes(words: &[u32], to_bytes: fn(u32) -> [u8; 4]) -> Vec<u8> {
+        words.iter().flat_map(|&word| to_bytes(word)).collect()
+    }
+
+    #[test]
+    fn spv_from_bytes_reads_a_valid_module() {
+        let bytes = to_bytes(&HEADER, u32::to_le_bytes);
+        assert_eq!(Shader::spv_from_bytes(&bytes), Ok(HEADER.to_vec()));
+        // 未对齐的切片同样可以读取
+        let mut unaligned = vec![0u8];
+        unaligned.extend_from_slice(&bytes);
+        assert_eq!(Shader::spv_from_bytes(&unaligned[1..]), Ok(HEADER.to_vec()));
+    }
+
+    #[test]
+    fn spv_from_bytes_swaps_a_big_endian_module() {
+        let bytes = to_bytes(&HEADER, u32::to_be_bytes);
+        assert_eq!(Shader::spv_from_bytes(&bytes), Ok(HEADER.to_vec()));
+    }
+
+    #[test]
+    fn spv_from_bytes_rejects_a_truncated_module() {
+        let bytes = to_bytes(&HEADER, u32::to_le_bytes);
+        assert!(Shader::spv_from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        assert!(Shader::spv_from_bytes(&bytes[..8]).is_err());
+        assert!(Shader::spv_from_bytes(&[]).is_err());
+    }
+
+    #[test]
+    fn spv_from_bytes_rejects_a_wrong_magic_number() {
+        let mut words = HEADER;
+        words[0] = 0xDEAD_BEEF;
+        let bytes = to_bytes(&words, u32::to_le_bytes);
+        assert_eq!(
+            Shader::spv_from_bytes(&bytes),
+            Err(ShaderError::InvalidSpirv("wrong magic number"))
+        );
+    }
+
+    #[test]
+    fn specialization_constants_are_packed() {
+        let info = SpecializationInfo::default()
+            .with_constant(0, true)
+            .with_constant(1, 2.0f64)
+            .with_constant(2, -1i32);
+        let offsets = info
+            .map_entries
+            .iter()
+            .map(|entry| (entry.constant_id, entry.offset, entry.size))
+            .collect::<Vec<_>>();
+        assert_eq!(offsets, [(0, 0, 4), (1, 4, 8), (2, 12, 4)]);
+        assert_eq!(info.data.len(), 16);
+        assert_eq!(info.data[..4], vk::TRUE.to_ne_bytes());
+    }
+
+    #[test]
+    fn vertex_fragment_is_part_of_all_graphics() {
+        assert!(vk::ShaderStageFlags::ALL_GRAPHICS
+            .contains(vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT));
+        assert_eq!(
+            VERTEX_FRAGMENT,
+            vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT
+        );
+        assert!(vk::ShaderStageFlags::ALL_GRAPHICS.contains(VERTEX_FRAGMENT));
+    }
+}
@@ -1,5 +1,40 @@
 use ash::{extensions::*, vk};
 
+use crate::{DeviceError, SurfaceError};
+
+/// Subset of `vk::SurfaceCapabilitiesKHR` that apps need to size and recreate swapchains.
+#[derive(Clone, Copy, Debug)]
+pub struct SurfaceCapabilities {
+    pub min_image_count: u32,
+    /// `None` if the surface has no upper limit on the image count.
+    pub max_image_count: Option<u32>,
+    /// `None` if the extent is decided by the swapchain, e.g. on Wayland.
+    pub current_extent: Option<vk::Extent2D>,
+    pub min_image_extent: vk::Extent2D,
+    pub max_image_extent: vk::Extent2D,
+    pub current_transform: vk::SurfaceTransformFlagsKHR,
+    pub supported_transforms: vk::SurfaceTransformFlagsKHR,
+    pub supported_composite_alpha: vk::CompositeAlphaFlagsKHR,
+}
+
+impl From<vk::SurfaceCapabilitiesKHR> for SurfaceCapabilities {
+    fn from(capabilities: vk::SurfaceCapabilitiesKHR) -> Self {
+        Self {
+            min_image_count: capabilities.min_image_count,
+            // 0 表示没有上限
+            max_image_count: (capabilities.max_image_count > 0)
+                .then_some(capabilities.max_image_count),
+            current_extent: (capabilities.current_extent.width != u32::MAX)
+                .then_some(capabilities.current_extent),
+            min_image_extent: capabilities.min_image_extent,
+            max_image_extent: capabilities.max_image_extent,
+            current_transform: capabilities.current_transform,
+            supported_transforms: capabilities.supported_transforms,
+            supported_composite_alpha: capabilities.supported_composite_alpha,
+        }
+    }
+}
+
 pub struct Surface {
     raw: vk::SurfaceKHR,
     loader: khr::Surface,
@@ -17,4 +52,20 @@ impl Surface {
     pub fn new(raw: vk::SurfaceKHR, loader: khr::Surface) -> Self {
         Self { raw, loader }
     }
+
+    /// Queries the current capabilities, they change when the window is resized or moved
+    /// between monitors.
+    pub fn capabilities(
+        &self,
+        physical_device: vk::PhysicalDevice,
+    ) -> Result<SurfaceCapabilities, SurfaceError> {
+        match unsafe {
+            self.loader
+                .get_physical_device_surface_capabilities(physical_device, self.raw)
+        } {
+            Ok(capabilities) => Ok(capabilities.into()),
+            Err(vk::Result::ERROR_SURFACE_LOST_KHR) => Err(SurfaceError::Lost),
+            Err(other) => Err(DeviceError::from(other).into()),
+        }
+    }
 }
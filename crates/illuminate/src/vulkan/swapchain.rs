@@ -22,17 +22,31 @@ use crate::vulkan::descriptor_set_allocator::{
     DescriptorSetAllocator, PerFrameDescriptorSetsCreateInfo,
 };
 use crate::vulkan::device::Device;
+use crate::vulkan::framebuffer::{FramebufferCache, FramebufferDescriptor};
 use crate::vulkan::image::{DepthImageDescriptor, Image, ImageDescriptor};
 use crate::vulkan::image_view::ImageView;
 use crate::vulkan::instance::Instance;
 use crate::vulkan::model::Model;
-use crate::vulkan::pipeline::Pipeline;
-use crate::vulkan::render_pass::{ImguiRenderPassDescriptor, RenderPass, RenderPassDescriptor};
+use crate::vulkan::passes::depth_prepass::{DepthPrepass, DepthPrepassDescriptor};
+use crate::vulkan::pipeline::{ColorBlendState, DepthState, Pipeline, PrimitiveState};
+use crate::vulkan::render_pass::{
+    DepthAttachmentType, ImguiRenderPassDescriptor, RenderPass, RenderPassDescriptor,
+};
 use crate::vulkan::shader::{Shader, ShaderDescriptor};
 use crate::vulkan::surface::Surface;
 use crate::vulkan::texture::{VulkanTexture, VulkanTextureDescriptor};
 use crate::vulkan::uniform_buffer::UniformBufferObject;
-use crate::{Color, DeviceError, QueueFamilyIndices, SurfaceError};
+use crate::{Color, DeviceError, QueueFamilyIndices, SurfaceError, VulkanCallExt};
+
+pub const DEFAULT_CLEAR_COLOR: Color = Color {
+    r: 0.65,
+    g: 0.8,
+    b: 0.9,
+    a: 1.0,
+};
+
+/// Shared by the main pipeline and the depth prepass.
+const MAIN_VERTEX_SHADER: &str = "triangle_push_constant.vert";
 
 pub struct Swapchain {
     raw: vk::SwapchainKHR,
@@ -50,15 +64,25 @@ pub struct Swapchain {
     render_pass: RenderPass,
     imgui_render_pass: RenderPass,
     pipeline: Pipeline,
+    /// Draws the model depth-only before `pipeline`, which then compares with `EQUAL`.
+    depth_prepass: Option<DepthPrepass>,
+    /// One per frame in flight, not per image: the renderer waits on the fence of a frame before
+    /// it records into that frame's command buffer again. Allocated with `RESET_COMMAND_BUFFER`
+    /// from the renderer's command pool and freed with the swapchain.
     command_buffers: Vec<CommandBuffer>,
     framebuffers: Vec<vk::Framebuffer>,
     imgui_framebuffers: Vec<vk::Framebuffer>,
+    framebuffer_cache: FramebufferCache,
     graphics_queue: vk::Queue,
     present_queue: vk::Queue,
     command_buffer_allocator: Rc<CommandBufferAllocator>,
     descriptor_set_allocator: Rc<DescriptorSetAllocator>,
-    depth_texture: VulkanTexture,
+    depth_texture: Rc<VulkanTexture>,
     color_texture: VulkanTexture,
+    /// Extent the main pass renders at, differs from `extent` with a render scale.
+    render_extent: vk::Extent2D,
+    /// Resolve target of the main pass when it renders at `render_extent`, blitted to the swapchain image.
+    scaled_target: Option<VulkanTexture>,
     vertex_buffer: Buffer,
     index_buffer: Buffer,
     uniform_buffers: Vec<Buffer>,
@@ -66,6 +90,33 @@ pub struct Swapchain {
     model: Rc<Model>,
     mip_levels: u32,
     instant: Instant,
+    reverse_z: bool,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct AcquiredImage {
+    pub index: u32,
+    /// The swapchain still works but no longer matches the surface, it should be recreated.
+    pub is_suboptimal: bool,
+}
+
+/// Returned by `VulkanRenderer::recreate_swapchain`.
+#[derive(Clone, Copy, Debug)]
+pub struct SwapchainRecreated {
+    /// `None` if there was no swapchain before.
+    pub old_format: Option<vk::SurfaceFormatKHR>,
+    pub new_format: vk::SurfaceFormatKHR,
+}
+
+impl SwapchainRecreated {
+    /// The surface format changed (e.g. the window moved between SDR and HDR monitors),
+    /// render passes and pipelines created for the old format must be rebuilt.
+    pub fn format_changed(&self) -> bool {
+        self.old_format.map_or(false, |old_format| {
+            old_format.format != self.new_format.format
+                || old_format.color_space != self.new_format.color_space
+        })
+    }
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -99,13 +150,18 @@ pub struct SwapchainDescriptor<'a> {
     pub model: Rc<Model>,
     pub mip_levels: u32,
     pub instant: Instant,
-}
-
-#[derive(Clone, TypedBuilder, Hash, PartialEq, Eq)]
-pub struct FramebufferDescriptor {
-    render_pass: vk::RenderPass,
-    texture_views: Vec<vk::ImageView>,
-    swapchain_extent: vk::Extent2D,
+    pub preferred_depth_format: Option<vk::Format>,
+    /// Depth texture of the old swapchain, reused when the extent is unchanged and it has the
+    /// preferred depth format.
+    pub old_depth_texture: Option<Rc<VulkanTexture>>,
+    /// Uses a reversed projection, clears depth to 0.0 and compares with GREATER_OR_EQUAL.
+    pub reverse_z: bool,
+    /// Background of the main pass, see `DEFAULT_CLEAR_COLOR`.
+    pub clear_color: Color,
+    /// The main pass renders at the swapchain extent times this, see `VulkanRenderer::set_render_scale`.
+    pub render_scale: f32,
+    /// See `VulkanRenderer::set_depth_prepass`.
+    pub depth_prepass: bool,
 }
 
 impl Swapchain {
@@ -125,6 +181,56 @@ impl Swapchain {
         self.extent
     }
 
+    /// Viewport covering the whole swapchain, flipped on Y if the device supports negative
+    /// viewport heights. Otherwise projections must be flipped with `conv::flip_projection_y`.
+    pub fn viewport(&self) -> Rect2D {
+        // 改为左手坐标系 NDC
+        conv::y_up_viewport(
+            self.extent,
+            self.device.is_negative_viewport_height_supported(),
+        )
+    }
+
+    /// Sets the viewport and scissor to the whole swapchain, the viewport is flipped on Y like
+    /// `viewport`.
+    pub fn cmd_set_full_viewport_scissor(&self, command_buffer: vk::CommandBuffer) {
+        self.device
+            .cmd_set_viewport(command_buffer, self.viewport());
+        let scissor = vk::Rect2D {
+            offset: vk::Offset2D { x: 0, y: 0 },
+            extent: self.extent,
+        };
+        self.device.cmd_set_scissor(command_buffer, 0, &[scissor]);
+    }
+
+    /// Extent of the main pass, the swapchain extent scaled by the render scale.
+    pub fn render_extent(&self) -> vk::Extent2D {
+        self.render_extent
+    }
+
+    /// `scale` is already clamped to `RENDER_SCALE_RANGE` by the renderer, the result is at least
+    /// one pixel on each side.
+    fn scale_extent(extent: vk::Extent2D, scale: f32) -> vk::Extent2D {
+        vk::Extent2D {
+            width: ((extent.width as f32 * scale).round() as u32).max(1),
+            height: ((extent.height as f32 * scale).round() as u32).max(1),
+        }
+    }
+
+    pub fn images(&self) -> &[vk::Image] {
+        &self.swapchain_images
+    }
+
+    /// Size per-image resources, e.g. framebuffers, by this rather than `MAX_FRAMES_IN_FLIGHT`,
+    /// the driver may create more images than requested.
+    pub fn image_count(&self) -> u32 {
+        self.swapchain_images.len() as u32
+    }
+
+    pub fn depth_texture(&self) -> &Rc<VulkanTexture> {
+        &self.depth_texture
+    }
+
     pub fn color_texture(&self) -> &VulkanTexture {
         &self.color_texture
     }
@@ -137,6 +243,12 @@ impl Swapchain {
         &self.imgui_render_pass
     }
 
+    /// Takes effect from the next recorded frame, the color attachment is the first one of the main pass.
+    pub fn set_clear_color(&mut self, clear_color: Color) {
+        self.render_pass
+            .set_clear_value(0, conv::convert_clear_color(clear_color));
+    }
+
     pub fn pipeline(&self) -> &Pipeline {
         &self.pipeline
     }
@@ -177,12 +289,51 @@ impl Swapchain {
         // };
 
         let color_format = properties.surface_format.format;
-        let color_texture = Self::create_color_objects(desc, color_format, extent)?;
-
-        let depth_texture = Self::create_depth_objects(desc, extent)?;
+        let mut render_extent = Self::scale_extent(extent, desc.render_scale);
+        let blit_features = vk::FormatFeatureFlags::BLIT_SRC
+            | vk::FormatFeatureFlags::BLIT_DST
+            | vk::FormatFeatureFlags::SAMPLED_IMAGE_FILTER_LINEAR;
+        let can_blit = support
+            .capabilities
+            .supported_usage_flags
+            .contains(vk::ImageUsageFlags::TRANSFER_DST)
+            && desc
+                .adapter
+                .format_properties(&desc.instance, color_format)
+                .optimal_tiling_features
+                .contains(blit_features);
+        if render_extent != extent && !can_blit {
+            log::warn!("Swapchain images can not be blitted to, render scale is ignored.");
+            render_extent = extent;
+        }
+        let scaled_target = if render_extent != extent {
+            Some(Self::create_scaled_target(
+                desc,
+                color_format,
+                render_extent,
+            )?)
+        } else {
+            None
+        };
+        let color_texture = Self::create_color_objects(desc, color_format, render_extent)?;
+
+        let depth_texture = match &desc.old_depth_texture {
+            Some(texture)
+                if texture.width() == render_extent.width
+                    && texture.height() == render_extent.height
+                    && desc
+                        .preferred_depth_format
+                        .map_or(true, |format| format == texture.image().format()) =>
+            {
+                log::debug!("Reuse depth texture of the old swapchain.");
+                texture.clone()
+            }
+            _ => Rc::new(Self::create_depth_objects(desc, render_extent)?),
+        };
         let depth_format = depth_texture.image().format();
 
-        let clear_color = Color::new(0.65, 0.8, 0.9, 1.0);
+        let clear_color = desc.clear_color;
+        let render_rect2d = Rect2D::full(render_extent.width as f32, render_extent.height as f32);
         let rect2d = Rect2D {
             x: 0.0,
             y: 0.0,
@@ -190,16 +341,17 @@ impl Swapchain {
             height: extent.height as f32,
         };
 
-        let map = Default::default();
+        let framebuffer_cache = FramebufferCache::new(device);
 
         let render_pass_desc = RenderPassDescriptor {
             device,
             surface_format: color_format,
             depth_format,
-            render_area: rect2d,
+            depth_attachment_type: DepthAttachmentType::Depth,
+            render_area: render_rect2d,
             clear_color,
             max_msaa_samples: desc.adapter.max_msaa_samples(),
-            depth: 1.0,
+            depth: if desc.reverse_z { 0.0 } else { 1.0 },
             stencil: 0,
         };
         let render_pass = RenderPass::new(&render_pass_desc)?;
@@ -207,17 +359,20 @@ impl Swapchain {
         let framebuffers = swapchain_image_views
             .iter()
             .map(|i| {
-                let image_view = i.raw();
+                // 有渲染缩放时解析到离屏目标，所有图像共用同一个帧缓冲
+                let resolve_view = scaled_target
+                    .as_ref()
+                    .map_or(i.raw(), |target| target.raw_image_view());
                 let framebuffer_desc = FramebufferDescriptor::builder()
                     .texture_views(vec![
                         color_texture.image_view().raw(),
                         depth_texture.image_view().raw(),
-                        image_view,
+                        resolve_view,
                     ])
-                    .swapchain_extent(extent)
+                    .swapchain_extent(render_extent)
                     .render_pass(render_pass.raw())
                     .build();
-                Self::create_framebuffer(device, &map, framebuffer_desc)
+                framebuffer_cache.get_or_create(framebuffer_desc)
             })
             .collect::<Result<Vec<_>, _>>()?;
 
@@ -237,17 +392,16 @@ impl Swapchain {
                     .swapchain_extent(extent)
                     .render_pass(imgui_render_pass.raw())
                     .build();
-                Self::create_framebuffer(device, &map, framebuffer_desc)
+                framebuffer_cache.get_or_create(framebuffer_desc)
             })
             .collect::<Result<Vec<_>, _>>()?;
 
         let vert_shader_desc = ShaderDescriptor {
             label: Some("Triangle Vert"),
             device,
-            spv_bytes: &Shader::load_pre_compiled_spv_bytes_from_name(
-                "triangle_push_constant.vert",
-            ),
+            spv_bytes: &Shader::load_pre_compiled_spv_bytes_from_name(MAIN_VERTEX_SHADER),
             entry_name: "main",
+            specialization: None,
         };
         let vert_shader = Shader::new_vert(&vert_shader_desc)?;
         let frag_shader_desc = ShaderDescriptor {
@@ -257,6 +411,7 @@ impl Swapchain {
                 "triangle_push_constant.frag",
             ),
             entry_name: "main",
+            specialization: None,
         };
         let frag_shader = Shader::new_frag(&frag_shader_desc)?;
 
@@ -300,18 +455,42 @@ impl Swapchain {
             descriptor_set_allocator.raw_texture_layout(),
         ];
 
+        let depth_prepass = if desc.depth_prepass {
+            Some(DepthPrepass::new(
+                DepthPrepassDescriptor::builder()
+                    .device(device)
+                    .render_pass(render_pass.raw())
+                    .msaa_samples(desc.adapter.max_msaa_samples())
+                    .descriptor_set_layouts(descriptor_set_layouts)
+                    .vertex_shader(MAIN_VERTEX_SHADER)
+                    .reverse_z(desc.reverse_z)
+                    .build(),
+            )?)
+        } else {
+            None
+        };
+        let depth_state = if depth_prepass.is_some() {
+            DepthPrepass::main_pass_depth_state()
+        } else if desc.reverse_z {
+            DepthState::reverse_z()
+        } else {
+            DepthState::default()
+        };
         let shaders = &[vert_shader, frag_shader];
         let pipeline = Pipeline::new(
             device,
             render_pass.raw(),
-            desc.adapter.max_msaa_samples(),
+            desc.adapter.max_msaa_samples().into(),
             descriptor_set_layouts,
             shaders,
+            PrimitiveState::default(),
+            depth_state,
+            &[ColorBlendState::default()],
         )?;
 
         let command_buffers = desc
             .command_buffer_allocator
-            .allocate_command_buffers(true, swapchain_image_views.len() as u32)?;
+            .allocate_command_buffers(true, desc.max_frame_in_flight)?;
 
         let model_texture = desc.model.texture();
         let descriptor_sets_create_info = PerFrameDescriptorSetsCreateInfo {
@@ -339,8 +518,10 @@ impl Swapchain {
             framebuffers,
             render_pass,
             imgui_framebuffers,
+            framebuffer_cache,
             imgui_render_pass,
             pipeline,
+            depth_prepass,
             command_buffers,
             graphics_queue: desc.graphics_queue,
             present_queue: desc.present_queue,
@@ -348,6 +529,8 @@ impl Swapchain {
             descriptor_set_allocator,
             depth_texture,
             color_texture,
+            render_extent,
+            scaled_target,
             vertex_buffer,
             index_buffer,
             uniform_buffers,
@@ -355,13 +538,18 @@ impl Swapchain {
             model: desc.model.clone(),
             mip_levels: desc.mip_levels,
             instant: desc.instant,
+            reverse_z: desc.reverse_z,
         };
 
         Ok(swapchain)
     }
 
+    /// Records the command buffer of `frame`, which must no longer be executing, drawing into
+    /// the swapchain image `image_index`.
+    #[allow(clippy::too_many_arguments)]
     pub fn render(
         &mut self,
+        frame: usize,
         image_index: usize,
         window: &Window,
         gui_context: &mut GuiContext,
@@ -372,6 +560,7 @@ impl Swapchain {
         self.update_uniform_buffer(image_index, ui_state);
 
         let command_buffer = self.update_command_buffers(
+            frame,
             image_index,
             window,
             gui_context,
@@ -383,8 +572,10 @@ impl Swapchain {
         Ok(command_buffer.raw())
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn update_command_buffers(
         &mut self,
+        frame: usize,
         image_index: usize,
         window: &Window,
         gui_context: &mut GuiContext,
@@ -392,7 +583,7 @@ impl Swapchain {
         ui_state: &mut GuiState,
         ui_func: impl FnOnce(&mut GuiState, &mut imgui::Ui),
     ) -> Result<&CommandBuffer, DeviceError> {
-        let command_buffer = &self.command_buffers[image_index];
+        let command_buffer = &self.command_buffers[frame];
 
         self.device
             .reset_command_buffer(command_buffer.raw(), vk::CommandBufferResetFlags::empty())?;
@@ -406,30 +597,23 @@ impl Swapchain {
         )?;
 
         let framebuffer = self.framebuffers[image_index];
-        self.render_pass.begin(command_buffer, framebuffer);
+        self.render_pass.begin(command_buffer, framebuffer)?;
 
-        self.device.cmd_bind_pipeline(
-            command_buffer.raw(),
-            vk::PipelineBindPoint::GRAPHICS,
-            self.pipeline.raw(),
+        let render_scale = self.render_extent.width as f32 / self.extent.width as f32;
+        // 两种翻转方式下视口原点的偏移方向相同
+        let mut viewport_rect2d = conv::y_up_viewport(
+            self.render_extent,
+            self.device.is_negative_viewport_height_supported(),
         );
-
-        // 改为左手坐标系 NDC
-        let viewport_rect2d = Rect2D {
-            x: 0f32 + ui_state.viewport_xy.x,
-            y: self.extent.height as f32 - ui_state.viewport_xy.y,
-            width: self.extent.width as f32,
-            height: -(self.extent.height as f32),
-        };
+        viewport_rect2d.x += ui_state.viewport_xy.x * render_scale;
+        viewport_rect2d.y -= ui_state.viewport_xy.y * render_scale;
         self.device
             .cmd_set_viewport(command_buffer.raw(), viewport_rect2d);
 
-        let scissor_rect2d = Rect2D {
-            x: 0.0,
-            y: 0.0,
-            width: self.extent.width as f32,
-            height: self.extent.height as f32,
-        };
+        let scissor_rect2d = Rect2D::full(
+            self.render_extent.width as f32,
+            self.render_extent.height as f32,
+        );
         self.device.cmd_set_scissor(
             command_buffer.raw(),
             0,
@@ -450,6 +634,50 @@ impl Swapchain {
             vk::IndexType::UINT32, // Model.indices
         );
 
+        let time = self.instant.elapsed().as_secs_f32();
+        let model = math::Transform::from_rotation(math::quat_angle_axis(
+            // time *  math::radians(&math::vec1(90.0))[0],
+            math::radians(&math::vec1(ui_state.value))[0],
+            &vec3(0.0, 0.0, 1.0),
+        ))
+        .to_matrix();
+
+        let (_, model_bytes, _) = unsafe { model.as_slice().align_to::<u8>() };
+
+        if let Some(depth_prepass) = &self.depth_prepass {
+            depth_prepass.bind(command_buffer.raw());
+            let layout = depth_prepass.pipeline().raw_pipeline_layout();
+            self.device.cmd_bind_descriptor_sets(
+                command_buffer.raw(),
+                vk::PipelineBindPoint::GRAPHICS,
+                layout,
+                0,
+                &[self.per_frame_descriptor_sets[image_index]],
+                &[],
+            );
+            self.device.cmd_push_constants(
+                command_buffer.raw(),
+                layout,
+                vk::ShaderStageFlags::VERTEX,
+                0,
+                model_bytes,
+            );
+            self.device.cmd_draw_indexed(
+                command_buffer.raw(),
+                self.model.indices().len() as u32,
+                1,
+                0,
+                0,
+                0,
+            );
+        }
+
+        self.device.cmd_bind_pipeline(
+            command_buffer.raw(),
+            vk::PipelineBindPoint::GRAPHICS,
+            self.pipeline.raw(),
+        );
+
         self.device.cmd_bind_descriptor_sets(
             command_buffer.raw(),
             vk::PipelineBindPoint::GRAPHICS,
@@ -459,16 +687,6 @@ impl Swapchain {
             &[],
         );
 
-        let time = self.instant.elapsed().as_secs_f32();
-        let model = math::rotate(
-            &math::identity(),
-            // time *  math::radians(&math::vec1(90.0))[0],
-            math::radians(&math::vec1(ui_state.value))[0],
-            &vec3(0.0, 0.0, 1.0),
-        );
-
-        let (_, model_bytes, _) = unsafe { model.as_slice().align_to::<u8>() };
-
         self.device.cmd_push_constants(
             command_buffer.raw(),
             self.pipeline.raw_pipeline_layout(),
@@ -497,14 +715,19 @@ impl Swapchain {
         );
 
         self.render_pass.end(command_buffer);
+        if let Some(scaled_target) = &self.scaled_target {
+            self.cmd_blit_scaled_target(command_buffer.raw(), scaled_target, image_index);
+        }
 
         self.imgui_render_pass
-            .begin(command_buffer, self.imgui_framebuffers[image_index]);
+            .begin(command_buffer, self.imgui_framebuffers[image_index])?;
 
         let draw_data = gui_context.render(window, ui_state, ui_func);
-        gui_renderer
-            .cmd_draw(command_buffer.raw(), draw_data)
-            .unwrap();
+        // 命令缓冲区会在下一帧重置，这里直接返回错误即可
+        if let Err(e) = gui_renderer.cmd_draw(command_buffer.raw(), draw_data) {
+            log::error!("failed to record imgui draw data. Err: {}", e);
+            return Err(DeviceError::Other("failed to record imgui draw data"));
+        }
 
         self.imgui_render_pass.end(command_buffer);
 
@@ -512,28 +735,150 @@ impl Swapchain {
         Ok(command_buffer)
     }
 
+    /// Blits the resolved main pass to the swapchain image and leaves it in `COLOR_ATTACHMENT_OPTIMAL`
+    /// for the imgui pass.
+    fn cmd_blit_scaled_target(
+        &self,
+        command_buffer: vk::CommandBuffer,
+        scaled_target: &VulkanTexture,
+        image_index: usize,
+    ) {
+        let swapchain_image = self.swapchain_images[image_index];
+        let subresource_range = vk::ImageSubresourceRange::builder()
+            .aspect_mask(vk::ImageAspectFlags::COLOR)
+            .base_mip_level(0)
+            .level_count(1)
+            .base_array_layer(0)
+            .layer_count(1)
+            .build();
+        let image_barrier = |image: vk::Image,
+                             old_layout: vk::ImageLayout,
+                             new_layout: vk::ImageLayout,
+                             src_access_mask: vk::AccessFlags,
+                             dst_access_mask: vk::AccessFlags| {
+            vk::ImageMemoryBarrier::builder()
+                .old_layout(old_layout)
+                .new_layout(new_layout)
+                .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                .image(image)
+                .subresource_range(subresource_range)
+                .src_access_mask(src_access_mask)
+                .dst_access_mask(dst_access_mask)
+                .build()
+        };
+        self.device.cmd_pipeline_barrier(
+            command_buffer,
+            vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+            vk::PipelineStageFlags::TRANSFER,
+            vk::DependencyFlags::empty(),
+            &[],
+            &[],
+            &[
+                image_barrier(
+                    scaled_target.raw_image(),
+                    vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+                    vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                    vk::AccessFlags::COLOR_ATTACHMENT_WRITE,
+                    vk::AccessFlags::TRANSFER_READ,
+                ),
+                // 交换链图像的旧内容不需要保留
+                image_barrier(
+                    swapchain_image,
+                    vk::ImageLayout::UNDEFINED,
+                    vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                    vk::AccessFlags::empty(),
+                    vk::AccessFlags::TRANSFER_WRITE,
+                ),
+            ],
+        );
+
+        let subresource = vk::ImageSubresourceLayers::builder()
+            .aspect_mask(vk::ImageAspectFlags::COLOR)
+            .mip_level(0)
+            .base_array_layer(0)
+            .layer_count(1)
+            .build();
+        let offsets = |extent: vk::Extent2D| {
+            [
+                vk::Offset3D { x: 0, y: 0, z: 0 },
+                vk::Offset3D {
+                    x: extent.width as i32,
+                    y: extent.height as i32,
+                    z: 1,
+                },
+            ]
+        };
+        let blit = vk::ImageBlit::builder()
+            .src_subresource(subresource)
+            .src_offsets(offsets(self.render_extent))
+            .dst_subresource(subresource)
+            .dst_offsets(offsets(self.extent))
+            .build();
+        self.device.cmd_blit_image(
+            command_buffer,
+            scaled_target.raw_image(),
+            vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+            swapchain_image,
+            vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+            &[blit],
+            vk::Filter::LINEAR,
+        );
+
+        // 离屏目标回到渲染通道的 final_layout，下一帧的解析写入要等待这次读取
+        self.device.cmd_pipeline_barrier(
+            command_buffer,
+            vk::PipelineStageFlags::TRANSFER,
+            vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+            vk::DependencyFlags::empty(),
+            &[],
+            &[],
+            &[
+                image_barrier(
+                    scaled_target.raw_image(),
+                    vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                    vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+                    vk::AccessFlags::TRANSFER_READ,
+                    vk::AccessFlags::COLOR_ATTACHMENT_WRITE,
+                ),
+                image_barrier(
+                    swapchain_image,
+                    vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                    vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+                    vk::AccessFlags::TRANSFER_WRITE,
+                    vk::AccessFlags::COLOR_ATTACHMENT_READ
+                        | vk::AccessFlags::COLOR_ATTACHMENT_WRITE,
+                ),
+            ],
+        );
+    }
+
     fn update_uniform_buffer(&mut self, image_index: usize, ui_state: &GuiState) {
         let view = math::look_at(
             &vec3(2.0, 2.0, 2.0),
             &vec3(0.0, 0.0, 0.0),
             &vec3(0.0, 0.0, 1.0),
         );
-        let projection = math::perspective_rh_zo(
-            self.extent.width as f32 / self.extent.height as f32,
-            // math::radians(&math::vec1(45.0))[0],
-            math::radians(&math::vec1(ui_state.fovy))[0],
-            0.1,
-            10.0,
-        );
-        // projection[(1, 1)] *= -1.0; // openGL clip space y 和 vulkan 相反，不过我们在 cmd_set_viewport 处理了
+        let aspect = self.extent.width as f32 / self.extent.height as f32;
+        // math::radians(&math::vec1(45.0))[0],
+        let fovy = math::radians(&math::vec1(ui_state.fovy))[0];
+        let mut projection = if self.reverse_z {
+            math::reversed_perspective_rh_zo(aspect, fovy, 0.1, 10.0)
+        } else {
+            math::perspective_rh_zo(aspect, fovy, 0.1, 10.0)
+        };
+        // openGL clip space y 和 vulkan 相反，优先在 cmd_set_viewport 处理，不支持负高度视口时翻转投影矩阵
+        if !self.device.is_negative_viewport_height_supported() {
+            conv::flip_projection_y(&mut projection);
+        }
         let ubo = UniformBufferObject { view, projection };
 
         let uniform_buffer = &mut self.uniform_buffers[image_index];
         uniform_buffer.copy_memory(&[ubo]);
     }
 
-    pub fn update_submitted_command_buffer(&mut self, command_buffer_index: usize) {
-        let command_buffer = &mut self.command_buffers[command_buffer_index];
+    pub fn update_submitted_command_buffer(&mut self, frame: usize) {
+        let command_buffer = &mut self.command_buffers[frame];
         command_buffer.set_state(CommandBufferState::Submitted);
     }
 
@@ -558,7 +903,7 @@ impl Swapchain {
                 desc.surface.raw(),
             )
         }?;
-        let properties = swapchain_support.get_ideal_swapchain_properties(desc.dimensions);
+        let properties = swapchain_support.get_ideal_swapchain_properties(desc.dimensions)?;
         let SwapchainProperties {
             surface_format,
             present_mode,
@@ -603,7 +948,12 @@ impl Swapchain {
             .image_extent(extent)
             // 这里，我们进行绘制操作
             // 如果要进行后处理，可以改成 TRANSFER_DST，让交换链 image 可以作为传输目的
-            .image_usage(vk::ImageUsageFlags::COLOR_ATTACHMENT)
+            // 支持的话加上 TRANSFER_SRC，用于截图回读，以及 TRANSFER_DST，用于渲染缩放的 blit
+            .image_usage(
+                vk::ImageUsageFlags::COLOR_ATTACHMENT
+                    | (swapchain_support.capabilities.supported_usage_flags
+                        & (vk::ImageUsageFlags::TRANSFER_SRC | vk::ImageUsageFlags::TRANSFER_DST)),
+            )
             .image_sharing_mode(image_sharing_mode)
             .queue_family_indices(&queue_family_indices)
             // 指定一个固定的变换操作，比如顺时针旋转 90 度或是水平翻转，这里不进行任何变换
@@ -619,7 +969,11 @@ impl Swapchain {
             .old_swapchain(old_swapchain);
 
         let swapchain_loader = khr::Swapchain::new(desc.instance.raw(), desc.device.raw());
-        let swapchain = unsafe { swapchain_loader.create_swapchain(&create_info, None)? };
+        let swapchain = unsafe {
+            swapchain_loader
+                .create_swapchain(&create_info, None)
+                .vulkan_call("vkCreateSwapchainKHR")?
+        };
         log::debug!("Vulkan swapchain created. min_image_count: {}", image_count);
 
         Ok((
@@ -631,38 +985,19 @@ impl Swapchain {
         ))
     }
 
-    pub fn create_framebuffer(
-        device: &Device,
-        map: &Mutex<fxhash::FxHashMap<FramebufferDescriptor, vk::Framebuffer>>,
-        desc: FramebufferDescriptor,
-    ) -> Result<vk::Framebuffer, DeviceError> {
-        use std::collections::hash_map::Entry;
-        Ok(match map.lock().entry(desc) {
-            Entry::Occupied(e) => *e.get(),
-            Entry::Vacant(e) => {
-                let desc = e.key();
-                let create_info = vk::FramebufferCreateInfo::builder()
-                    .render_pass(desc.render_pass)
-                    .attachments(&desc.texture_views)
-                    .width(desc.swapchain_extent.width)
-                    .height(desc.swapchain_extent.height)
-                    .layers(1)
-                    .build();
-                device.create_framebuffer(&create_info)?
-            }
-        })
-    }
-
     pub fn acquire_next_image(
         &self,
         timeout: u64,
         semaphore: vk::Semaphore,
-    ) -> Result<(u32, bool), SurfaceError> {
+    ) -> Result<AcquiredImage, SurfaceError> {
         match unsafe {
             self.loader
                 .acquire_next_image(self.raw, timeout, semaphore, vk::Fence::null())
         } {
-            Ok(pair) => Ok(pair),
+            Ok((index, is_suboptimal)) => Ok(AcquiredImage {
+                index,
+                is_suboptimal,
+            }),
             Err(error) => match error {
                 vk::Result::ERROR_OUT_OF_DATE_KHR | vk::Result::NOT_READY => {
                     Err(SurfaceError::OutOfDate)
@@ -673,6 +1008,21 @@ impl Swapchain {
         }
     }
 
+    /// Presents `image_index` on the present queue after `wait_semaphores` are signaled.
+    pub fn present(
+        &self,
+        image_index: u32,
+        wait_semaphores: &[vk::Semaphore],
+    ) -> Result<bool, SurfaceError> {
+        let swapchains = [self.raw];
+        let image_indices = [image_index];
+        let present_info = vk::PresentInfoKHR::builder()
+            .wait_semaphores(wait_semaphores)
+            .swapchains(&swapchains)
+            .image_indices(&image_indices);
+        self.queue_present(&present_info)
+    }
+
     pub fn queue_present(&self, present_info: &vk::PresentInfoKHR) -> Result<bool, SurfaceError> {
         match unsafe { self.loader.queue_present(self.present_queue, present_info) } {
             Ok(suboptimal) => Ok(suboptimal),
@@ -719,6 +1069,8 @@ impl Swapchain {
             allocator: desc.allocator.clone(),
             width: extent.width,
             height: extent.height,
+            attachment_type: DepthAttachmentType::Depth,
+            preferred_format: desc.preferred_depth_format,
             command_buffer_allocator: &desc.command_buffer_allocator,
         };
         let depth_image = Image::new_depth_image(&depth_image_desc)?;
@@ -744,6 +1096,44 @@ impl Swapchain {
         Ok(texture)
     }
 
+    /// Single sampled resolve target of the main pass, the source of the blit to the swapchain image.
+    fn create_scaled_target(
+        desc: &SwapchainDescriptor,
+        format: vk::Format,
+        extent: vk::Extent2D,
+    ) -> Result<VulkanTexture, DeviceError> {
+        let image = Image::new(&ImageDescriptor {
+            device: desc.device,
+            image_type: vk::ImageType::TYPE_2D,
+            format,
+            dimension: [extent.width, extent.height],
+            mip_levels: 1,
+            array_layers: 1,
+            flags: vk::ImageCreateFlags::empty(),
+            samples: vk::SampleCountFlags::TYPE_1,
+            tiling: vk::ImageTiling::OPTIMAL,
+            usage: vk::ImageUsageFlags::COLOR_ATTACHMENT | vk::ImageUsageFlags::TRANSFER_SRC,
+            sharing_mode: vk::SharingMode::EXCLUSIVE,
+            allocator: desc.allocator.clone(),
+        })?;
+        let image_view = ImageView::new_color_image_view(
+            Some("Scaled Target Image View"),
+            desc.device,
+            image.raw(),
+            format,
+            1,
+        )?;
+        VulkanTexture::new(VulkanTextureDescriptor {
+            adapter: &desc.adapter,
+            instance: &desc.instance,
+            device: desc.device,
+            command_buffer_allocator: &desc.command_buffer_allocator,
+            image,
+            image_view,
+            generate_mipmaps: false,
+        })
+    }
+
     fn create_color_objects(
         desc: &SwapchainDescriptor,
         format: vk::Format,
@@ -756,6 +1146,7 @@ impl Swapchain {
             dimension: [extent.width, extent.height],
             mip_levels: 1,
             array_layers: 1,
+            flags: vk::ImageCreateFlags::empty(),
             samples: desc.adapter.max_msaa_samples(),
             tiling: vk::ImageTiling::OPTIMAL,
             usage: vk::ImageUsageFlags::COLOR_ATTACHMENT
@@ -809,23 +1200,29 @@ impl SwapChainSupportDetail {
         })
     }
 
+    /// Fails if the surface reports no formats or present modes, e.g. after it is lost or on
+    /// driver bugs.
     pub fn get_ideal_swapchain_properties(
         &self,
         preferred_dimensions: [u32; 2],
-    ) -> SwapchainProperties {
-        let format = Self::choose_swapchain_format(&self.surface_formats);
+    ) -> Result<SwapchainProperties, DeviceError> {
+        let format = Self::choose_swapchain_format(&self.surface_formats)?;
+        if self.present_modes.is_empty() {
+            log::error!("Surface reports no supported present modes!");
+            return Err(DeviceError::Other("surface has no supported present modes"));
+        }
         let present_mode = Self::choose_swapchain_present_mode(&self.present_modes);
         let extent = Self::choose_swapchain_extent(&self.capabilities, preferred_dimensions);
-        SwapchainProperties {
+        Ok(SwapchainProperties {
             surface_format: format,
             present_mode,
             extent,
-        }
+        })
     }
 
     fn choose_swapchain_format(
-        available_formats: &Vec<vk::SurfaceFormatKHR>,
-    ) -> vk::SurfaceFormatKHR {
+        available_formats: &[vk::SurfaceFormatKHR],
+    ) -> Result<vk::SurfaceFormatKHR, DeviceError> {
         // check if list contains most widely used R8G8B8A8 format with nonlinear color space
         // if you want to use SRGB, check https://github.com/ocornut/imgui/issues/578
         // and https://github.com/ocornut/imgui/issues/4890
@@ -833,12 +1230,15 @@ impl SwapChainSupportDetail {
             if available_format.format == vk::Format::B8G8R8A8_UNORM
                 && available_format.color_space == vk::ColorSpaceKHR::SRGB_NONLINEAR
             {
-                return *available_format;
+                return Ok(*available_format);
             }
         }
 
         // return the first format from the list
-        return *available_formats.first().unwrap();
+        available_formats.first().copied().ok_or_else(|| {
+            log::error!("Surface reports no supported formats!");
+            DeviceError::Other("surface has no supported formats")
+        })
     }
 
     fn choose_swapchain_present_mode(
@@ -894,9 +1294,11 @@ impl SwapChainSupportDetail {
 impl Drop for Swapchain {
     fn drop(&mut self) {
         log::debug!("Swapchain start destroy!");
-        self.framebuffers
-            .iter()
-            .for_each(|e| self.device.destroy_framebuffer(*e));
+        self.framebuffer_cache.clear();
+        for command_buffer in self.command_buffers.iter_mut() {
+            self.command_buffer_allocator
+                .free_command_buffer(command_buffer);
+        }
 
         unsafe {
             self.loader.destroy_swapchain(self.raw, None);
@@ -904,3 +1306,95 @@ impl Drop for Swapchain {
         log::debug!("Swapchain destroyed.");
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scale_extent_halves_720p() {
+        let extent = vk::Extent2D {
+            width: 1280,
+            height: 720,
+        };
+        assert_eq!(
+            Swapchain::scale_extent(extent, 0.5),
+            vk::Extent2D {
+                width: 640,
+                height: 360,
+            }
+        );
+    }
+
+    #[test]
+    fn scale_extent_keeps_at_least_one_pixel() {
+        let extent = vk::Extent2D {
+            width: 1,
+            height: 3,
+        };
+        assert_eq!(
+            Swapchain::scale_extent(extent, 0.25),
+            vk::Extent2D {
+                width: 1,
+                height: 1,
+            }
+        );
+    }
+
+    fn support_detail(
+        surface_formats: Vec<vk::SurfaceFormatKHR>,
+        present_modes: Vec<vk::PresentModeKHR>,
+    ) -> SwapChainSupportDetail {
+        SwapChainSupportDetail {
+            capabilities: vk::SurfaceCapabilitiesKHR::default(),
+            surface_formats,
+            present_modes,
+        }
+    }
+
+    #[test]
+    fn empty_surface_formats_are_an_error() {
+        assert!(SwapChainSupportDetail::choose_swapchain_format(&[]).is_err());
+        let detail = support_detail(vec![], vec![vk::PresentModeKHR::FIFO]);
+        assert!(detail.get_ideal_swapchain_properties([1280, 720]).is_err());
+    }
+
+    #[test]
+    fn empty_present_modes_are_an_error() {
+        let format = vk::SurfaceFormatKHR {
+            format: vk::Format::B8G8R8A8_UNORM,
+            color_space: vk::ColorSpaceKHR::SRGB_NONLINEAR,
+        };
+        let detail = support_detail(vec![format], vec![]);
+        assert!(detail.get_ideal_swapchain_properties([1280, 720]).is_err());
+        let detail = support_detail(vec![format], vec![vk::PresentModeKHR::FIFO]);
+        assert!(detail.get_ideal_swapchain_properties([1280, 720]).is_ok());
+    }
+
+    #[test]
+    fn format_changed_ignores_a_resize_alone() {
+        let sdr = vk::SurfaceFormatKHR {
+            format: vk::Format::B8G8R8A8_UNORM,
+            color_space: vk::ColorSpaceKHR::SRGB_NONLINEAR,
+        };
+        let hdr = vk::SurfaceFormatKHR {
+            format: vk::Format::A2B10G10R10_UNORM_PACK32,
+            color_space: vk::ColorSpaceKHR::HDR10_ST2084_EXT,
+        };
+        let changed = SwapchainRecreated {
+            old_format: Some(sdr),
+            new_format: hdr,
+        };
+        assert!(changed.format_changed());
+        let resized = SwapchainRecreated {
+            old_format: Some(sdr),
+            new_format: sdr,
+        };
+        assert!(!resized.format_changed());
+        let created = SwapchainRecreated {
+            old_format: None,
+            new_format: sdr,
+        };
+        assert!(!created.format_changed());
+    }
+}
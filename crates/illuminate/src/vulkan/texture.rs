@@ -12,6 +12,7 @@ use crate::vulkan::adapter::Adapter;
 use crate::vulkan::buffer::{Buffer, StagingBufferDescriptor};
 use crate::vulkan::command_buffer_allocator::CommandBufferAllocator;
 use crate::vulkan::device::Device;
+use crate::vulkan::format::FormatExt;
 use crate::vulkan::image::{ColorImageDescriptor, Image};
 use crate::vulkan::image_view::ImageView;
 use crate::vulkan::instance::Instance;
@@ -127,12 +128,44 @@ impl VulkanTexture {
         texture
     }
 
+    /// The pixels must hold exactly one texel of `format` for each pixel of the extent, anything
+    /// else would make the staging copy read past the end of the data.
+    fn validate_pixel_bytes(
+        format: vk::Format,
+        extent: [u32; 2],
+        len: usize,
+    ) -> Result<(), DeviceError> {
+        let texel_size = match format.texel_size() {
+            Some(texel_size) => texel_size,
+            None => {
+                log::error!("Texel size of {:?} is unknown.", format);
+                return Err(DeviceError::NotSupport);
+            }
+        };
+        let expected = extent[0] as usize * extent[1] as usize * texel_size as usize;
+        if expected == 0 || len != expected {
+            log::error!(
+                "{} bytes of pixels do not fit a {}x{} {:?} texture, expected {} bytes.",
+                len,
+                extent[0],
+                extent[1],
+                format,
+                expected
+            );
+            return Err(DeviceError::Other(
+                "Pixel data does not match the texture extent",
+            ));
+        }
+        Ok(())
+    }
+
     pub fn new_from_pixels(
         desc: VulkanTextureFromPixelsDescriptor,
     ) -> Result<VulkanTexture, DeviceError> {
         let width = desc.extent[0];
         let height = desc.extent[1];
         let pixels = desc.bytes;
+        Self::validate_pixel_bytes(desc.format, desc.extent, pixels.len())?;
 
         let mip_levels = if desc.enable_mip_levels {
             Image::max_mip_levels(width, height)
@@ -232,13 +265,10 @@ impl VulkanTexture {
     ) -> Result<(), DeviceError> {
         log::info!("generate_mipmaps {}", mip_levels);
         let support_mip_levels = if mip_levels > 1 {
-            unsafe {
-                instance
-                    .raw()
-                    .get_physical_device_format_properties(adapter.raw(), format)
-                    .optimal_tiling_features
-                    .contains(vk::FormatFeatureFlags::SAMPLED_IMAGE_FILTER_LINEAR)
-            }
+            adapter
+                .format_properties(instance, format)
+                .optimal_tiling_features
+                .contains(vk::FormatFeatureFlags::SAMPLED_IMAGE_FILTER_LINEAR)
         } else {
             true
         };
@@ -370,6 +400,28 @@ impl VulkanTexture {
                 &[] as &[vk::BufferMemoryBarrier],
                 &[barrier],
             );
+            // 所有 mip 级别最终都处于 SHADER_READ_ONLY_OPTIMAL
+            device.set_image_layout(image, vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL);
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pixel_bytes_must_cover_every_texel() {
+        let format = vk::Format::R8G8B8A8_SRGB;
+        assert!(VulkanTexture::validate_pixel_bytes(format, [2, 2], 16).is_ok());
+        assert!(VulkanTexture::validate_pixel_bytes(format, [2, 2], 4).is_err());
+        assert!(VulkanTexture::validate_pixel_bytes(format, [2, 2], 20).is_err());
+        assert!(VulkanTexture::validate_pixel_bytes(format, [0, 2], 0).is_err());
+    }
+
+    #[test]
+    fn pixel_bytes_of_unknown_texel_size_are_rejected() {
+        let format = vk::Format::BC1_RGB_UNORM_BLOCK;
+        assert!(VulkanTexture::validate_pixel_bytes(format, [4, 4], 8).is_err());
+    }
+}
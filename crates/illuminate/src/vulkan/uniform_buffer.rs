@@ -1,5 +1,20 @@
+use std::marker::PhantomData;
+use std::mem::size_of;
+use std::rc::Rc;
+
+use ash::vk;
+use gpu_allocator::vulkan::Allocator;
+use gpu_allocator::MemoryLocation;
+use parking_lot::Mutex;
+use typed_builder::TypedBuilder;
+
 use math::Mat4;
 
+use crate::vulkan::adapter::Adapter;
+use crate::vulkan::buffer::{Buffer, BufferDescriptor};
+use crate::vulkan::device::Device;
+use crate::{DeviceError, Label};
+
 /// 统一缓冲区对象（UBO）
 #[repr(C)]
 #[derive(Copy, Clone, Default, Debug)]
@@ -18,3 +33,165 @@ pub struct UniformBufferObject {
 //     view: glm::Mat4,
 //     proj: glm::Mat4,
 // }
+
+#[derive(TypedBuilder)]
+pub struct UniformRingDescriptor<'a> {
+    pub label: Label<'a>,
+    pub device: &'a Rc<Device>,
+    pub adapter: &'a Adapter,
+    pub allocator: Rc<Mutex<Allocator>>,
+    pub frames_in_flight: u32,
+    /// Blocks per frame, e.g. one per drawn object, all addressed by the same descriptor set.
+    #[builder(default = 1)]
+    pub objects_per_frame: u32,
+}
+
+/// 每帧 `objects_per_frame` 个块的 uniform 环形缓冲区，使用 `UNIFORM_BUFFER_DYNAMIC` 描述符和动态偏移绑定，
+/// 避免覆盖 GPU 还在读取的数据。缓冲区是持久映射的，写入不需要 map/unmap。
+pub struct UniformRing<T> {
+    device: Rc<Device>,
+    buffer: Buffer,
+    block_size: u64,
+    frames_in_flight: u32,
+    objects_per_frame: u32,
+    _marker: PhantomData<T>,
+}
+
+impl<T> UniformRing<T> {
+    pub fn raw(&self) -> vk::Buffer {
+        self.buffer.raw()
+    }
+
+    pub fn block_size(&self) -> u64 {
+        self.block_size
+    }
+
+    pub fn new(desc: &UniformRingDescriptor) -> Result<Self, DeviceError> {
+        let block_size =
+            Self::aligned_block_size(desc.adapter.limits().min_uniform_buffer_offset_alignment);
+
+        let buffer_desc = BufferDescriptor {
+            label: desc.label.or(Some("Uniform Ring Buffer")),
+            device: desc.device,
+            allocator: desc.allocator.clone(),
+            element_size: block_size as usize,
+            element_count: desc.frames_in_flight * desc.objects_per_frame,
+            buffer_usage: vk::BufferUsageFlags::UNIFORM_BUFFER,
+            memory_location: MemoryLocation::CpuToGpu,
+        };
+        let buffer = Buffer::new(buffer_desc)?;
+
+        Ok(Self {
+            device: desc.device.clone(),
+            buffer,
+            block_size,
+            frames_in_flight: desc.frames_in_flight,
+            objects_per_frame: desc.objects_per_frame,
+            _marker: PhantomData,
+        })
+    }
+
+    /// `size_of::<T>()` rounded up to `min_uniform_buffer_offset_alignment`.
+    pub fn aligned_block_size(min_alignment: vk::DeviceSize) -> u64 {
+        let size = size_of::<T>() as u64;
+        if min_alignment == 0 {
+            return size;
+        }
+        debug_assert!(math::is_power_of_2(min_alignment));
+        (size + min_alignment - 1) & !(min_alignment - 1)
+    }
+
+    pub fn dynamic_offset(&self, frame: usize) -> u32 {
+        self.object_dynamic_offset(frame, 0)
+    }
+
+    pub fn object_dynamic_offset(&self, frame: usize, object: u32) -> u32 {
+        block_offset(
+            frame,
+            object,
+            self.frames_in_flight,
+            self.objects_per_frame,
+            self.block_size,
+        )
+    }
+
+    /// Writes `data` into the block owned by `frame`, returns the buffer and the dynamic offset
+    /// to pass to `cmd_bind_descriptor_sets`.
+    pub fn write(&mut self, frame: usize, data: &T) -> (vk::Buffer, u32) {
+        let offset = self.write_object(frame, 0, data);
+        (self.buffer.raw(), offset)
+    }
+
+    /// Writes the uniforms of `object` for `frame`, returns its dynamic offset.
+    pub fn write_object(&mut self, frame: usize, object: u32, data: &T) -> u32 {
+        let offset = self.object_dynamic_offset(frame, object);
+        self.buffer
+            .copy_memory_with_offset(offset as u64, std::slice::from_ref(data));
+        offset
+    }
+
+    /// The range covers a single block, the dynamic offset selects which one.
+    pub fn descriptor_buffer_info(&self) -> vk::DescriptorBufferInfo {
+        vk::DescriptorBufferInfo::builder()
+            .buffer(self.buffer.raw())
+            .offset(0)
+            .range(size_of::<T>() as u64)
+            .build()
+    }
+
+    /// Points the `UNIFORM_BUFFER_DYNAMIC` `binding` of `descriptor_set` at this ring.
+    pub fn update_descriptor_set(&self, descriptor_set: vk::DescriptorSet, binding: u32) {
+        let buffer_infos = [self.descriptor_buffer_info()];
+        let write = vk::WriteDescriptorSet::builder()
+            .dst_set(descriptor_set)
+            .dst_binding(binding)
+            .dst_array_element(0)
+            .descriptor_type(vk::DescriptorType::UNIFORM_BUFFER_DYNAMIC)
+            .buffer_info(&buffer_infos)
+            .build();
+        self.device.update_descriptor_sets(&[write], &[]);
+    }
+}
+
+/// Byte offset of the block of `object` in `frame`, frames wrap around `frames_in_flight`.
+fn block_offset(
+    frame: usize,
+    object: u32,
+    frames_in_flight: u32,
+    objects_per_frame: u32,
+    block_size: u64,
+) -> u32 {
+    debug_assert!(object < objects_per_frame);
+    let block = (frame as u32 % frames_in_flight) * objects_per_frame + object;
+    (block as u64 * block_size) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn per_frame_offsets_are_distinct_and_aligned() {
+        let min_alignment = 256;
+        let block_size = UniformRing::<UniformBufferObject>::aligned_block_size(min_alignment);
+        assert_eq!(block_size, 256);
+
+        let offsets: Vec<_> = (0..3)
+            .map(|frame| block_offset(frame, 0, 3, 1, block_size))
+            .collect();
+        assert_eq!(offsets, [0, 256, 512]);
+        assert!(offsets
+            .iter()
+            .all(|&offset| offset as u64 % min_alignment == 0));
+        // 第四帧重新使用第一帧的块
+        assert_eq!(block_offset(3, 0, 3, 1, block_size), 0);
+    }
+
+    #[test]
+    fn objects_of_a_frame_are_consecutive_blocks() {
+        let block_size = UniformRing::<UniformBufferObject>::aligned_block_size(64);
+        assert_eq!(block_size, 128);
+        assert_eq!(block_offset(1, 0, 2, 3, block_size), 384);
+        assert_eq!(block_offset(1, 2, 2, 3, block_size), 640);
+    }
+}
@@ -0,0 +1,309 @@
+use alloc::rc::Rc;
+
+use ash::vk;
+use gpu_allocator::vulkan::Allocator;
+use parking_lot::Mutex;
+use typed_builder::TypedBuilder;
+
+use crate::vulkan::buffer::{Buffer, StagingBufferDescriptor};
+use crate::vulkan::command_buffer::CommandBuffer;
+use crate::vulkan::command_buffer_allocator::CommandBufferAllocator;
+use crate::vulkan::device::Device;
+use crate::DeviceError;
+
+#[derive(Clone, TypedBuilder)]
+pub struct UploadQueueDescriptor<'a> {
+    pub device: &'a Rc<Device>,
+    pub allocator: Rc<Mutex<Allocator>>,
+    pub queue_family_index: u32,
+    /// Queue of `queue_family_index` that the copies are submitted to.
+    pub queue: vk::Queue,
+    /// Family of the queue that uses the uploaded buffers, usually the graphics family.
+    pub owner_queue_family_index: u32,
+    /// Queue of `owner_queue_family_index`, acquires the ownership of the buffers when the
+    /// families differ.
+    pub owner_queue: vk::Queue,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct UploadHandle(u64);
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum UploadStage {
+    /// The copy, followed by the ownership release if the families differ.
+    Copy,
+    /// The ownership acquire on the owner queue.
+    Acquire,
+}
+
+struct PendingUpload {
+    handle: UploadHandle,
+    stage: UploadStage,
+    fence: vk::Fence,
+    command_buffer: CommandBuffer,
+    destination: vk::Buffer,
+    // 拷贝完成前必须保持 staging buffer 存活
+    _staging_buffer: Option<Buffer>,
+}
+
+/// Uploads buffer data on the transfer queue without waiting for the copy to finish.
+///
+/// 每次上传使用独立的 command buffer 和 fence，调用方通过 `is_upload_complete` 轮询，
+/// 目标 buffer 在上传完成前必须保持存活。
+///
+/// When the transfer family differs from the owner family, the `EXCLUSIVE` destination is
+/// released by the copy and acquired on the owner queue once the copy has finished, an upload
+/// is only complete after the acquire.
+pub struct UploadQueue {
+    device: Rc<Device>,
+    allocator: Rc<Mutex<Allocator>>,
+    command_buffer_allocator: CommandBufferAllocator,
+    /// Records the ownership acquires, `None` if the transfer family is the owner family.
+    acquire_command_buffer_allocator: Option<CommandBufferAllocator>,
+    queue_family_index: u32,
+    owner_queue_family_index: u32,
+    pending: Vec<PendingUpload>,
+    next_handle: u64,
+}
+
+impl UploadQueue {
+    pub fn new(desc: &UploadQueueDescriptor) -> Result<Self, DeviceError> {
+        let create_info = vk::CommandPoolCreateInfo::builder()
+            .flags(vk::CommandPoolCreateFlags::TRANSIENT)
+            .queue_family_index(desc.queue_family_index)
+            .build();
+        let command_pool = desc.device.create_command_pool(&create_info)?;
+        let acquire_command_buffer_allocator =
+            if desc.queue_family_index != desc.owner_queue_family_index {
+                let create_info = vk::CommandPoolCreateInfo::builder()
+                    .flags(vk::CommandPoolCreateFlags::TRANSIENT)
+                    .queue_family_index(desc.owner_queue_family_index)
+                    .build();
+                let command_pool = desc.device.create_command_pool(&create_info)?;
+                Some(CommandBufferAllocator::new(
+                    desc.device,
+                    command_pool,
+                    desc.owner_queue,
+                ))
+            } else {
+                None
+            };
+        log::debug!("Upload Queue created.");
+        Ok(Self {
+            device: desc.device.clone(),
+            allocator: desc.allocator.clone(),
+            command_buffer_allocator: CommandBufferAllocator::new(
+                desc.device,
+                command_pool,
+                desc.queue,
+            ),
+            acquire_command_buffer_allocator,
+            queue_family_index: desc.queue_family_index,
+            owner_queue_family_index: desc.owner_queue_family_index,
+            pending: Vec::new(),
+            next_handle: 0,
+        })
+    }
+
+    /// Records a copy of `data` into `destination` and submits it to the transfer queue.
+    pub fn enqueue_upload<T>(
+        &mut self,
+        data: &[T],
+        destination: &Buffer,
+    ) -> Result<UploadHandle, DeviceError> {
+        debug_assert!(destination
+            .buffer_usage()
+            .contains(vk::BufferUsageFlags::TRANSFER_DST));
+        self.collect_completed()?;
+
+        let staging_buffer = Buffer::new_staging_buffer(&StagingBufferDescriptor {
+            label: Some("Upload Staging Buffer"),
+            device: &self.device,
+            allocator: self.allocator.clone(),
+            elements: data,
+            command_buffer_allocator: &self.command_buffer_allocator,
+        })?;
+
+        let allocator = &self.command_buffer_allocator;
+        let mut command_buffer = allocator.allocate_and_begin_single_use()?;
+        let regions = [vk::BufferCopy::builder()
+            .size(std::mem::size_of_val(data) as u64)
+            .build()];
+        self.device.cmd_copy_buffer(
+            command_buffer.raw(),
+            staging_buffer.raw(),
+            destination.raw(),
+            &regions,
+        );
+        if self.acquire_command_buffer_allocator.is_some() {
+            // 释放所有权，由 collect_completed 在拷贝完成后于所属队列上获取
+            let release =
+                self.ownership_barrier(destination.raw(), vk::AccessFlags::TRANSFER_WRITE);
+            self.device.cmd_pipeline_barrier(
+                command_buffer.raw(),
+                vk::PipelineStageFlags::TRANSFER,
+                vk::PipelineStageFlags::BOTTOM_OF_PIPE,
+                vk::DependencyFlags::empty(),
+                &[],
+                &[release],
+                &[],
+            );
+        }
+        let fence = self.submit(allocator, &mut command_buffer)?;
+
+        let handle = UploadHandle(self.next_handle);
+        self.next_handle += 1;
+        self.pending.push(PendingUpload {
+            handle,
+            stage: UploadStage::Copy,
+            fence,
+            command_buffer,
+            destination: destination.raw(),
+            _staging_buffer: Some(staging_buffer),
+        });
+        Ok(handle)
+    }
+
+    /// The release and the acquire barrier must describe the same ownership transfer, only the
+    /// access masks of the other side are ignored.
+    fn ownership_barrier(
+        &self,
+        buffer: vk::Buffer,
+        src_access_mask: vk::AccessFlags,
+    ) -> vk::BufferMemoryBarrier {
+        vk::BufferMemoryBarrier::builder()
+            .src_access_mask(src_access_mask)
+            .dst_access_mask(vk::AccessFlags::empty())
+            .src_queue_family_index(self.queue_family_index)
+            .dst_queue_family_index(self.owner_queue_family_index)
+            .buffer(buffer)
+            .offset(0)
+            .size(vk::WHOLE_SIZE)
+            .build()
+    }
+
+    fn submit(
+        &self,
+        allocator: &CommandBufferAllocator,
+        command_buffer: &mut CommandBuffer,
+    ) -> Result<vk::Fence, DeviceError> {
+        allocator.end_command_buffer(command_buffer)?;
+        let fence = self.device.create_fence(&vk::FenceCreateInfo::default())?;
+        let command_buffers = [command_buffer.raw()];
+        let submit_info = vk::SubmitInfo::builder()
+            .command_buffers(&command_buffers)
+            .build();
+        self.device
+            .queue_submit(allocator.queue(), &[submit_info], fence)?;
+        allocator.update_submitted_command_buffer(command_buffer);
+        Ok(fence)
+    }
+
+    /// Submits the ownership acquire of a finished copy to the owner queue. The copy's fence
+    /// has signaled, so the release is known to have executed before the acquire is submitted.
+    fn acquire(
+        &self,
+        handle: UploadHandle,
+        destination: vk::Buffer,
+    ) -> Result<PendingUpload, DeviceError> {
+        let allocator = self.acquire_command_buffer_allocator.as_ref().unwrap();
+        let mut command_buffer = allocator.allocate_and_begin_single_use()?;
+        let mut acquire = self.ownership_barrier(destination, vk::AccessFlags::empty());
+        acquire.dst_access_mask = vk::AccessFlags::MEMORY_READ;
+        self.device.cmd_pipeline_barrier(
+            command_buffer.raw(),
+            vk::PipelineStageFlags::TOP_OF_PIPE,
+            vk::PipelineStageFlags::ALL_COMMANDS,
+            vk::DependencyFlags::empty(),
+            &[],
+            &[acquire],
+            &[],
+        );
+        let fence = self.submit(allocator, &mut command_buffer)?;
+        Ok(PendingUpload {
+            handle,
+            stage: UploadStage::Acquire,
+            fence,
+            command_buffer,
+            destination,
+            _staging_buffer: None,
+        })
+    }
+
+    /// Handles that are no longer pending are treated as completed.
+    pub fn is_upload_complete(&self, handle: UploadHandle) -> Result<bool, DeviceError> {
+        match self.pending.iter().find(|upload| upload.handle == handle) {
+            Some(upload) if upload.stage == UploadStage::Copy && self.needs_acquire() => Ok(false),
+            Some(upload) => self.device.get_fence_status(upload.fence),
+            None => Ok(true),
+        }
+    }
+
+    fn needs_acquire(&self) -> bool {
+        self.acquire_command_buffer_allocator.is_some()
+    }
+
+    pub fn pending_count(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Frees the staging buffers and command buffers of finished uploads, and submits the
+    /// ownership acquires of finished copies.
+    pub fn collect_completed(&mut self) -> Result<(), DeviceError> {
+        let mut acquires = Vec::new();
+        let mut index = 0;
+        while index < self.pending.len() {
+            if self.device.get_fence_status(self.pending[index].fence)? {
+                let upload = self.pending.swap_remove(index);
+                if upload.stage == UploadStage::Copy && self.needs_acquire() {
+                    acquires.push((upload.handle, upload.destination));
+                }
+                self.release(upload);
+            } else {
+                index += 1;
+            }
+        }
+        for (handle, destination) in acquires {
+            let upload = self.acquire(handle, destination)?;
+            self.pending.push(upload);
+        }
+        Ok(())
+    }
+
+    /// Blocks until every pending upload has finished, including the ownership acquires.
+    pub fn wait_all(&mut self) -> Result<(), DeviceError> {
+        while !self.pending.is_empty() {
+            let fences = self
+                .pending
+                .iter()
+                .map(|upload| upload.fence)
+                .collect::<Vec<_>>();
+            self.device.wait_for_fence(&fences, true, u64::MAX)?;
+            self.collect_completed()?;
+        }
+        Ok(())
+    }
+
+    fn release(&self, mut upload: PendingUpload) {
+        let allocator = match upload.stage {
+            UploadStage::Copy => &self.command_buffer_allocator,
+            UploadStage::Acquire => self.acquire_command_buffer_allocator.as_ref().unwrap(),
+        };
+        allocator.free_command_buffer(&mut upload.command_buffer);
+        self.device.destroy_fence(upload.fence);
+    }
+}
+
+impl Drop for UploadQueue {
+    fn drop(&mut self) {
+        if let Err(e) = self.wait_all() {
+            log::error!("failed to wait pending uploads. Err: {}", e);
+        }
+        self.device
+            .destroy_command_pool(self.command_buffer_allocator.command_pool());
+        if let Some(allocator) = &self.acquire_command_buffer_allocator {
+            self.device.destroy_command_pool(allocator.command_pool());
+        }
+        log::debug!("Upload Queue destroyed.");
+    }
+}
@@ -36,6 +36,31 @@ pub fn vk_to_string(raw_string_array: &[c_char]) -> String {
         .to_owned()
 }
 
+/// Keeps the requested names that are available and logs them, dropped names are marked with `✗`.
+pub fn select_available_names<'a>(
+    kind: &str,
+    requested: &[&'a CStr],
+    available: &[&CStr],
+) -> Vec<&'a CStr> {
+    log::info!("Enabled {}:", kind);
+    let mut enabled = Vec::with_capacity(requested.len());
+    for &name in requested {
+        if available.contains(&name) {
+            log::info!("  * {}", name.to_string_lossy());
+            enabled.push(name);
+        } else {
+            log::warn!(
+                "  ✗ {} (requested but not available, dropped)",
+                name.to_string_lossy()
+            );
+        }
+    }
+    if enabled.is_empty() {
+        log::info!("  (none)");
+    }
+    enabled
+}
+
 pub fn get_queue_family_indices(
     instance: &ash::Instance,
     adapter: vk::PhysicalDevice,
@@ -44,28 +69,30 @@ pub fn get_queue_family_indices(
     let queue_families = unsafe { instance.get_physical_device_queue_family_properties(adapter) };
     let mut indices = QueueFamilyIndices::default();
     for (i, queue_family) in queue_families.iter().enumerate() {
-        if indices.is_complete() {
-            break;
-        }
         let index = i as u32;
-        if queue_family.queue_flags.contains(vk::QueueFlags::GRAPHICS) {
+        let flags = queue_family.queue_flags;
+        if flags.contains(vk::QueueFlags::GRAPHICS) && indices.graphics_family.is_none() {
             indices.graphics_family = Some(index);
         };
-        if queue_family.queue_flags.contains(vk::QueueFlags::COMPUTE) {
+        if flags.contains(vk::QueueFlags::COMPUTE) && indices.compute_family.is_none() {
             indices.compute_family = Some(index);
         };
-        if queue_family.queue_flags.contains(vk::QueueFlags::TRANSFER) {
+        // 优先使用没有图形能力的专用传输队列族，拷贝才能与渲染并行
+        if flags.contains(vk::QueueFlags::TRANSFER)
+            && (indices.transfer_family.is_none() || !flags.contains(vk::QueueFlags::GRAPHICS))
+        {
             indices.transfer_family = Some(index);
         };
-        let support_present = unsafe {
-            surface
-                .loader()
-                .get_physical_device_surface_support(adapter, index, surface.raw())
-                .map_err(crate::DeviceError::VulkanError)?
-        };
-
-        if support_present {
-            indices.present_family = Some(index);
+        if indices.present_family.is_none() {
+            let support_present = unsafe {
+                surface
+                    .loader()
+                    .get_physical_device_surface_support(adapter, index, surface.raw())
+                    .map_err(crate::DeviceError::VulkanError)?
+            };
+            if support_present {
+                indices.present_family = Some(index);
+            }
         }
     }
     Ok(indices)
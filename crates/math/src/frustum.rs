@@ -0,0 +1,92 @@
+use nalgebra_glm::{Mat4, Vec3, Vec4};
+
+/// The six planes of a view frustum, normals point inwards.
+///
+/// Each plane is stored as `(normal, distance)`, a point `p` is inside when `dot(normal, p) + distance >= 0`.
+#[derive(Copy, Clone, Debug)]
+pub struct Frustum {
+    pub planes: [Vec4; 6],
+}
+
+impl Frustum {
+    /// Extracts the planes from a projection * view matrix (Gribb-Hartmann).
+    ///
+    /// Expects a zero-to-one depth range, e.g. `perspective_rh_zo`, as used by the Vulkan renderer.
+    pub fn from_view_projection(vp: Mat4) -> Self {
+        let row = |i: usize| -> Vec4 { vp.row(i).transpose() };
+        let (r0, r1, r2, r3) = (row(0), row(1), row(2), row(3));
+        let planes = [
+            r3 + r0, // left
+            r3 - r0, // right
+            r3 + r1, // bottom
+            r3 - r1, // top
+            r2,      // near
+            r3 - r2, // far
+        ]
+        .map(|plane| {
+            let length = plane.xyz().norm();
+            if length > 0.0 {
+                plane / length
+            } else {
+                plane
+            }
+        });
+        Self { planes }
+    }
+
+    /// `false` only if the box is completely outside one of the planes, so boxes near the corners may be kept.
+    pub fn intersects_aabb(&self, min: Vec3, max: Vec3) -> bool {
+        self.planes.iter().all(|plane| {
+            // 取沿法线方向最远的顶点，它在平面外则整个包围盒都在平面外
+            let positive = Vec3::new(
+                if plane.x >= 0.0 { max.x } else { min.x },
+                if plane.y >= 0.0 { max.y } else { min.y },
+                if plane.z >= 0.0 { max.z } else { min.z },
+            );
+            plane.xyz().dot(&positive) + plane.w >= 0.0
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn playground_frustum() -> Frustum {
+        let view = nalgebra_glm::look_at(
+            &Vec3::new(2.0, 2.0, 2.0),
+            &Vec3::zeros(),
+            &Vec3::new(0.0, 0.0, 1.0),
+        );
+        let projection = nalgebra_glm::perspective_rh_zo(16.0 / 9.0, 0.8, 0.1, 10.0);
+        Frustum::from_view_projection(projection * view)
+    }
+
+    #[test]
+    fn box_in_front_of_the_camera_is_visible() {
+        let frustum = playground_frustum();
+        assert!(frustum.intersects_aabb(Vec3::new(-0.5, -0.5, -0.5), Vec3::new(0.5, 0.5, 0.5)));
+    }
+
+    #[test]
+    fn box_behind_the_camera_is_culled() {
+        let frustum = playground_frustum();
+        assert!(!frustum.intersects_aabb(Vec3::new(3.0, 3.0, 3.0), Vec3::new(4.0, 4.0, 4.0)));
+    }
+
+    #[test]
+    fn box_beyond_the_far_plane_is_culled() {
+        let frustum = playground_frustum();
+        let center = Vec3::new(-8.0, -8.0, -8.0);
+        let half = Vec3::new(0.5, 0.5, 0.5);
+        assert!(!frustum.intersects_aabb(center - half, center + half));
+    }
+
+    #[test]
+    fn planes_are_normalized() {
+        let frustum = playground_frustum();
+        for plane in frustum.planes {
+            assert!((plane.xyz().norm() - 1.0).abs() < 1e-5);
+        }
+    }
+}
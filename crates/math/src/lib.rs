@@ -1,9 +1,15 @@
 pub use nalgebra_glm::*;
 
+pub use frustum::*;
+pub use ray::*;
 pub use rect::*;
+pub use transform::*;
 pub use vertex::*;
 
+mod frustum;
+mod ray;
 mod rect;
+mod transform;
 mod vertex;
 
 pub const PI: f32 = std::f32::consts::PI;
@@ -28,11 +34,21 @@ pub fn is_power_of_2(value: u64) -> bool {
     (value != 0) && ((value & (value - 1)) == 0)
 }
 
+/// `a + (b - a) * t`, `t` is not clamped.
+pub fn lerp_vec3(a: &Vec3, b: &Vec3, t: f32) -> Vec3 {
+    nalgebra_glm::lerp(a, b, t)
+}
+
+/// Spherical interpolation of rotations along the shorter arc, `t` in `[0, 1]`.
+pub fn slerp_quat(a: &Quat, b: &Quat, t: f32) -> Quat {
+    nalgebra_glm::quat_slerp(a, b, t)
+}
+
 pub mod prelude {
     #[doc(hidden)]
     pub use crate::{
         mat2, mat2x2, mat2x3, mat2x4, mat3, mat3x2, mat3x3, mat3x4, mat4, mat4x2, mat4x3, mat4x4,
-        quat, vec2, vec3, vec4, BVec2, BVec3, BVec4, IVec2, IVec3, IVec4, Mat2, Mat3, Mat4, Quat,
-        Rect2D, UVec2, UVec3, UVec4, Vec2, Vec3, Vec4, Vertex3D,
+        quat, vec2, vec3, vec4, BVec2, BVec3, BVec4, Frustum, IVec2, IVec3, IVec4, Mat2, Mat3,
+        Mat4, Quat, Ray, Rect2D, Transform, UVec2, UVec3, UVec4, Vec2, Vec3, Vec4, Vertex3D,
     };
 }
@@ -0,0 +1,146 @@
+use nalgebra_glm::{Mat4, Vec2, Vec3, Vec4};
+
+/// A half-line for picking, hits are returned as the distance `t` along `dir`, i.e. at `origin + dir * t`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Ray {
+    pub origin: Vec3,
+    pub dir: Vec3,
+}
+
+impl Ray {
+    pub fn new(origin: Vec3, dir: Vec3) -> Self {
+        Self { origin, dir }
+    }
+
+    /// Builds a picking ray through a cursor position in normalized device coordinates.
+    ///
+    /// Expects a zero-to-one depth range, the ray starts on the near plane and `dir` is normalized.
+    pub fn from_screen(ndc: Vec2, inv_view_proj: Mat4) -> Self {
+        let unproject = |depth: f32| -> Vec3 {
+            let point = inv_view_proj * Vec4::new(ndc.x, ndc.y, depth, 1.0);
+            point.xyz() / point.w
+        };
+        let near = unproject(0.0);
+        let far = unproject(1.0);
+        Self::new(near, (far - near).normalize())
+    }
+
+    pub fn at(&self, t: f32) -> Vec3 {
+        self.origin + self.dir * t
+    }
+
+    /// Slab method, returns the entry distance, or 0.0 if the origin is inside the box.
+    pub fn intersect_aabb(&self, min: Vec3, max: Vec3) -> Option<f32> {
+        let mut t_min = 0.0_f32;
+        let mut t_max = f32::INFINITY;
+        for axis in 0..3 {
+            // 方向分量为 0 时倒数为无穷大，原点在平板外的话区间为空
+            let inv_dir = 1.0 / self.dir[axis];
+            let t0 = (min[axis] - self.origin[axis]) * inv_dir;
+            let t1 = (max[axis] - self.origin[axis]) * inv_dir;
+            t_min = t_min.max(t0.min(t1));
+            t_max = t_max.min(t0.max(t1));
+            if t_max < t_min {
+                return None;
+            }
+        }
+        Some(t_min)
+    }
+
+    /// Möller–Trumbore, both faces are hit.
+    pub fn intersect_triangle(&self, v0: Vec3, v1: Vec3, v2: Vec3) -> Option<f32> {
+        const EPSILON: f32 = 1e-7;
+        let edge1 = v1 - v0;
+        let edge2 = v2 - v0;
+        let p = self.dir.cross(&edge2);
+        let det = edge1.dot(&p);
+        // 射线与三角形平行
+        if det.abs() < EPSILON {
+            return None;
+        }
+        let inv_det = 1.0 / det;
+        let s = self.origin - v0;
+        let u = s.dot(&p) * inv_det;
+        if !(0.0..=1.0).contains(&u) {
+            return None;
+        }
+        let q = s.cross(&edge1);
+        let v = self.dir.dot(&q) * inv_det;
+        if v < 0.0 || u + v > 1.0 {
+            return None;
+        }
+        let t = edge2.dot(&q) * inv_det;
+        (t >= 0.0).then_some(t)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn view_proj() -> Mat4 {
+        let view = nalgebra_glm::look_at(
+            &Vec3::new(2.0, 2.0, 2.0),
+            &Vec3::zeros(),
+            &Vec3::new(0.0, 0.0, 1.0),
+        );
+        let projection = nalgebra_glm::perspective_rh_zo(16.0 / 9.0, 0.8, 0.1, 10.0);
+        projection * view
+    }
+
+    #[test]
+    fn screen_ray_passes_through_the_projected_point() {
+        let view_proj = view_proj();
+        let point = Vec3::new(0.3, -0.2, 0.5);
+        let clip = view_proj * Vec4::new(point.x, point.y, point.z, 1.0);
+        let ndc = clip.xy() / clip.w;
+
+        let ray = Ray::from_screen(ndc, view_proj.try_inverse().unwrap());
+        let t = (point - ray.origin).dot(&ray.dir);
+        assert!(t > 0.0);
+        assert!((ray.at(t) - point).norm() < 1e-4);
+        assert!((ray.dir.norm() - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn screen_ray_starts_on_the_near_plane() {
+        let view_proj = view_proj();
+        let ray = Ray::from_screen(Vec2::zeros(), view_proj.try_inverse().unwrap());
+        let clip = view_proj * Vec4::new(ray.origin.x, ray.origin.y, ray.origin.z, 1.0);
+        assert!((clip.z / clip.w).abs() < 1e-4);
+        // 屏幕中心的射线沿视线方向
+        let forward = (Vec3::zeros() - Vec3::new(2.0, 2.0, 2.0)).normalize();
+        assert!((ray.dir - forward).norm() < 1e-4);
+    }
+
+    #[test]
+    fn ray_down_negative_z_hits_unit_box() {
+        let ray = Ray::new(Vec3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0));
+        let t = ray
+            .intersect_aabb(Vec3::new(-0.5, -0.5, -0.5), Vec3::new(0.5, 0.5, 0.5))
+            .unwrap();
+        assert!((t - 4.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn offset_ray_misses_unit_box() {
+        let ray = Ray::new(Vec3::new(2.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0));
+        assert_eq!(
+            ray.intersect_aabb(Vec3::new(-0.5, -0.5, -0.5), Vec3::new(0.5, 0.5, 0.5)),
+            None
+        );
+    }
+
+    #[test]
+    fn ray_hits_and_misses_triangle() {
+        let v0 = Vec3::new(-1.0, -1.0, 0.0);
+        let v1 = Vec3::new(1.0, -1.0, 0.0);
+        let v2 = Vec3::new(0.0, 1.0, 0.0);
+        let hit = Ray::new(Vec3::new(0.0, 0.0, 3.0), Vec3::new(0.0, 0.0, -1.0));
+        let t = hit.intersect_triangle(v0, v1, v2).unwrap();
+        assert!((t - 3.0).abs() < 1e-6);
+
+        let miss = Ray::new(Vec3::new(1.0, 1.0, 3.0), Vec3::new(0.0, 0.0, -1.0));
+        assert_eq!(miss.intersect_triangle(v0, v1, v2), None);
+    }
+}
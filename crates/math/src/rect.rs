@@ -1,5 +1,5 @@
 #[repr(C)]
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub struct Rect2D {
     pub x: f32,
     pub y: f32,
@@ -16,4 +16,143 @@ impl Rect2D {
             height,
         }
     }
+
+    /// The rect covering a whole `width` x `height` extent.
+    pub fn full(width: f32, height: f32) -> Self {
+        Self::new(0.0, 0.0, width, height)
+    }
+
+    pub fn right(&self) -> f32 {
+        self.x + self.width
+    }
+
+    pub fn bottom(&self) -> f32 {
+        self.y + self.height
+    }
+
+    /// The overlapping area, `None` if the rects don't overlap.
+    pub fn intersect(&self, other: &Self) -> Option<Self> {
+        let x = self.x.max(other.x);
+        let y = self.y.max(other.y);
+        let right = self.right().min(other.right());
+        let bottom = self.bottom().min(other.bottom());
+        if right <= x || bottom <= y {
+            return None;
+        }
+        Some(Self::new(x, y, right - x, bottom - y))
+    }
+
+    /// The smallest rect containing both rects.
+    pub fn union(&self, other: &Self) -> Self {
+        let x = self.x.min(other.x);
+        let y = self.y.min(other.y);
+        let right = self.right().max(other.right());
+        let bottom = self.bottom().max(other.bottom());
+        Self::new(x, y, right - x, bottom - y)
+    }
+
+    /// Clamps the rect inside a `width` x `height` extent, e.g. a scissor after the framebuffer was resized.
+    /// A rect fully outside the extent becomes empty.
+    pub fn clamp_to_extent(&self, width: f32, height: f32) -> Self {
+        self.intersect(&Self::full(width, height))
+            .unwrap_or_else(|| {
+                Self::new(
+                    self.x.clamp(0.0, width),
+                    self.y.clamp(0.0, height),
+                    0.0,
+                    0.0,
+                )
+            })
+    }
+
+    /// The largest rect of `aspect` (width / height) centered in a `width` x `height` extent,
+    /// the rest of the extent is left for black bars.
+    pub fn letterbox(aspect: f32, width: f32, height: f32) -> Self {
+        if aspect <= 0.0 || width <= 0.0 || height <= 0.0 {
+            return Self::full(width, height);
+        }
+        if width / height > aspect {
+            // 窗口更宽，左右留黑边
+            let fitted_width = height * aspect;
+            Self::new((width - fitted_width) * 0.5, 0.0, fitted_width, height)
+        } else {
+            // 窗口更高，上下留黑边
+            let fitted_height = width / aspect;
+            Self::new(0.0, (height - fitted_height) * 0.5, width, fitted_height)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn intersect_of_overlapping_rects() {
+        let a = Rect2D::new(0.0, 0.0, 100.0, 50.0);
+        let b = Rect2D::new(60.0, 20.0, 100.0, 100.0);
+        assert_eq!(a.intersect(&b), Some(Rect2D::new(60.0, 20.0, 40.0, 30.0)));
+        assert_eq!(a.intersect(&b), b.intersect(&a));
+    }
+
+    #[test]
+    fn intersect_of_touching_rects_is_none() {
+        let a = Rect2D::new(0.0, 0.0, 10.0, 10.0);
+        let b = Rect2D::new(10.0, 0.0, 10.0, 10.0);
+        assert_eq!(a.intersect(&b), None);
+    }
+
+    #[test]
+    fn union_contains_both_rects() {
+        let a = Rect2D::new(0.0, 0.0, 10.0, 10.0);
+        let b = Rect2D::new(20.0, -5.0, 5.0, 5.0);
+        assert_eq!(a.union(&b), Rect2D::new(0.0, -5.0, 25.0, 15.0));
+    }
+
+    #[test]
+    fn clamp_to_extent_cuts_off_the_outside() {
+        let rect = Rect2D::new(-10.0, 20.0, 100.0, 100.0);
+        assert_eq!(
+            rect.clamp_to_extent(80.0, 60.0),
+            Rect2D::new(0.0, 20.0, 80.0, 40.0)
+        );
+    }
+
+    #[test]
+    fn clamp_to_extent_of_a_rect_outside_is_empty() {
+        let rect = Rect2D::new(200.0, -50.0, 10.0, 10.0);
+        assert_eq!(
+            rect.clamp_to_extent(100.0, 100.0),
+            Rect2D::new(100.0, 0.0, 0.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn letterbox_pillarboxes_a_wider_extent() {
+        let rect = Rect2D::letterbox(4.0 / 3.0, 1600.0, 900.0);
+        assert_eq!(rect, Rect2D::new(200.0, 0.0, 1200.0, 900.0));
+    }
+
+    #[test]
+    fn letterbox_adds_bars_to_a_taller_extent() {
+        let rect = Rect2D::letterbox(16.0 / 9.0, 1600.0, 1200.0);
+        assert_eq!(rect, Rect2D::new(0.0, 150.0, 1600.0, 900.0));
+    }
+
+    #[test]
+    fn letterbox_of_a_matching_aspect_fills_the_extent() {
+        assert_eq!(
+            Rect2D::letterbox(16.0 / 9.0, 1920.0, 1080.0),
+            Rect2D::full(1920.0, 1080.0)
+        );
+    }
+
+    #[test]
+    fn letterbox_of_a_degenerate_input_is_the_full_extent() {
+        assert_eq!(
+            Rect2D::letterbox(0.0, 800.0, 600.0),
+            Rect2D::full(800.0, 600.0)
+        );
+        assert_eq!(Rect2D::letterbox(1.0, 800.0, 0.0), Rect2D::full(800.0, 0.0));
+    }
 }
@@ -0,0 +1,183 @@
+use nalgebra_glm as glm;
+use nalgebra_glm::{Mat3, Mat4, Quat, Vec3};
+
+/// Translation, rotation and scale of a scene node, applied as scale, then rotation, then translation.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Transform {
+    pub translation: Vec3,
+    pub rotation: Quat,
+    pub scale: Vec3,
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl Transform {
+    pub const IDENTITY: Self = Self {
+        translation: Vec3::new(0.0, 0.0, 0.0),
+        rotation: Quat::new(1.0, 0.0, 0.0, 0.0),
+        scale: Vec3::new(1.0, 1.0, 1.0),
+    };
+
+    pub fn from_translation(translation: Vec3) -> Self {
+        Self {
+            translation,
+            ..Self::IDENTITY
+        }
+    }
+
+    pub fn from_rotation(rotation: Quat) -> Self {
+        Self {
+            rotation,
+            ..Self::IDENTITY
+        }
+    }
+
+    pub fn from_scale(scale: Vec3) -> Self {
+        Self {
+            scale,
+            ..Self::IDENTITY
+        }
+    }
+
+    /// `T * R * S`
+    pub fn to_matrix(&self) -> Mat4 {
+        let translation = glm::translation(&self.translation);
+        let rotation = glm::quat_to_mat4(&self.rotation);
+        let scale = glm::scaling(&self.scale);
+        translation * rotation * scale
+    }
+
+    /// Decomposes an affine matrix without shear or projection.
+    ///
+    /// 负缩放无法与旋转区分，行列式为负时把 x 轴的缩放取反。
+    pub fn from_matrix(matrix: Mat4) -> Self {
+        let translation = matrix.column(3).xyz();
+        let mut x_axis: Vec3 = matrix.column(0).xyz();
+        let y_axis: Vec3 = matrix.column(1).xyz();
+        let z_axis: Vec3 = matrix.column(2).xyz();
+        let mut scale = Vec3::new(x_axis.norm(), y_axis.norm(), z_axis.norm());
+        if x_axis.dot(&y_axis.cross(&z_axis)) < 0.0 {
+            scale.x = -scale.x;
+            x_axis = -x_axis;
+        }
+        let safe_div = |axis: Vec3, length: f32| {
+            if length != 0.0 {
+                axis / length
+            } else {
+                axis
+            }
+        };
+        let rotation = Mat3::from_columns(&[
+            safe_div(x_axis, scale.x.abs()),
+            safe_div(y_axis, scale.y),
+            safe_div(z_axis, scale.z),
+        ]);
+        Self {
+            translation,
+            rotation: glm::quat_normalize(&glm::mat3_to_quat(&rotation)),
+            scale,
+        }
+    }
+
+    /// Interpolates each component separately, the rotation with `slerp_quat`, e.g. between animation keyframes.
+    pub fn lerp(&self, other: &Self, t: f32) -> Self {
+        Self {
+            translation: crate::lerp_vec3(&self.translation, &other.translation, t),
+            rotation: crate::slerp_quat(&self.rotation, &other.rotation, t),
+            scale: crate::lerp_vec3(&self.scale, &other.scale, t),
+        }
+    }
+
+    pub fn transform_point(&self, point: Vec3) -> Vec3 {
+        self.transform_vector(point) + self.translation
+    }
+
+    /// Applies scale and rotation only, e.g. for directions.
+    pub fn transform_vector(&self, vector: Vec3) -> Vec3 {
+        glm::quat_rotate_vec3(&self.rotation, &vector.component_mul(&self.scale))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_vec3_eq(a: Vec3, b: Vec3) {
+        assert!((a - b).norm() < 1e-5, "{a:?} != {b:?}");
+    }
+
+    fn sample() -> Transform {
+        Transform {
+            translation: Vec3::new(1.0, -2.0, 3.0),
+            rotation: glm::quat_angle_axis(0.7, &Vec3::new(0.0, 0.0, 1.0)),
+            scale: Vec3::new(2.0, 0.5, 1.5),
+        }
+    }
+
+    #[test]
+    fn matrix_round_trip() {
+        let transform = sample();
+        let decomposed = Transform::from_matrix(transform.to_matrix());
+        assert_vec3_eq(decomposed.translation, transform.translation);
+        assert_vec3_eq(decomposed.scale, transform.scale);
+        assert!(glm::quat_dot(&decomposed.rotation, &transform.rotation).abs() > 1.0 - 1e-5);
+    }
+
+    #[test]
+    fn negative_determinant_goes_into_x_scale() {
+        let transform = Transform::from_scale(Vec3::new(-2.0, 1.0, 1.0));
+        let decomposed = Transform::from_matrix(transform.to_matrix());
+        assert_vec3_eq(decomposed.scale, Vec3::new(-2.0, 1.0, 1.0));
+        let point = Vec3::new(1.0, 2.0, 3.0);
+        assert_vec3_eq(
+            decomposed.transform_point(point),
+            transform.transform_point(point),
+        );
+    }
+
+    #[test]
+    fn scale_then_rotation_then_translation() {
+        let transform = Transform {
+            translation: Vec3::new(10.0, 0.0, 0.0),
+            rotation: glm::quat_angle_axis(crate::HALF_PI, &Vec3::new(0.0, 0.0, 1.0)),
+            scale: Vec3::new(2.0, 1.0, 1.0),
+        };
+        // (1, 0, 0) 缩放为 (2, 0, 0)，绕 z 轴旋转 90° 为 (0, 2, 0)，再平移
+        let expected = Vec3::new(10.0, 2.0, 0.0);
+        assert_vec3_eq(
+            transform.transform_point(Vec3::new(1.0, 0.0, 0.0)),
+            expected,
+        );
+        let matrix_point = transform.to_matrix() * glm::vec4(1.0, 0.0, 0.0, 1.0);
+        assert_vec3_eq(matrix_point.xyz(), expected);
+    }
+
+    #[test]
+    fn lerp_end_points_and_midpoint() {
+        let a = Transform::IDENTITY;
+        let b = sample();
+        assert_eq!(a.lerp(&b, 0.0).translation, a.translation);
+        assert_vec3_eq(a.lerp(&b, 1.0).translation, b.translation);
+        assert_vec3_eq(a.lerp(&b, 1.0).scale, b.scale);
+
+        let middle = a.lerp(&b, 0.5);
+        assert_vec3_eq(middle.translation, Vec3::new(0.5, -1.0, 1.5));
+        assert_vec3_eq(middle.scale, Vec3::new(1.5, 0.75, 1.25));
+        // 旋转按球面插值，绕 z 轴 0.7 弧度的一半
+        let expected = glm::quat_angle_axis(0.35, &Vec3::new(0.0, 0.0, 1.0));
+        assert!(glm::quat_dot(&middle.rotation, &expected).abs() > 1.0 - 1e-5);
+    }
+
+    #[test]
+    fn vectors_ignore_translation() {
+        let transform = Transform::from_translation(Vec3::new(1.0, 2.0, 3.0));
+        assert_vec3_eq(
+            transform.transform_vector(Vec3::new(0.0, 1.0, 0.0)),
+            Vec3::new(0.0, 1.0, 0.0),
+        );
+    }
+}
@@ -0,0 +1,169 @@
+use winit::{dpi::PhysicalSize, event::*, window::Window};
+
+use eureka_imgui::controls::InputState;
+use eureka_imgui::gui::{GuiContext, GuiContextDescriptor};
+use eureka_imgui::GuiTheme;
+use illuminate::vulkan::renderer::VulkanRenderer;
+
+#[cfg(feature = "gamepad")]
+use crate::gamepad;
+use crate::timestep::FixedTimestep;
+
+#[derive(Copy, Clone, Debug)]
+pub struct EngineConfig {
+    /// Seconds per `fixed_update`, `None` disables the fixed update.
+    pub fixed_timestep: Option<f32>,
+    /// Fixed updates per frame are capped, so a long frame does not stall the following ones.
+    pub max_fixed_steps_per_frame: u32,
+    /// See `Engine::set_render_scale`.
+    pub render_scale: f32,
+}
+
+impl Default for EngineConfig {
+    fn default() -> Self {
+        Self {
+            fixed_timestep: Some(1.0 / 60.0),
+            max_fixed_steps_per_frame: 8,
+            render_scale: 1.0,
+        }
+    }
+}
+
+/// 不持有事件循环的引擎，宿主程序（例如编辑器）把事件转发给它即可。
+pub struct Engine {
+    renderer: VulkanRenderer,
+    gui_context: GuiContext,
+    input_state: InputState,
+    // workaround of vulkan window resize warning https://github.com/rust-windowing/winit/issues/2094
+    is_init: bool,
+    minimized: bool,
+    fixed_timestep: Option<FixedTimestep>,
+    #[cfg(feature = "gamepad")]
+    gamepad_input: Option<gamepad::GamepadInput>,
+}
+
+impl Engine {
+    pub fn new(window: &Window, config: EngineConfig) -> Self {
+        let editor_context_desc = GuiContextDescriptor {
+            window,
+            hidpi_factor: window.scale_factor(),
+            theme: GuiTheme::Dark,
+        };
+
+        let mut gui_context = GuiContext::new(&editor_context_desc);
+        let renderer = VulkanRenderer::new(window, gui_context.get_context()).unwrap();
+        let mut engine = Self {
+            renderer,
+            gui_context,
+            input_state: InputState::default(),
+            is_init: false,
+            minimized: false,
+            fixed_timestep: config
+                .fixed_timestep
+                .map(|step| FixedTimestep::new(step, config.max_fixed_steps_per_frame)),
+            #[cfg(feature = "gamepad")]
+            gamepad_input: gamepad::GamepadInput::new(),
+        };
+        engine.set_render_scale(config.render_scale);
+        engine
+    }
+
+    /// Returns `true` when the event asks the host to exit.
+    pub fn handle_event(&mut self, window: &Window, event: &Event<()>) -> bool {
+        self.gui_context.handle_event(window, event);
+        self.input_state = self.input_state.update(event);
+
+        match event {
+            Event::WindowEvent {
+                ref event,
+                window_id,
+            } if *window_id == window.id() => {
+                if self.input(event) {
+                    return false;
+                }
+                match event {
+                    WindowEvent::CloseRequested
+                    | WindowEvent::KeyboardInput {
+                        input:
+                            KeyboardInput {
+                                state: ElementState::Pressed,
+                                virtual_keycode: Some(VirtualKeyCode::Escape),
+                                ..
+                            },
+                        ..
+                    } => return true,
+                    WindowEvent::Resized(size) => {
+                        if self.is_init {
+                            return false;
+                        }
+                        self.minimized = size.width == 0 || size.height == 0;
+                        self.resize(*size);
+                    }
+                    WindowEvent::ScaleFactorChanged { new_inner_size, .. } => {
+                        self.resize(**new_inner_size);
+                    }
+                    _ => {}
+                }
+            }
+            Event::MainEventsCleared =>
+            {
+                #[cfg(feature = "gamepad")]
+                if let Some(gamepad_input) = self.gamepad_input.as_mut() {
+                    gamepad_input.poll();
+                }
+            }
+            Event::NewEvents(cause) => {
+                self.is_init = *cause == StartCause::Init;
+                self.gui_context.update_delta_time();
+            }
+            _ => {}
+        }
+        false
+    }
+
+    pub fn render(&mut self, window: &Window, delta_time: f32) {
+        self.gui_context.prepare_frame(window);
+
+        let mut interpolation_alpha = 1.0;
+        if let Some(mut fixed_timestep) = self.fixed_timestep {
+            for _ in 0..fixed_timestep.advance(delta_time) {
+                self.fixed_update(fixed_timestep.step());
+            }
+            interpolation_alpha = fixed_timestep.alpha();
+            self.fixed_timestep = Some(fixed_timestep);
+        }
+        self.update(interpolation_alpha);
+        if !self.minimized {
+            self.renderer.render(window, &mut self.gui_context).unwrap();
+        }
+
+        profiling::finish_frame!();
+    }
+
+    pub fn resize(&mut self, new_size: PhysicalSize<u32>) {
+        if new_size.width > 0 && new_size.height > 0 {
+            self.renderer.recreate_swapchain(new_size).unwrap();
+        }
+    }
+
+    /// Renders at the window size times `scale` and upscales to the window, see
+    /// `VulkanRenderer::set_render_scale` for the accepted range.
+    pub fn set_render_scale(&mut self, scale: f32) {
+        self.renderer.set_render_scale(scale).unwrap();
+    }
+
+    pub fn exit(self) {
+        // make sure the GPU no longer uses any resource before they are freed
+        self.renderer.wait_idle().unwrap();
+    }
+
+    fn input(&mut self, _event: &WindowEvent) -> bool {
+        false
+    }
+
+    /// Runs a deterministic number of times per frame, see `EngineConfig::fixed_timestep`.
+    fn fixed_update(&mut self, _fixed_delta_time: f32) {}
+
+    /// `interpolation_alpha` blends the last two fixed update states, 1.0 without fixed updates.
+    fn update(&mut self, _interpolation_alpha: f32) {}
+}
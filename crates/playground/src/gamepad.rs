@@ -0,0 +1,152 @@
+use gilrs::{Axis, Button, EventType, Gilrs};
+
+/// Stick, trigger and button values of the connected controllers, merged into one state.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct GamepadState {
+    left_stick: [f32; 2],
+    right_stick: [f32; 2],
+    left_trigger: f32,
+    right_trigger: f32,
+    /// bit `Button as u16` is set while the button is pressed
+    buttons: u32,
+}
+
+impl GamepadState {
+    pub fn update(self, event: &EventType) -> Self {
+        let mut state = self;
+        match *event {
+            EventType::ButtonPressed(button, _) => state.set_button(button, true),
+            EventType::ButtonReleased(button, _) => state.set_button(button, false),
+            EventType::ButtonChanged(button, value, _) => state.set_button_value(button, value),
+            EventType::AxisChanged(axis, value, _) => state.set_axis(axis, value),
+            EventType::Disconnected => state = Self::default(),
+            _ => {}
+        }
+        state
+    }
+
+    fn set_button_value(&mut self, button: Button, value: f32) {
+        match button {
+            Button::LeftTrigger2 => self.left_trigger = value,
+            Button::RightTrigger2 => self.right_trigger = value,
+            _ => {}
+        }
+    }
+
+    fn set_axis(&mut self, axis: Axis, value: f32) {
+        match axis {
+            Axis::LeftStickX => self.left_stick[0] = value,
+            Axis::LeftStickY => self.left_stick[1] = value,
+            Axis::RightStickX => self.right_stick[0] = value,
+            Axis::RightStickY => self.right_stick[1] = value,
+            _ => {}
+        }
+    }
+
+    fn set_button(&mut self, button: Button, pressed: bool) {
+        if button == Button::Unknown {
+            return;
+        }
+        let bit = 1 << button as u16;
+        if pressed {
+            self.buttons |= bit;
+        } else {
+            self.buttons &= !bit;
+        }
+    }
+}
+
+// 供相机等控制逻辑读取
+#[allow(dead_code)]
+impl GamepadState {
+    pub fn left_stick(&self) -> [f32; 2] {
+        self.left_stick
+    }
+
+    pub fn right_stick(&self) -> [f32; 2] {
+        self.right_stick
+    }
+
+    pub fn left_trigger(&self) -> f32 {
+        self.left_trigger
+    }
+
+    pub fn right_trigger(&self) -> f32 {
+        self.right_trigger
+    }
+
+    pub fn is_pressed(&self, button: Button) -> bool {
+        button != Button::Unknown && self.buttons & (1 << button as u16) != 0
+    }
+}
+
+/// Polls gilrs alongside the winit events.
+pub struct GamepadInput {
+    gilrs: Gilrs,
+    state: GamepadState,
+}
+
+impl GamepadInput {
+    pub fn new() -> Option<Self> {
+        match Gilrs::new() {
+            Ok(gilrs) => {
+                for (_id, gamepad) in gilrs.gamepads() {
+                    log::info!("Gamepad connected: {}", gamepad.name());
+                }
+                Some(Self {
+                    gilrs,
+                    state: GamepadState::default(),
+                })
+            }
+            Err(e) => {
+                log::warn!("Gamepad input is unavailable. Err: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Applies all pending gilrs events and returns the current state.
+    pub fn poll(&mut self) -> GamepadState {
+        while let Some(event) = self.gilrs.next_event() {
+            self.state = self.state.update(&event.event);
+        }
+        self.state
+    }
+}
+
+#[cfg(all(test, feature = "gamepad"))]
+mod tests {
+    use super::*;
+
+    // gilrs 不允许在外部构造 `Code`，带 `Code` 的事件拆成对应的状态更新
+    #[test]
+    fn events_update_the_state() {
+        let mut state = GamepadState::default();
+        state.set_button(Button::South, true);
+        state.set_button(Button::Unknown, true);
+        state.set_button_value(Button::LeftTrigger2, 0.75);
+        state.set_axis(Axis::LeftStickX, -0.5);
+        state.set_axis(Axis::RightStickY, 0.25);
+        state = state.update(&EventType::Connected);
+
+        assert!(state.is_pressed(Button::South));
+        assert!(!state.is_pressed(Button::East));
+        assert!(!state.is_pressed(Button::Unknown));
+        assert_eq!(state.left_trigger(), 0.75);
+        assert_eq!(state.left_stick(), [-0.5, 0.0]);
+        assert_eq!(state.right_stick(), [0.0, 0.25]);
+
+        state.set_button(Button::South, false);
+        assert!(!state.is_pressed(Button::South));
+    }
+
+    #[test]
+    fn disconnect_resets_the_state() {
+        let mut state = GamepadState::default();
+        state.set_button(Button::Start, true);
+        state.set_axis(Axis::LeftStickY, 1.0);
+        let state = state.update(&EventType::Disconnected);
+        assert!(!state.is_pressed(Button::Start));
+        assert_eq!(state.left_stick(), [0.0, 0.0]);
+    }
+}
@@ -0,0 +1,79 @@
+/// Fixed-timestep accumulator, so that gameplay and physics advance by the same step at any frame rate.
+#[derive(Copy, Clone, Debug)]
+pub struct FixedTimestep {
+    step: f32,
+    accumulator: f32,
+    max_steps_per_frame: u32,
+}
+
+impl FixedTimestep {
+    pub fn new(step: f32, max_steps_per_frame: u32) -> Self {
+        assert!(step > 0.0, "fixed timestep must be positive");
+        Self {
+            step,
+            accumulator: 0.0,
+            max_steps_per_frame,
+        }
+    }
+
+    pub fn step(&self) -> f32 {
+        self.step
+    }
+
+    /// Adds the frame time and returns how many fixed steps to run this frame.
+    ///
+    /// 帧时间过长（例如断点或拖动窗口）时最多执行 `max_steps_per_frame` 步，丢弃剩余的时间，避免越追越慢。
+    pub fn advance(&mut self, delta_time: f32) -> u32 {
+        self.accumulator += delta_time.max(0.0);
+        let steps = (self.accumulator / self.step) as u32;
+        if steps > self.max_steps_per_frame {
+            self.accumulator %= self.step;
+            return self.max_steps_per_frame;
+        }
+        self.accumulator -= steps as f32 * self.step;
+        steps
+    }
+
+    /// How far the remaining time is into the next step, in `[0, 1)`, to interpolate between
+    /// the last two fixed states when rendering.
+    pub fn alpha(&self) -> f32 {
+        self.accumulator / self.step
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn advance_counts_whole_steps() {
+        let mut timestep = FixedTimestep::new(0.25, 8);
+        assert_eq!(timestep.advance(0.1), 0);
+        assert_eq!(timestep.advance(0.9), 4);
+        assert_eq!(timestep.advance(0.5), 2);
+    }
+
+    #[test]
+    fn alpha_is_the_fraction_of_the_next_step() {
+        let mut timestep = FixedTimestep::new(0.25, 8);
+        assert_eq!(timestep.advance(0.625), 2);
+        assert_eq!(timestep.alpha(), 0.5);
+        assert_eq!(timestep.advance(0.125), 1);
+        assert_eq!(timestep.alpha(), 0.0);
+    }
+
+    #[test]
+    fn long_frames_are_capped_and_the_rest_dropped() {
+        let mut timestep = FixedTimestep::new(0.25, 4);
+        assert_eq!(timestep.advance(10.125), 4);
+        assert_eq!(timestep.alpha(), 0.5);
+        assert_eq!(timestep.advance(0.0), 0);
+    }
+
+    #[test]
+    fn negative_frame_time_is_ignored() {
+        let mut timestep = FixedTimestep::new(0.25, 4);
+        assert_eq!(timestep.advance(-1.0), 0);
+        assert_eq!(timestep.alpha(), 0.0);
+    }
+}
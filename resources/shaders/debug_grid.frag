@@ -0,0 +1,14 @@
+#version 450
+
+layout (location = 0) in vec3 inColor;
+layout (location = 1) in vec3 inWorldPosition;
+layout (location = 2) flat in vec4 inCameraFade;
+
+layout (location = 0) out vec4 outColor;
+
+void main() {
+    // 线条很长，逐片段计算到相机在地面上投影的距离
+    float distance = length(inWorldPosition.xy - inCameraFade.xy);
+    float fade = 1.0 - clamp(distance / inCameraFade.w, 0.0, 1.0);
+    outColor = vec4(inColor, fade);
+}
@@ -0,0 +1,21 @@
+#version 450
+
+layout (location = 0) in vec3 inPosition;
+layout (location = 1) in vec3 inColor;
+
+layout (location = 0) out vec3 outColor;
+layout (location = 1) out vec3 outWorldPosition;
+layout (location = 2) flat out vec4 outCameraFade;
+
+layout (push_constant) uniform PushConstants {
+    mat4 viewProjection;
+    // xyz 为相机位置，w 为线条完全淡出的距离
+    vec4 cameraFade;
+} pcs;
+
+void main() {
+    gl_Position = pcs.viewProjection * vec4(inPosition, 1.0);
+    outColor = inColor;
+    outWorldPosition = inPosition;
+    outCameraFade = pcs.cameraFade;
+}
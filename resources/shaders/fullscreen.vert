@@ -0,0 +1,9 @@
+#version 450
+
+layout (location = 0) out vec2 outUV;
+
+// 覆盖整个视口的三角形，顶点由 gl_VertexIndex 生成，不需要顶点缓冲区
+void main() {
+    outUV = vec2(float((gl_VertexIndex << 1) & 2), float(gl_VertexIndex & 2));
+    gl_Position = vec4(outUV * 2.0 - 1.0, 0.0, 1.0);
+}
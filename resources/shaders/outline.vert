@@ -0,0 +1,17 @@
+#version 450
+
+layout (location = 0) in vec3 inPosition;
+
+layout (location = 0) out vec4 outColor;
+
+layout (push_constant) uniform PushConstants {
+    mat4 modelViewProjection;
+    vec4 color;
+    // 在模型空间中绕原点缩放，模板写入时为 1.0
+    float scale;
+} pcs;
+
+void main() {
+    gl_Position = pcs.modelViewProjection * vec4(inPosition * pcs.scale, 1.0);
+    outColor = pcs.color;
+}
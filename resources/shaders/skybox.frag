@@ -0,0 +1,15 @@
+#version 450
+
+layout (location = 0) in vec4 inNear;
+layout (location = 1) in vec4 inFar;
+
+layout (location = 0) out vec4 outColor;
+
+// https://github.com/gfx-rs/naga/issues/1012
+layout (set = 0, binding = 0) uniform textureCube cubemap;
+layout (set = 0, binding = 1) uniform sampler cubemapSampler;
+
+void main() {
+    vec3 direction = normalize(inFar.xyz / inFar.w - inNear.xyz / inNear.w);
+    outColor = vec4(texture(samplerCube(cubemap, cubemapSampler), direction).rgb, 1.0);
+}
@@ -0,0 +1,22 @@
+#version 450
+
+layout (location = 0) out vec4 outNear;
+layout (location = 1) out vec4 outFar;
+
+// reverse-Z 时远平面的深度为 0.0
+layout (constant_id = 0) const bool REVERSE_Z = false;
+
+layout (push_constant) uniform PushConstants {
+    mat4 inverseViewProjection;
+} pcs;
+
+// 与 fullscreen.vert 相同的全屏三角形，但位于远平面上，只在没有几何体的地方通过深度测试
+void main() {
+    vec2 ndc = vec2(float((gl_VertexIndex << 1) & 2), float(gl_VertexIndex & 2)) * 2.0 - 1.0;
+    float nearDepth = REVERSE_Z ? 1.0 : 0.0;
+    float farDepth = REVERSE_Z ? 0.0 : 1.0;
+    gl_Position = vec4(ndc, farDepth, 1.0);
+    // 齐次坐标在屏幕空间内线性插值，透视除法留到片段着色器
+    outNear = pcs.inverseViewProjection * vec4(ndc, nearDepth, 1.0);
+    outFar = pcs.inverseViewProjection * vec4(ndc, farDepth, 1.0);
+}
@@ -7,6 +7,9 @@ layout (location = 2) in vec2 inTexCoord;
 layout (location = 0) out vec3 fragColor;
 layout (location = 1) out vec2 fragTexCoord;
 
+// 深度预渲染与主通道以 EQUAL 比较深度，两条管线必须算出相同的位置
+invariant gl_Position;
+
 layout (set = 0, binding = 0) uniform UniformBufferObject {
     mat4 view;
     mat4 proj;